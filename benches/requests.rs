@@ -0,0 +1,166 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+//! Measures the allocations and time taken to serve a request, end to end,
+//! over a local connection.
+//!
+//! Run with `cargo bench --bench requests`. Allocations are counted across
+//! the whole process, so the client's share is included; compare results
+//! between revisions rather than reading them as the server's alone.
+
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  io::{Read, Write},
+  net::{SocketAddr, TcpStream},
+  sync::atomic::{AtomicUsize, Ordering},
+  time::{Duration, Instant},
+};
+
+use openssl::{
+  asn1::Asn1Time,
+  ec::{EcGroup, EcKey},
+  hash::MessageDigest,
+  nid::Nid,
+  pkey::PKey,
+  ssl::{SslConnector, SslMethod, SslVerifyMode},
+  x509::{X509NameBuilder, X509},
+};
+use windmark::response::Response;
+
+const WARMUP: usize = 100;
+const REQUESTS: usize = 2000;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+    System.dealloc(pointer, layout);
+  }
+
+  unsafe fn realloc(
+    &self,
+    pointer: *mut u8,
+    layout: Layout,
+    size: usize,
+  ) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+    System.realloc(pointer, layout, size)
+  }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// A self-signed certificate for `localhost` and its private key, as PEM.
+fn identity() -> (String, String) {
+  let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+  let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+  let mut name = X509NameBuilder::new().unwrap();
+
+  name.append_entry_by_text("CN", "localhost").unwrap();
+
+  let name = name.build();
+  let mut certificate = X509::builder().unwrap();
+
+  certificate.set_version(2).unwrap();
+  certificate.set_subject_name(&name).unwrap();
+  certificate.set_issuer_name(&name).unwrap();
+  certificate.set_pubkey(&key).unwrap();
+  certificate
+    .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+    .unwrap();
+  certificate
+    .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+    .unwrap();
+  certificate.sign(&key, MessageDigest::sha256()).unwrap();
+
+  (
+    String::from_utf8(certificate.build().to_pem().unwrap()).unwrap(),
+    String::from_utf8(key.private_key_to_pem_pkcs8().unwrap()).unwrap(),
+  )
+}
+
+fn request(connector: &SslConnector, address: SocketAddr) {
+  let mut stream = connector
+    .configure()
+    .unwrap()
+    .verify_hostname(false)
+    .connect("localhost", TcpStream::connect(address).unwrap())
+    .unwrap();
+  let mut response = Vec::new();
+
+  stream.write_all(b"gemini://localhost/\r\n").unwrap();
+  // The server may close the connection without a TLS close_notify.
+  let _ = stream.read_to_end(&mut response);
+
+  assert!(response.starts_with(b"20 "));
+}
+
+fn main() {
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  let (certificate, private_key) = identity();
+  let server = runtime.block_on(async {
+    windmark::router::Router::new()
+      .set_certificate(certificate)
+      .set_private_key(private_key)
+      .add_listener("127.0.0.1:0")
+      .mount("/", |_| Response::success("Hello, World!"))
+      .start()
+      .unwrap()
+  });
+  let address = server.local_addr();
+  let mut connector = SslConnector::builder(SslMethod::tls_client()).unwrap();
+
+  connector.set_verify(SslVerifyMode::NONE);
+
+  let connector = connector.build();
+
+  for _ in 0..WARMUP {
+    request(&connector, address);
+  }
+
+  let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+  let started_at = Instant::now();
+
+  for _ in 0..REQUESTS {
+    request(&connector, address);
+  }
+
+  let elapsed = started_at.elapsed();
+  let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+  #[allow(clippy::cast_precision_loss)]
+  let per_request = allocations as f64 / REQUESTS as f64;
+
+  println!(
+    "{REQUESTS} requests: {per_request:.1} allocations and {:?} per request",
+    elapsed / u32::try_from(REQUESTS).unwrap_or(u32::MAX)
+  );
+
+  runtime.block_on(async {
+    server.shutdown();
+    let _ = tokio::time::timeout(Duration::from_secs(5), server.wait()).await;
+  });
+}
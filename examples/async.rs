@@ -18,7 +18,7 @@
 //! `cargo run --example async --features response-macros`
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   let mut router = windmark::router::Router::new();
   #[cfg(feature = "tokio")]
   let async_clicks = std::sync::Arc::new(tokio::sync::Mutex::new(0));
@@ -17,7 +17,7 @@
 
 //! `cargo run --example async_stateful_module --features response-macros`
 
-use windmark::{context::HookContext, router::Router};
+use windmark::{context::HookContext, module::ModuleResult, router::Router};
 
 #[derive(Default)]
 struct Clicker {
@@ -30,7 +30,7 @@ impl windmark::module::AsyncModule for Clicker {
     println!("module 'clicker' has been attached!");
   }
 
-  async fn on_pre_route(&mut self, context: HookContext) {
+  async fn on_pre_route(&mut self, context: HookContext) -> ModuleResult {
     *self.clicks.lock().unwrap() += 1;
 
     println!(
@@ -38,20 +38,24 @@ impl windmark::module::AsyncModule for Clicker {
       context.url.path(),
       self.clicks.lock().unwrap()
     );
+
+    Ok(())
   }
 
-  async fn on_post_route(&mut self, context: HookContext) {
+  async fn on_post_route(&mut self, context: HookContext) -> ModuleResult {
     println!(
       "module 'clicker' clicker has been called after the route '{}' with {} \
        clicks!",
       context.url.path(),
       self.clicks.lock().unwrap()
     );
+
+    Ok(())
   }
 }
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   let mut router = Router::new();
 
   router.set_private_key_file("windmark_private.pem");
@@ -21,7 +21,7 @@
 //! enabled.
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   let mut router = windmark::router::Router::new();
 
   router.set_private_key_file("windmark_private.pem");
@@ -20,7 +20,7 @@
 use windmark::context::HookContext;
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   windmark::router::Router::new()
     .set_private_key_file("windmark_private.pem")
     .set_certificate_file("windmark_public.pem")
@@ -37,8 +37,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         content.content = content.content.replace("Hello", "Hi");
 
         println!(
-          "closed connection from {}",
-          context.peer_address.unwrap().ip()
+          "closed connection from {} after {:?} with status {}",
+          context.peer_address.unwrap().ip(),
+          context.elapsed.unwrap_or_default(),
+          context.status.unwrap_or_default(),
         )
       },
     )
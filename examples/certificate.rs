@@ -20,7 +20,7 @@
 use windmark::response::Response;
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   windmark::router::Router::new()
     .set_private_key_file("windmark_private.pem")
     .set_certificate_file("windmark_public.pem")
@@ -47,6 +47,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
       "/invalid",
       windmark::certificate_not_valid!("Your certificate is invalid."),
     )
+    .mount(
+      "/forbidden",
+      windmark::certificate_not_authorised!("You may not see this."),
+    )
     .run()
     .await
 }
@@ -18,7 +18,7 @@
 //! `cargo run --example default_logger --features logger,response-macros`
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   let mut router = windmark::router::Router::new();
 
   router.set_private_key_file("windmark_private.pem");
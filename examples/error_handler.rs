@@ -20,19 +20,22 @@
 use windmark::response::Response;
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   let mut error_count = 0;
 
   windmark::router::Router::new()
     .set_private_key_file("windmark_private.pem")
     .set_certificate_file("windmark_public.pem")
-    .set_error_handler(move |_| {
+    .set_error_handler(move |context| {
       error_count += 1;
 
-      println!("{} errors so far", error_count);
+      println!("{} errors so far, latest: {}", error_count, context.kind);
 
       Response::permanent_failure("e")
     })
+    .set_not_found_handler(|context| {
+      Response::not_found(format!("{} does not exist!", context.url.path()))
+    })
     .mount("/error", |_| {
       let nothing = None::<String>;
 
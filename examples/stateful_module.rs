@@ -50,7 +50,7 @@ impl windmark::module::Module for Clicker {
 }
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   let mut router = Router::new();
 
   router.set_private_key_file("windmark_private.pem");
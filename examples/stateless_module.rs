@@ -26,7 +26,7 @@ fn smiley(_context: windmark::context::RouteContext) -> Response {
 fn emojis(router: &mut Router) { router.mount("/smiley", smiley); }
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   Router::new()
     .set_private_key_file("windmark_private.pem")
     .set_certificate_file("windmark_public.pem")
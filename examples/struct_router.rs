@@ -32,7 +32,7 @@ impl Router {
 }
 
 #[windmark::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), windmark::Error> {
   {
     let mut router = Router::new();
 
@@ -0,0 +1,54 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! `cargo run --example subrequest`
+
+use windmark::{context::RouteContext, response::Response};
+
+#[windmark::main]
+async fn main() -> Result<(), windmark::Error> {
+  windmark::router::Router::new()
+    .set_private_key_file("windmark_private.pem")
+    .set_certificate_file("windmark_public.pem")
+    .mount("/fragment/navigation", |_| {
+      Response::success("=> / Home\n=> /about About")
+    })
+    .mount("/", |context: RouteContext| {
+      async move {
+        let navigation = context
+          .subrequest("/fragment/navigation")
+          .await
+          .map(|response| response.content)
+          .unwrap_or_default();
+
+        Response::success(format!("# Home\n\n{navigation}"))
+      }
+    })
+    .mount("/about", |context: RouteContext| {
+      async move {
+        let navigation = context
+          .subrequest("/fragment/navigation")
+          .await
+          .map(|response| response.content)
+          .unwrap_or_default();
+
+        Response::success(format!("# About\n\n{navigation}"))
+      }
+    })
+    .run()
+    .await
+}
@@ -90,7 +90,7 @@ pub fn fields(arguments: TokenStream, item: syn::ItemStruct) -> TokenStream {
     impl #router_identifier {
       #new_methods
 
-      pub async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+      pub async fn run(&mut self) -> Result<(), ::windmark::Error> {
         self.router.run().await
       }
 
@@ -0,0 +1,293 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! An outbound Gemini client, for routes which aggregate or proxy other
+//! capsules
+
+mod error;
+mod known_hosts;
+mod link_checker;
+mod response;
+
+use std::time::Duration;
+
+#[cfg(feature = "async-std")]
+use async_std::io::{ReadExt, WriteExt};
+use openssl::{
+  hash::MessageDigest,
+  pkey::{PKey, Private},
+  ssl::{SslConnector, SslMethod, SslVerifyMode},
+  x509::X509,
+};
+#[cfg(feature = "smol")]
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub use self::{
+  error::Error,
+  known_hosts::KnownHosts,
+  link_checker::{BrokenLink, LinkChecker, LinkReport},
+  response::Response,
+};
+use crate::{router::runtime, utilities::hex};
+
+/// A Gemini client which trusts servers' certificates on first use.
+///
+/// Every clone shares the same [`KnownHosts`], so a client can be created
+/// once and moved into each route which needs it.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # async fn example() -> Result<(), windmark::client::Error> {
+/// let client = windmark::client::Client::new();
+/// let response = client.get("gemini://geminiprotocol.net/").await?;
+///
+/// if response.is_success() {
+///   println!("{}", response.text());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Client {
+  known_hosts:       KnownHosts,
+  identity:          Option<(X509, PKey<Private>)>,
+  timeout:           Option<Duration>,
+  max_redirects:     usize,
+  max_response_size: usize,
+}
+
+impl Default for Client {
+  fn default() -> Self {
+    Self {
+      known_hosts:       KnownHosts::new(),
+      identity:          None,
+      timeout:           Some(Duration::from_secs(30)),
+      max_redirects:     5,
+      max_response_size: 16 * 1024 * 1024,
+    }
+  }
+}
+
+impl Client {
+  /// Create a client with in-memory known hosts, no client certificate, a
+  /// 30 second timeout, which follows up to 5 redirects and accepts
+  /// responses of up to 16 MiB.
+  #[must_use]
+  pub fn new() -> Self { Self::default() }
+
+  /// Set the hosts whose certificates are trusted.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::client::{Client, KnownHosts};
+  ///
+  /// Client::new().set_known_hosts(KnownHosts::new());
+  /// ```
+  pub fn set_known_hosts(&mut self, known_hosts: KnownHosts) -> &mut Self {
+    self.known_hosts = known_hosts;
+
+    self
+  }
+
+  /// The hosts whose certificates are trusted.
+  #[must_use]
+  pub const fn known_hosts(&self) -> &KnownHosts { &self.known_hosts }
+
+  /// Present a client certificate to the server of each URL requested with
+  /// [`Client::get`], but not to other hosts it redirects to.
+  pub fn set_identity(
+    &mut self,
+    certificate: X509,
+    private_key: PKey<Private>,
+  ) -> &mut Self {
+    self.identity = Some((certificate, private_key));
+
+    self
+  }
+
+  /// Present the client certificate and private key in the given PEM files,
+  /// as with [`Client::set_identity`].
+  ///
+  /// # Errors
+  ///
+  /// if either file could not be read or is not valid PEM.
+  pub fn set_identity_files(
+    &mut self,
+    certificate: impl AsRef<std::path::Path>,
+    private_key: impl AsRef<std::path::Path>,
+  ) -> Result<&mut Self, Error> {
+    let certificate = X509::from_pem(&std::fs::read(certificate)?)?;
+    let private_key = PKey::private_key_from_pem(&std::fs::read(private_key)?)?;
+
+    Ok(self.set_identity(certificate, private_key))
+  }
+
+  /// Set how long connecting, the TLS handshake, and receiving a response may
+  /// each take; `None` waits indefinitely.
+  pub fn set_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+    self.timeout = timeout;
+
+    self
+  }
+
+  /// Set how many redirects are followed before a request fails; `0` returns
+  /// redirects as responses.
+  pub fn set_max_redirects(&mut self, max_redirects: usize) -> &mut Self {
+    self.max_redirects = max_redirects;
+
+    self
+  }
+
+  /// Set the largest response, header included, in bytes, which is read
+  /// before a request fails.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::client::Client::new().set_max_response_size(1024 * 1024);
+  /// ```
+  pub fn set_max_response_size(
+    &mut self,
+    max_response_size: usize,
+  ) -> &mut Self {
+    self.max_response_size = max_response_size;
+
+    self
+  }
+
+  /// Request `url`, following redirects.
+  ///
+  /// # Errors
+  ///
+  /// if `url` is not a valid Gemini URL, the server could not be reached,
+  /// its certificate differs from the one trusted for it, its response is
+  /// invalid or too large, or it redirected too many times.
+  pub async fn get(&self, url: impl AsRef<str>) -> Result<Response, Error> {
+    let mut url = url::Url::parse(url.as_ref())?;
+    // Gemini URLs have opaque origins, which never compare equal.
+    let origin = (url.host_str().map(str::to_string), url.port());
+    let mut redirects = 0;
+
+    loop {
+      let identify = (url.host_str().map(str::to_string), url.port()) == origin;
+      let response = self.request(url, identify).await?;
+
+      if response.status / 10 != 3 || self.max_redirects == 0 {
+        return Ok(response);
+      }
+
+      if redirects == self.max_redirects {
+        return Err(Error::TooManyRedirects);
+      }
+
+      redirects += 1;
+      url = response.url.join(&response.meta)?;
+    }
+  }
+
+  /// Request `url` without following redirects, presenting the client
+  /// certificate if `identify`.
+  async fn request(
+    &self,
+    url: url::Url,
+    identify: bool,
+  ) -> Result<Response, Error> {
+    let (Some(host), "gemini") = (url.host_str(), url.scheme()) else {
+      return Err(Error::UnsupportedUrl(url.to_string()));
+    };
+    let port = url.port().unwrap_or(1965);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let tcp =
+      runtime::timeout(self.timeout, runtime::TcpStream::connect((host, port)))
+        .await??;
+    let ssl = self
+      .connector(identify)?
+      .configure()?
+      .verify_hostname(false)
+      .use_server_name_indication(url.domain().is_some())
+      .into_ssl(host)?;
+    #[cfg(feature = "tokio")]
+    let mut stream = tokio_openssl::SslStream::new(ssl, tcp)?;
+    #[cfg(any(feature = "async-std", feature = "smol"))]
+    let mut stream = async_std_openssl::SslStream::new(ssl, tcp)?;
+
+    runtime::timeout(self.timeout, std::pin::Pin::new(&mut stream).connect())
+      .await?
+      .map_err(Error::Handshake)?;
+
+    let fingerprint = stream
+      .ssl()
+      .peer_certificate()
+      .ok_or(Error::InvalidResponse)?
+      .digest(MessageDigest::sha256())?;
+    let known_host = if port == 1965 {
+      host.to_string()
+    } else {
+      format!("{host}:{port}")
+    };
+
+    self
+      .known_hosts
+      .verify(&known_host, &hex(&fingerprint))
+      .await?;
+    stream.write_all(format!("{url}\r\n").as_bytes()).await?;
+
+    let raw = runtime::timeout(self.timeout, async {
+      let mut raw = Vec::new();
+      let mut chunk = [0u8; 4096];
+
+      loop {
+        match stream.read(&mut chunk).await {
+          Ok(0) => break,
+          Ok(size) if raw.len() + size > self.max_response_size =>
+            return Err(Error::ResponseTooLarge),
+          Ok(size) => raw.extend_from_slice(&chunk[..size]),
+          // Servers often close the connection without a TLS close_notify,
+          // which is only an error if nothing was received.
+          Err(_) if !raw.is_empty() => break,
+          Err(e) => return Err(Error::Io(e)),
+        }
+      }
+
+      Ok(raw)
+    })
+    .await??;
+
+    Response::parse(url, &raw).ok_or(Error::InvalidResponse)
+  }
+
+  fn connector(&self, identify: bool) -> Result<SslConnector, Error> {
+    let mut builder = SslConnector::builder(SslMethod::tls_client())?;
+
+    // Certificates are verified against the known hosts instead.
+    builder.set_verify(SslVerifyMode::NONE);
+
+    if let Some((certificate, private_key)) =
+      self.identity.as_ref().filter(|_| identify)
+    {
+      builder.set_certificate(certificate)?;
+      builder.set_private_key(private_key)?;
+      builder.check_private_key()?;
+    }
+
+    Ok(builder.build())
+  }
+}
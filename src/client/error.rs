@@ -0,0 +1,96 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fmt, io};
+
+/// An error which prevents a [`Client`](super::Client) request from
+/// completing.
+#[derive(Debug)]
+pub enum Error {
+  /// The URL could not be parsed.
+  Url(url::ParseError),
+  /// The URL does not use the `gemini` scheme, or has no host.
+  UnsupportedUrl(String),
+  /// The connection could not be made, or failed.
+  Io(io::Error),
+  /// The client's TLS settings could not be applied.
+  Tls(openssl::error::ErrorStack),
+  /// The TLS handshake with the server failed.
+  Handshake(openssl::ssl::Error),
+  /// The server presented a different certificate than the one trusted for
+  /// its host when it was first visited.
+  CertificateChanged {
+    host:        String,
+    /// The SHA-256 fingerprint of the newly presented certificate.
+    fingerprint: String,
+  },
+  /// The server's response did not begin with a valid header.
+  InvalidResponse,
+  /// More redirects were followed than the client allows.
+  TooManyRedirects,
+  /// The response is larger than the client allows.
+  ResponseTooLarge,
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Url(e) => write!(f, "invalid url: {e}"),
+      Self::UnsupportedUrl(url) =>
+        write!(f, "\"{url}\" is not a gemini url with a host"),
+      Self::Io(e) => write!(f, "{e}"),
+      Self::Tls(e) => write!(f, "could not configure TLS: {e}"),
+      Self::Handshake(e) => write!(f, "tls handshake failed: {e}"),
+      Self::CertificateChanged {
+        host,
+        fingerprint,
+      } =>
+        write!(
+          f,
+          "the certificate of {host} has changed since it was trusted; it is \
+           now {fingerprint}"
+        ),
+      Self::InvalidResponse => write!(f, "the response header is invalid"),
+      Self::TooManyRedirects => write!(f, "too many redirects"),
+      Self::ResponseTooLarge => write!(f, "the response is too large"),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Url(e) => Some(e),
+      Self::Io(e) => Some(e),
+      Self::Tls(e) => Some(e),
+      Self::Handshake(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<url::ParseError> for Error {
+  fn from(e: url::ParseError) -> Self { Self::Url(e) }
+}
+
+impl From<io::Error> for Error {
+  fn from(e: io::Error) -> Self { Self::Io(e) }
+}
+
+impl From<openssl::error::ErrorStack> for Error {
+  fn from(e: openssl::error::ErrorStack) -> Self { Self::Tls(e) }
+}
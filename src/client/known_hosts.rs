@@ -0,0 +1,212 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+#![allow(clippy::significant_drop_tightening)]
+
+use std::{
+  collections::BTreeMap,
+  fmt::Write,
+  io,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+};
+
+use crate::router::runtime;
+
+#[derive(Default)]
+struct Inner {
+  hosts: BTreeMap<String, String>,
+  path:  Option<PathBuf>,
+}
+
+/// The certificate fingerprints trusted for each host.
+///
+/// Hosts are trusted on first use: the first certificate a host presents is
+/// trusted, and a different one is rejected until the host is forgotten.
+///
+/// Every clone shares the same hosts.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::client::KnownHosts;
+///
+/// let known_hosts = KnownHosts::new();
+///
+/// known_hosts.trust("fuwn.me", "0a1b2c");
+///
+/// assert_eq!(
+///   known_hosts.fingerprint("fuwn.me").as_deref(),
+///   Some("0a1b2c")
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct KnownHosts {
+  inner:  Arc<Mutex<Inner>>,
+  /// Held while the file is written, so that saves land in order without
+  /// holding the hosts' lock.
+  saving: Arc<Mutex<()>>,
+}
+
+impl KnownHosts {
+  /// Create an empty, in-memory set of known hosts.
+  #[must_use]
+  pub fn new() -> Self { Self::default() }
+
+  /// Load known hosts from `path`, which is replaced whole whenever a host is
+  /// trusted or forgotten. A missing file is treated as empty.
+  ///
+  /// The file holds one host and the hexadecimal SHA-256 fingerprint of its
+  /// certificate per line, separated by a space.
+  ///
+  /// # Errors
+  ///
+  /// if the file exists but could not be read.
+  pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+    let path = path.as_ref();
+    let hosts = match std::fs::read_to_string(path) {
+      Ok(content) =>
+        content
+          .lines()
+          .filter_map(|line| line.trim().split_once(' '))
+          .map(|(host, fingerprint)| {
+            (host.to_string(), fingerprint.trim().to_string())
+          })
+          .collect(),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+      Err(e) => return Err(e),
+    };
+
+    Ok(Self {
+      inner:  Arc::new(Mutex::new(Inner {
+        hosts,
+        path: Some(path.to_path_buf()),
+      })),
+      saving: Arc::default(),
+    })
+  }
+
+  /// The fingerprint trusted for `host`, if any.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the known hosts' lock is poisoned.
+  #[must_use]
+  pub fn fingerprint(&self, host: &str) -> Option<String> {
+    self.inner.lock().unwrap().hosts.get(host).cloned()
+  }
+
+  /// Trust `fingerprint` for `host`, replacing any fingerprint trusted before.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the known hosts' lock is poisoned.
+  pub fn trust(&self, host: &str, fingerprint: &str) {
+    self
+      .inner
+      .lock()
+      .unwrap()
+      .hosts
+      .insert(host.to_string(), fingerprint.to_string());
+    self.save();
+  }
+
+  /// Stop trusting the certificate of `host`, so that the next one it presents
+  /// is trusted. Returns whether the host was known.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the known hosts' lock is poisoned.
+  #[allow(clippy::must_use_candidate)]
+  pub fn forget(&self, host: &str) -> bool {
+    let known = self.inner.lock().unwrap().hosts.remove(host).is_some();
+
+    if known {
+      self.save();
+    }
+
+    known
+  }
+
+  /// Check `fingerprint` against the one trusted for `host`, trusting it if
+  /// the host has not been seen before.
+  ///
+  /// The file, if any, is written off the async runtime.
+  pub(super) async fn verify(
+    &self,
+    host: &str,
+    fingerprint: &str,
+  ) -> Result<(), super::Error> {
+    {
+      let mut inner = self.inner.lock().unwrap();
+
+      match inner.hosts.get(host) {
+        Some(trusted) if trusted == fingerprint => return Ok(()),
+        Some(_) =>
+          return Err(super::Error::CertificateChanged {
+            host:        host.to_string(),
+            fingerprint: fingerprint.to_string(),
+          }),
+        None => {
+          inner
+            .hosts
+            .insert(host.to_string(), fingerprint.to_string());
+        }
+      }
+    }
+
+    let known_hosts = self.clone();
+
+    runtime::unblock(move || known_hosts.save()).await;
+
+    Ok(())
+  }
+
+  /// Write the hosts to their file, if any, without holding their lock while
+  /// writing.
+  fn save(&self) {
+    let Ok(_saving) = self.saving.lock() else {
+      return;
+    };
+    let Some((path, content)) = self.snapshot() else {
+      return;
+    };
+
+    if let Err(e) =
+      crate::utilities::write_atomically(&path, content.as_bytes())
+    {
+      warn!("could not save known hosts to {}: {e}", path.display());
+    }
+  }
+
+  /// The file the hosts are saved to, if any, and its content.
+  fn snapshot(&self) -> Option<(PathBuf, String)> {
+    let inner = self.inner.lock().ok()?;
+    let path = inner.path.clone()?;
+    let content = inner.hosts.iter().fold(
+      String::new(),
+      |mut content, (host, fingerprint)| {
+        // Writing to a `String` can not fail.
+        let _ = writeln!(content, "{host} {fingerprint}");
+
+        content
+      },
+    );
+
+    Some((path, content))
+  }
+}
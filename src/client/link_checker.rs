@@ -0,0 +1,205 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::{HashSet, VecDeque};
+
+use url::Url;
+
+use super::{Client, Error};
+use crate::gemtext::{self, Line};
+
+/// A link which could not be followed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BrokenLink {
+  /// The page the link was found on.
+  pub source: Url,
+  pub target: Url,
+  /// The failure status and meta of the response, or why no response was
+  /// received.
+  pub reason: String,
+}
+
+/// The outcome of a [`LinkChecker`] crawl.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinkReport {
+  /// The number of URLs requested.
+  pub checked: usize,
+  pub broken:  Vec<BrokenLink>,
+  /// Gemini links which were not requested, either because they are external
+  /// and external links are not checked, or because the page limit was
+  /// reached.
+  pub skipped: Vec<Url>,
+}
+
+impl LinkReport {
+  /// Whether every checked link could be followed.
+  #[must_use]
+  pub fn is_ok(&self) -> bool { self.broken.is_empty() }
+}
+
+/// Crawls a capsule, or a subtree of one, and reports its broken links.
+///
+/// Gemtext pages under the starting URL are crawled and their links checked.
+/// Links which leave the subtree are requested but not crawled, and links to
+/// other protocols are ignored. A link is broken if it can not be requested,
+/// or responds with a temporary or permanent failure status (`4x` or `5x`).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # async fn example() -> Result<(), windmark::client::Error> {
+/// use windmark::client::{Client, LinkChecker};
+///
+/// let report = LinkChecker::new(Client::new())
+///   .check("gemini://localhost/gemlog/")
+///   .await?;
+///
+/// for link in &report.broken {
+///   eprintln!("{} -> {}: {}", link.source, link.target, link.reason);
+/// }
+///
+/// assert!(report.is_ok());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct LinkChecker {
+  client:         Client,
+  check_external: bool,
+  max_pages:      usize,
+}
+
+impl LinkChecker {
+  /// Create a link checker which checks external links and requests at most
+  /// 1000 URLs.
+  #[must_use]
+  pub const fn new(client: Client) -> Self {
+    Self {
+      client,
+      check_external: true,
+      max_pages: 1000,
+    }
+  }
+
+  /// Set whether links outside of the crawled subtree are requested.
+  pub fn set_check_external(&mut self, check_external: bool) -> &mut Self {
+    self.check_external = check_external;
+
+    self
+  }
+
+  /// Set the maximum number of URLs requested during a crawl.
+  pub fn set_max_pages(&mut self, max_pages: usize) -> &mut Self {
+    self.max_pages = max_pages;
+
+    self
+  }
+
+  /// Crawl the subtree rooted at `url`.
+  ///
+  /// # Errors
+  ///
+  /// if `url` is not a valid URL; broken links are reported, not returned as
+  /// errors.
+  pub async fn check(&self, url: impl AsRef<str>) -> Result<LinkReport, Error> {
+    let mut report = LinkReport::default();
+    let root = Url::parse(url.as_ref())?;
+    let mut seen = HashSet::from([without_fragment(&root)]);
+    let mut queue = VecDeque::from([(root.clone(), root.clone())]);
+
+    while let Some((source, target)) = queue.pop_front() {
+      let internal = is_within(&root, &target);
+
+      if report.checked == self.max_pages || !internal && !self.check_external {
+        report.skipped.push(target);
+
+        continue;
+      }
+
+      report.checked += 1;
+
+      let response = match self.client.get(target.as_str()).await {
+        Ok(response) => response,
+        Err(e) => {
+          report.broken.push(BrokenLink {
+            source,
+            target,
+            reason: e.to_string(),
+          });
+
+          continue;
+        }
+      };
+
+      if (40..60).contains(&response.status) {
+        report.broken.push(BrokenLink {
+          source,
+          target,
+          reason: format!("{} {}", response.status, response.meta),
+        });
+
+        continue;
+      }
+
+      if !internal
+        || !is_within(&root, &response.url)
+        || response.mime() != Some("text/gemini")
+      {
+        continue;
+      }
+
+      for line in gemtext::parse(&response.text()) {
+        let Line::Link {
+          url, ..
+        } = line
+        else {
+          continue;
+        };
+        let Ok(link) = response.url.join(&url) else {
+          report.broken.push(BrokenLink {
+            source: response.url.clone(),
+            target: response.url.clone(),
+            reason: format!("invalid link \"{url}\""),
+          });
+
+          continue;
+        };
+
+        if link.scheme() == "gemini" && seen.insert(without_fragment(&link)) {
+          queue.push_back((response.url.clone(), link));
+        }
+      }
+    }
+
+    Ok(report)
+  }
+}
+
+/// Whether `url` is on the same host as `root`, at or below its path.
+fn is_within(root: &Url, url: &Url) -> bool {
+  url.host_str() == root.host_str()
+    && url.port().unwrap_or(1965) == root.port().unwrap_or(1965)
+    && url.path().starts_with(root.path())
+}
+
+fn without_fragment(url: &Url) -> Url {
+  let mut url = url.clone();
+
+  url.set_fragment(None);
+
+  url
+}
@@ -0,0 +1,75 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::borrow::Cow;
+
+/// A response received by a [`Client`](super::Client).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+  pub status: i32,
+  /// The text following the status: the MIME type of a successful response,
+  /// the prompt of an input request, the target of a redirect, or an error
+  /// message.
+  pub meta:   String,
+  /// The body of a successful response; empty otherwise.
+  pub body:   Vec<u8>,
+  /// The URL the response was received from, after any redirects.
+  pub url:    url::Url,
+}
+
+impl Response {
+  /// Parse a raw response to a request for `url`.
+  pub(super) fn parse(url: url::Url, raw: &[u8]) -> Option<Self> {
+    let end = raw.iter().position(|byte| *byte == b'\n')?;
+    let header = std::str::from_utf8(&raw[..end])
+      .ok()?
+      .trim_end_matches('\r');
+    let (status, meta) = header.split_once(' ').unwrap_or((header, ""));
+
+    if status.len() != 2 {
+      return None;
+    }
+
+    Some(Self {
+      status: status.parse().ok()?,
+      meta: meta.trim().to_string(),
+      body: raw[end + 1..].to_vec(),
+      url,
+    })
+  }
+
+  #[must_use]
+  pub const fn is_success(&self) -> bool { self.status / 10 == 2 }
+
+  /// The MIME type of a successful response, without its parameters.
+  #[must_use]
+  pub fn mime(&self) -> Option<&str> {
+    self.is_success().then(|| {
+      let mime = self.meta.split(';').next().unwrap_or_default().trim();
+
+      if mime.is_empty() {
+        "text/gemini"
+      } else {
+        mime
+      }
+    })
+  }
+
+  /// The body decoded as UTF-8, replacing invalid sequences.
+  #[must_use]
+  pub fn text(&self) -> Cow<'_, str> { String::from_utf8_lossy(&self.body) }
+}
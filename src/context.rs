@@ -17,10 +17,26 @@
 
 #![allow(clippy::module_name_repetitions)]
 
+mod closed;
+mod connection;
 mod error;
+mod extensions;
 mod hook;
+mod not_found;
+mod parameters;
+mod query;
+mod raw_request;
 mod route;
+mod tls;
 
-pub use error::ErrorContext;
+pub(crate) use closed::Closed;
+pub use connection::ConnectionContext;
+pub use error::{ErrorContext, ErrorKind, Rejection};
+pub use extensions::Extensions;
 pub use hook::HookContext;
-pub use route::RouteContext;
+pub use not_found::NotFoundContext;
+pub use parameters::Parameters;
+pub use query::Query;
+pub use raw_request::RawRequest;
+pub use route::{PendingResponse, RouteContext};
+pub use tls::TlsInfo;
@@ -0,0 +1,76 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  future::poll_fn,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+    Mutex,
+  },
+  task::{Poll, Waker},
+};
+
+/// Whether the client of a request has disconnected, shared by every clone of
+/// its context.
+#[derive(Clone, Default)]
+pub struct Closed {
+  inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+  closed: AtomicBool,
+  wakers: Mutex<Vec<Waker>>,
+}
+
+impl Closed {
+  pub fn close(&self) {
+    if !self.inner.closed.swap(true, Ordering::SeqCst) {
+      if let Ok(mut wakers) = self.inner.wakers.lock() {
+        for waker in wakers.drain(..) {
+          waker.wake();
+        }
+      }
+    }
+  }
+
+  pub fn is_closed(&self) -> bool { self.inner.closed.load(Ordering::SeqCst) }
+
+  /// Resolve once the client has disconnected.
+  pub async fn closed(&self) {
+    poll_fn(|context| {
+      if self.is_closed() {
+        return Poll::Ready(());
+      }
+
+      if let Ok(mut wakers) = self.inner.wakers.lock() {
+        if !wakers.iter().any(|waker| waker.will_wake(context.waker())) {
+          wakers.push(context.waker().clone());
+        }
+      }
+
+      // The client may have disconnected while the waker was being registered.
+      if self.is_closed() {
+        Poll::Ready(())
+      } else {
+        Poll::Pending
+      }
+    })
+    .await;
+  }
+}
@@ -15,15 +15,114 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::sync::Arc;
+
 use openssl::x509::X509;
 use url::Url;
 
+/// The cause of an error which invoked the error handler.
+#[derive(Clone, Debug)]
+pub enum ErrorKind {
+  /// No route matched the requested path and no not-found handler was set.
+  RouteMiss,
+  /// A route handler failed with an error.
+  Handler(Arc<dyn std::error::Error + Send + Sync>),
+  /// A route handler panicked; carries the panic message.
+  Panic(String),
+  /// A route handler did not respond within its deadline; carries the
+  /// deadline.
+  Timeout(std::time::Duration),
+  /// The request violated the specification; carries the reason.
+  BadRequest(Rejection),
+  /// A route handler returned an early failure; only passed to the failure
+  /// handler set for its status.
+  Failure(crate::response::Failure),
+}
+
+/// Why a request was rejected before it could be routed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rejection {
+  /// The request line was empty, or the client sent nothing before closing
+  /// the connection.
+  Empty,
+  /// The request was longer than 1024 bytes.
+  Oversized,
+  /// The request was not valid UTF-8.
+  InvalidUtf8,
+  /// The request was not an absolute URL.
+  InvalidUrl(url::ParseError),
+  /// The URL's scheme was not `gemini`; carries the scheme.
+  SchemeMismatch(String),
+  /// The URL had no host.
+  MissingHost,
+  /// The URL's host is not served by this server; carries the host.
+  HostMismatch(Option<String>),
+  /// The URL contained a username or password.
+  Userinfo,
+  /// The URL contained a fragment.
+  Fragment,
+}
+
+impl Rejection {
+  /// A short, stable name for the reason, e.g., for labelling metrics.
+  #[must_use]
+  pub const fn code(&self) -> &'static str {
+    match self {
+      Self::Empty => "empty-request",
+      Self::Oversized => "oversized",
+      Self::InvalidUtf8 => "invalid-utf8",
+      Self::InvalidUrl(_) => "invalid-url",
+      Self::SchemeMismatch(_) => "scheme-mismatch",
+      Self::MissingHost => "missing-host",
+      Self::HostMismatch(_) => "host-mismatch",
+      Self::Userinfo => "userinfo",
+      Self::Fragment => "fragment",
+    }
+  }
+}
+
+impl std::fmt::Display for Rejection {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Empty => write!(f, "empty request"),
+      Self::Oversized => write!(f, "the request is longer than 1024 bytes"),
+      Self::InvalidUtf8 => write!(f, "the request is not valid UTF-8"),
+      Self::InvalidUrl(e) => write!(f, "{e}"),
+      Self::SchemeMismatch(scheme) => write!(f, "unsupported scheme: {scheme}"),
+      Self::MissingHost => write!(f, "missing host"),
+      Self::HostMismatch(Some(host)) => write!(f, "host not served: {host}"),
+      Self::HostMismatch(None) => write!(f, "host not served"),
+      Self::Userinfo => write!(f, "userinfo is not allowed"),
+      Self::Fragment => write!(f, "fragments are not allowed"),
+    }
+  }
+}
+
+impl std::fmt::Display for ErrorKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::RouteMiss => write!(f, "no route matched"),
+      Self::Handler(error) => write!(f, "handler error: {error}"),
+      Self::Panic(message) => write!(f, "handler panicked: {message}"),
+      Self::Timeout(timeout) =>
+        write!(f, "handler timed out after {timeout:?}"),
+      Self::BadRequest(reason) => write!(f, "bad request: {reason}"),
+      Self::Failure(failure) => write!(f, "handler failed: {failure}"),
+    }
+  }
+}
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone)]
 pub struct ErrorContext {
   pub peer_address: Option<std::net::SocketAddr>,
+  /// The URL requested, or `gemini://invalid/` for a bad request whose URL
+  /// could not be parsed; see [`Rejection`].
   pub url:          Url,
+  pub request_id:   String,
   pub certificate:  Option<X509>,
+  pub tls:          super::TlsInfo,
+  pub kind:         ErrorKind,
 }
 
 impl ErrorContext {
@@ -31,12 +130,18 @@ impl ErrorContext {
   pub fn new(
     peer_address: std::io::Result<std::net::SocketAddr>,
     url: Url,
+    request_id: String,
     certificate: Option<X509>,
+    tls: super::TlsInfo,
+    kind: ErrorKind,
   ) -> Self {
     Self {
       peer_address: peer_address.ok(),
       url,
+      request_id,
       certificate,
+      tls,
+      kind,
     }
   }
 }
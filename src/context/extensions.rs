@@ -0,0 +1,90 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  any::{Any, TypeId},
+  collections::HashMap,
+  sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// Typed values attached to a single request.
+///
+/// Modules and hooks may insert values before routing which the route handler
+/// and post-route hooks can then read back, keyed by their type. Every context
+/// of a request shares the same extensions, so clones observe each other's
+/// insertions.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::context::Extensions;
+///
+/// struct User(String);
+///
+/// let extensions = Extensions::default();
+///
+/// extensions.insert(User("fuwn".to_string()));
+///
+/// assert_eq!(extensions.get::<User>().unwrap().0, "fuwn");
+/// ```
+#[derive(Clone, Default)]
+pub struct Extensions {
+  values: Arc<Mutex<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>>,
+}
+
+impl Extensions {
+  // A panic while the map is locked can not leave it half-updated, so a
+  // poisoned lock is recovered rather than propagated.
+  fn values(
+    &self,
+  ) -> MutexGuard<'_, HashMap<TypeId, Arc<dyn Any + Send + Sync>>> {
+    self.values.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  /// Insert a value, replacing and returning any previous value of the same
+  /// type.
+  pub fn insert<T: Any + Send + Sync>(&self, value: T) -> Option<Arc<T>> {
+    self
+      .values()
+      .insert(TypeId::of::<T>(), Arc::new(value))
+      .and_then(|previous| previous.downcast().ok())
+  }
+
+  /// Get the value of type `T`, if one has been inserted.
+  #[must_use]
+  pub fn get<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+    self
+      .values()
+      .get(&TypeId::of::<T>())
+      .and_then(|value| value.clone().downcast().ok())
+  }
+
+  /// Remove and return the value of type `T`, if one has been inserted.
+  #[allow(clippy::must_use_candidate)]
+  pub fn remove<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+    self
+      .values()
+      .remove(&TypeId::of::<T>())
+      .and_then(|value| value.downcast().ok())
+  }
+
+  /// Check whether a value of type `T` has been inserted.
+  #[must_use]
+  pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+    self.values().contains_key(&TypeId::of::<T>())
+  }
+}
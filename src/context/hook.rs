@@ -15,34 +15,86 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 
-use matchit::Params;
 use openssl::x509::X509;
 use url::Url;
 
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone)]
 pub struct HookContext {
-  pub peer_address: Option<std::net::SocketAddr>,
-  pub url:          Url,
-  pub parameters:   Option<HashMap<String, String>>,
-  pub certificate:  Option<X509>,
+  pub peer_address:  Option<std::net::SocketAddr>,
+  /// The address of the listener the request arrived on.
+  pub listener:      std::net::SocketAddr,
+  /// The name of the listener the request arrived on, if it was added with
+  /// one.
+  pub listener_name: Option<String>,
+  /// The URL requested, or `gemini://invalid/` for a bad request whose URL
+  /// could not be parsed.
+  pub url:           Url,
+  pub request_id:    String,
+  pub parameters:    Option<super::Parameters>,
+  pub certificate:   Option<X509>,
+  pub tls:           super::TlsInfo,
+  /// The exact bytes of the request line, as received from the client; empty
+  /// for requests which did not arrive over Gemini.
+  pub raw_request:   super::RawRequest,
+  /// When the request was received.
+  pub received_at:   SystemTime,
+  /// The time taken to produce the response; only available after routing.
+  pub elapsed:       Option<Duration>,
+  /// The status of the response; only available after routing.
+  pub status:        Option<i32>,
+  /// The size of the response body returned by the handler, in bytes; only
+  /// available after routing.
+  pub bytes:         Option<usize>,
+  /// Values attached to the request, shared with the route handler.
+  pub extensions:    super::Extensions,
 }
 
 impl HookContext {
   #[must_use]
   pub fn new(
     peer_address: std::io::Result<std::net::SocketAddr>,
+    listener: std::net::SocketAddr,
     url: Url,
-    parameters: Option<Params<'_, '_>>,
+    request_id: String,
+    parameters: Option<super::Parameters>,
     certificate: Option<X509>,
+    tls: super::TlsInfo,
   ) -> Self {
     Self {
       peer_address: peer_address.ok(),
+      listener,
+      listener_name: None,
       url,
-      parameters: parameters.map(|p| crate::utilities::params_to_hashmap(&p)),
+      request_id,
+      parameters,
       certificate,
+      tls,
+      raw_request: super::RawRequest::default(),
+      received_at: SystemTime::now(),
+      elapsed: None,
+      status: None,
+      bytes: None,
+      extensions: super::Extensions::default(),
     }
   }
+
+  pub(crate) fn with_listener_name(mut self, name: Option<String>) -> Self {
+    self.listener_name = name;
+
+    self
+  }
+
+  pub(crate) fn with_request(
+    mut self,
+    raw_request: super::RawRequest,
+    received_at: SystemTime,
+  ) -> Self {
+    self.raw_request = raw_request;
+    self.received_at = received_at;
+
+    self
+  }
 }
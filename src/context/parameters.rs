@@ -0,0 +1,109 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  collections::HashMap,
+  fmt,
+  ops::Deref,
+  sync::{Arc, OnceLock},
+};
+
+use matchit::Params;
+
+use crate::router::RouteTable;
+
+/// The parameters captured from the path of a request by its route, e.g.,
+/// `id` for a route mounted at `/posts/:id`.
+///
+/// The parameters are only collected into a map the first time they are read,
+/// so requests which never read them do not pay for it. Clones share the same
+/// map.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{context::RouteContext, response::Response};
+///
+/// windmark::router::Router::new().mount(
+///   "/posts/:id",
+///   |context: RouteContext| {
+///     Response::success(format!("Post {}", context.parameters["id"]))
+///   },
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct Parameters {
+  inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+  source: Option<(Arc<RouteTable>, Box<str>)>,
+  map:    OnceLock<HashMap<String, String>>,
+}
+
+impl Parameters {
+  /// Parameters which are captured by matching `path` against `routes` once
+  /// they are first read.
+  pub(crate) fn lazy(routes: Arc<RouteTable>, path: &str) -> Self {
+    Self {
+      inner: Arc::new(Inner {
+        source: Some((routes, path.into())),
+        map:    OnceLock::new(),
+      }),
+    }
+  }
+}
+
+impl From<HashMap<String, String>> for Parameters {
+  fn from(map: HashMap<String, String>) -> Self {
+    Self {
+      inner: Arc::new(Inner {
+        source: None,
+        map:    OnceLock::from(map),
+      }),
+    }
+  }
+}
+
+impl From<&Params<'_, '_>> for Parameters {
+  fn from(params: &Params<'_, '_>) -> Self {
+    crate::utilities::params_to_hashmap(params).into()
+  }
+}
+
+impl Deref for Parameters {
+  type Target = HashMap<String, String>;
+
+  fn deref(&self) -> &Self::Target {
+    self.inner.map.get_or_init(|| {
+      self
+        .inner
+        .source
+        .as_ref()
+        .and_then(|(routes, path)| routes.at(path).ok())
+        .map(|route| crate::utilities::params_to_hashmap(&route.params))
+        .unwrap_or_default()
+    })
+  }
+}
+
+impl fmt::Debug for Parameters {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_map().entries(self.iter()).finish()
+  }
+}
@@ -0,0 +1,102 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::utilities::percent_decode;
+
+/// The decoded key-value pairs of a URL's query, in the order they appear.
+///
+/// Unlike HTML forms, Gemini clients percent-encode spaces, so a `+` is
+/// decoded as a literal plus sign.
+///
+/// # Examples
+///
+/// ```rust
+/// let url =
+///   url::Url::parse("gemini://fuwn.me/?tag=a&tag=b+c&q=%F0%9F%A6%80").unwrap();
+/// let query = windmark::context::Query::from_url(&url);
+///
+/// assert_eq!(query.get("q"), Some("🦀"));
+/// assert_eq!(query.get_all("tag"), vec!["a", "b+c"]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+  pairs: Vec<(String, String)>,
+}
+
+impl Query {
+  /// Parse the query of `url`.
+  #[must_use]
+  pub fn from_url(url: &url::Url) -> Self {
+    url.query().map(Self::parse).unwrap_or_default()
+  }
+
+  /// Parse a raw, percent-encoded query string.
+  #[must_use]
+  pub fn parse(query: &str) -> Self {
+    Self {
+      pairs: query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+          let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+          (percent_decode(key), percent_decode(value))
+        })
+        .collect(),
+    }
+  }
+
+  /// The first value of `key`.
+  #[must_use]
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self
+      .pairs
+      .iter()
+      .find(|(name, _)| name == key)
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// Every value of `key`, in the order they appear.
+  #[must_use]
+  pub fn get_all(&self, key: &str) -> Vec<&str> {
+    self
+      .pairs
+      .iter()
+      .filter(|(name, _)| name == key)
+      .map(|(_, value)| value.as_str())
+      .collect()
+  }
+
+  #[must_use]
+  pub fn contains(&self, key: &str) -> bool {
+    self.pairs.iter().any(|(name, _)| name == key)
+  }
+
+  /// Iterate over every key-value pair.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .pairs
+      .iter()
+      .map(|(key, value)| (key.as_str(), value.as_str()))
+  }
+
+  #[must_use]
+  pub fn len(&self) -> usize { self.pairs.len() }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool { self.pairs.is_empty() }
+}
@@ -0,0 +1,68 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+use std::{fmt, ops::Deref, sync::Arc};
+
+use crate::router::buffers::Buffer;
+
+/// The exact bytes of a request line, as received from the client.
+///
+/// Every context of a request shares the buffer the request was read into,
+/// which is returned to the `Router`'s pool once the last of them is dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// windmark::router::Router::new().mount("/", |context| {
+///   windmark::response::Response::success(format!(
+///     "You sent {} bytes.",
+///     context.raw_request.len()
+///   ))
+/// });
+/// ```
+#[derive(Clone, Default)]
+pub struct RawRequest {
+  buffer: Option<Arc<Buffer>>,
+}
+
+impl RawRequest {
+  pub(crate) fn new(buffer: Buffer) -> Self {
+    Self {
+      buffer: Some(Arc::new(buffer)),
+    }
+  }
+}
+
+impl Deref for RawRequest {
+  type Target = [u8];
+
+  fn deref(&self) -> &Self::Target {
+    self
+      .buffer
+      .as_deref()
+      .map_or(&[], |buffer| buffer.as_slice())
+  }
+}
+
+impl AsRef<[u8]> for RawRequest {
+  fn as_ref(&self) -> &[u8] { self }
+}
+
+impl fmt::Debug for RawRequest {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&String::from_utf8_lossy(self), f)
+  }
+}
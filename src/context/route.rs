@@ -15,19 +15,65 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::collections::HashMap;
+use std::{sync::Arc, time::SystemTime};
 
-use matchit::Params;
 use openssl::x509::X509;
 use url::Url;
 
+use crate::{
+  response::Response,
+  router::{RouteInfo, RouteTable},
+};
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone)]
 pub struct RouteContext {
-  pub peer_address: Option<std::net::SocketAddr>,
-  pub url:          Url,
-  pub parameters:   HashMap<String, String>,
-  pub certificate:  Option<X509>,
+  pub peer_address:  Option<std::net::SocketAddr>,
+  pub url:           Url,
+  pub request_id:    String,
+  pub parameters:    super::Parameters,
+  pub certificate:   Option<X509>,
+  pub tls:           super::TlsInfo,
+  /// The address of the listener the request arrived on; unavailable to
+  /// requests made by the `Router` itself, e.g., to warm a route.
+  pub listener:      Option<std::net::SocketAddr>,
+  /// The name of the listener the request arrived on, if it was added with
+  /// one.
+  pub listener_name: Option<String>,
+  /// The exact bytes of the request line, as received from the client; empty
+  /// for requests which did not arrive over Gemini.
+  pub raw_request:   super::RawRequest,
+  /// When the request was received.
+  pub received_at:   SystemTime,
+  /// Values attached to the request by modules and pre-route hooks.
+  pub extensions:    super::Extensions,
+  routes:            Option<Arc<RouteTable>>,
+  closed:            super::Closed,
+  response:          Option<PendingResponse>,
+  /// The handlers already handling this request, through subrequests, whose
+  /// locks are held.
+  handling:          Vec<usize>,
+}
+
+/// The status and type of the response a header or footer is displayed
+/// around.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingResponse {
+  pub status:        i32,
+  pub mime:          Option<String>,
+  pub character_set: Option<String>,
+  pub languages:     Option<Vec<String>>,
+}
+
+impl From<&Response> for PendingResponse {
+  fn from(response: &Response) -> Self {
+    Self {
+      status:        response.status,
+      mime:          response.mime.clone(),
+      character_set: response.character_set.clone(),
+      languages:     response.languages.clone(),
+    }
+  }
 }
 
 impl RouteContext {
@@ -35,14 +81,310 @@ impl RouteContext {
   pub fn new(
     peer_address: std::io::Result<std::net::SocketAddr>,
     url: Url,
-    parameters: &Params<'_, '_>,
+    request_id: String,
+    parameters: super::Parameters,
     certificate: Option<X509>,
+    tls: super::TlsInfo,
   ) -> Self {
     Self {
       peer_address: peer_address.ok(),
       url,
-      parameters: crate::utilities::params_to_hashmap(parameters),
+      request_id,
+      parameters,
       certificate,
+      tls,
+      listener: None,
+      listener_name: None,
+      raw_request: super::RawRequest::default(),
+      received_at: SystemTime::now(),
+      extensions: super::Extensions::default(),
+      routes: None,
+      closed: super::Closed::default(),
+      response: None,
+      handling: Vec::new(),
     }
   }
+
+  pub(crate) fn with_routes(mut self, routes: Arc<RouteTable>) -> Self {
+    self.routes = Some(routes);
+
+    self
+  }
+
+  pub(crate) fn with_extensions(
+    mut self,
+    extensions: super::Extensions,
+  ) -> Self {
+    self.extensions = extensions;
+
+    self
+  }
+
+  pub(crate) fn with_listener(
+    mut self,
+    address: std::net::SocketAddr,
+    name: Option<String>,
+  ) -> Self {
+    self.listener = Some(address);
+    self.listener_name = name;
+
+    self
+  }
+
+  pub(crate) fn with_request(
+    mut self,
+    raw_request: super::RawRequest,
+    received_at: SystemTime,
+  ) -> Self {
+    self.raw_request = raw_request;
+    self.received_at = received_at;
+
+    self
+  }
+
+  pub(crate) fn with_handler(mut self, handler_id: usize) -> Self {
+    self.handling.push(handler_id);
+
+    self
+  }
+
+  pub(crate) fn with_closed(mut self, closed: super::Closed) -> Self {
+    self.closed = closed;
+
+    self
+  }
+
+  pub(crate) fn with_response(mut self, response: &Response) -> Self {
+    self.response = Some(response.into());
+
+    self
+  }
+
+  /// The response of the route handler, which is only available to headers
+  /// and footers, as they are rendered once the handler has responded.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::context::RouteContext;
+  ///
+  /// windmark::router::Router::new().add_footer(|context: RouteContext| {
+  ///   if context
+  ///     .response()
+  ///     .is_some_and(|response| response.status == 20)
+  ///   {
+  ///     "=> / Home".to_string()
+  ///   } else {
+  ///     String::new()
+  ///   }
+  /// });
+  /// ```
+  #[must_use]
+  pub const fn response(&self) -> Option<&PendingResponse> {
+    self.response.as_ref()
+  }
+
+  /// Whether the client has disconnected, in which case any response will go
+  /// unread.
+  ///
+  /// Disconnects are only detected for requests which arrived over Gemini.
+  #[must_use]
+  pub fn is_closed(&self) -> bool { self.closed.is_closed() }
+
+  /// Resolve once the client disconnects, so that expensive work can be
+  /// abandoned.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{context::RouteContext, response::Response};
+  ///
+  /// async fn render_report() -> String { String::from("# Report") }
+  ///
+  /// windmark::router::Router::new().mount("/report", |context: RouteContext| {
+  ///   async move {
+  ///     tokio::select! {
+  ///       report = render_report() => Response::success(report),
+  ///       () = context.closed() => Response::temporary_failure("Abandoned."),
+  ///     }
+  ///   }
+  /// });
+  /// ```
+  pub async fn closed(&self) { self.closed.closed().await; }
+
+  /// The decoded input a client submitted in response to an input or
+  /// sensitive input prompt, i.e., the whole query of the requested URL.
+  ///
+  /// Returns `None` if the URL has no query.
+  #[must_use]
+  pub fn input(&self) -> Option<String> {
+    self.url.query().map(crate::utilities::percent_decode)
+  }
+
+  /// Ask the client for input with `prompt`, or, once it has been submitted,
+  /// respond with `respond`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{context::RouteContext, response::Response};
+  ///
+  /// windmark::router::Router::new().mount("/greet", |context: RouteContext| {
+  ///   context.prompt_or_input("What is your name?", |name| {
+  ///     Response::success(format!("Hello, {name}!"))
+  ///   })
+  /// });
+  /// ```
+  pub fn prompt_or_input(
+    &self,
+    prompt: impl Into<String> + AsRef<str>,
+    respond: impl FnOnce(String) -> Response,
+  ) -> Response {
+    self
+      .input()
+      .map_or_else(|| Response::input(prompt), respond)
+  }
+
+  /// The same as [`RouteContext::prompt_or_input`], but the client is asked
+  /// for sensitive input, which it should not echo.
+  pub fn sensitive_prompt_or_input(
+    &self,
+    prompt: impl Into<String> + AsRef<str>,
+    respond: impl FnOnce(String) -> Response,
+  ) -> Response {
+    self
+      .input()
+      .map_or_else(|| Response::sensitive_input(prompt), respond)
+  }
+
+  /// The decoded query of the requested URL, preserving repeated keys.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{context::RouteContext, response::Response};
+  ///
+  /// windmark::router::Router::new().mount("/search", |context: RouteContext| {
+  ///   let query = context.query();
+  ///
+  ///   Response::success(format!(
+  ///     "Searching for {:?} in {}",
+  ///     query.get("q").unwrap_or_default(),
+  ///     query.get_all("tag").join(", ")
+  ///   ))
+  /// });
+  /// ```
+  #[must_use]
+  pub fn query(&self) -> super::Query { super::Query::from_url(&self.url) }
+
+  /// Deserialise the query of the requested URL into `T`.
+  ///
+  /// As with [`RouteContext::query`], a `+` is taken literally rather than as
+  /// a space.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{context::RouteContext, response::Response};
+  ///
+  /// #[derive(serde::Deserialize)]
+  /// struct Page {
+  ///   number: usize,
+  /// }
+  ///
+  /// windmark::router::Router::new().mount("/posts", |context: RouteContext| {
+  ///   match context.query_as::<Page>() {
+  ///     Ok(page) => Response::success(format!("Page {}", page.number)),
+  ///     Err(_) => Response::bad_request("Which page?"),
+  ///   }
+  /// });
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// if the query does not describe a `T`.
+  #[cfg(feature = "query")]
+  pub fn query_as<T: serde::de::DeserializeOwned>(
+    &self,
+  ) -> Result<T, serde_urlencoded::de::Error> {
+    serde_urlencoded::from_str(
+      &self.url.query().unwrap_or_default().replace('+', "%2B"),
+    )
+  }
+
+  /// List the routes mounted on the `Router` which created this context, in
+  /// the order they were mounted.
+  #[must_use]
+  pub fn routes(&self) -> Vec<RouteInfo> {
+    self
+      .routes
+      .as_ref()
+      .map(|routes| routes.routes())
+      .unwrap_or_default()
+  }
+
+  /// Execute another mounted route internally and return its `Response`.
+  ///
+  /// The subrequest shares the peer address and certificate of the current
+  /// request, but no network traffic, hooks, headers, or footers are involved.
+  /// `path` is resolved relative to the current URL.
+  ///
+  /// Returns `None` if no route matches `path`, if this context was not
+  /// created by a `Router`, or if the matching route is already handling this
+  /// request, e.g., a route subrequesting itself, which would otherwise wait
+  /// on its own handler forever.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new()
+  ///   .mount("/fragment/footer", |_| {
+  ///     Response::success("Thanks for reading!")
+  ///   })
+  ///   .mount("/", |context: windmark::context::RouteContext| {
+  ///     async move {
+  ///       let footer = context
+  ///         .subrequest("/fragment/footer")
+  ///         .await
+  ///         .map(|response| response.content)
+  ///         .unwrap_or_default();
+  ///
+  ///       Response::success(format!("# Hello!\n\n{footer}"))
+  ///     }
+  ///   });
+  /// ```
+  pub async fn subrequest(
+    &self,
+    path: impl AsRef<str> + Send,
+  ) -> Option<Response> {
+    let url = self.url.join(path.as_ref()).ok()?;
+    let routes = self.routes.as_ref()?;
+    let route = routes.at(url.path()).ok()?.value;
+    let handler_id = route.handler_id();
+
+    if self.handling.contains(&handler_id) {
+      warn!(
+        "[{}] not subrequesting {}, as its route is already handling this \
+         request",
+        self.request_id,
+        url.path()
+      );
+
+      return None;
+    }
+
+    let handler = route.handler.clone();
+    let parameters = super::Parameters::lazy(routes.clone(), url.path());
+    let context = Self {
+      url,
+      parameters,
+      ..self.clone()
+    }
+    .with_handler(handler_id);
+    let response = handler.call(context).await;
+
+    Some(response)
+  }
 }
@@ -0,0 +1,59 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use openssl::{
+  ssl::SslRef,
+  x509::{X509Ref, X509},
+};
+
+/// Details of the TLS session a request arrived on.
+#[derive(Clone, Default)]
+pub struct TlsInfo {
+  /// The certificates presented by the peer, starting with the leaf
+  /// certificate; empty if the peer did not present a certificate.
+  pub certificate_chain: Vec<X509>,
+  /// The negotiated protocol version, e.g. `"TLSv1.3"`.
+  pub version:           &'static str,
+  /// The name of the negotiated cipher suite.
+  pub cipher:            Option<&'static str>,
+}
+
+impl TlsInfo {
+  #[must_use]
+  pub fn new(ssl: &SslRef) -> Self {
+    let leaf = ssl.peer_certificate();
+    let leaf_der = leaf.as_ref().and_then(|leaf| leaf.to_der().ok());
+    let mut certificate_chain = leaf.into_iter().collect::<Vec<_>>();
+
+    // Depending on the side of the connection, OpenSSL may or may not include
+    // the leaf certificate in the peer chain.
+    if let Some(chain) = ssl.peer_cert_chain() {
+      certificate_chain.extend(
+        chain
+          .iter()
+          .filter(|certificate| certificate.to_der().ok() != leaf_der)
+          .map(X509Ref::to_owned),
+      );
+    }
+
+    Self {
+      certificate_chain,
+      version: ssl.version_str(),
+      cipher: ssl.current_cipher().map(openssl::ssl::SslCipherRef::name),
+    }
+  }
+}
@@ -0,0 +1,107 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fmt, io};
+
+/// An error which prevents a `Router` from serving requests.
+#[derive(Debug)]
+pub enum Error {
+  /// No certificate, or no private key, was set; contains which.
+  MissingTlsMaterial(&'static str),
+  /// A certificate or private key file could not be read.
+  UnreadableTlsMaterial {
+    /// Either `"certificate"` or `"private key"`.
+    kind:   &'static str,
+    path:   String,
+    source: io::Error,
+  },
+  /// The TLS acceptor could not be built from the configured certificate,
+  /// private key, and TLS settings.
+  Tls(openssl::error::ErrorStack),
+  /// The keylog file set with `Router::set_keylog_file` could not be opened.
+  UnwritableKeylog { path: String, source: io::Error },
+  /// The port set with `Router::set_port` is not a valid TCP port.
+  InvalidPort(i32),
+  /// A hostname added with `Router::add_hostname` could never match the host
+  /// of a request, e.g., because it includes a port or a path.
+  InvalidHostname(String),
+  /// Routes which could not be mounted, e.g., because they conflict with a
+  /// route mounted before them.
+  Routes(Vec<String>),
+  /// A listener could not be bound or used.
+  Io(io::Error),
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::MissingTlsMaterial(kind) =>
+        write!(
+          f,
+          "no {kind} was set; Gemini requires TLS, so a {kind} must be set \
+           before the router is run"
+        ),
+      Self::UnreadableTlsMaterial {
+        kind,
+        path,
+        source,
+      } => write!(f, "could not read the {kind} file \"{path}\": {source}"),
+      Self::Tls(e) => write!(f, "could not configure TLS: {e}"),
+      Self::UnwritableKeylog {
+        path,
+        source,
+      } => write!(f, "could not open the keylog file \"{path}\": {source}"),
+      Self::InvalidPort(port) =>
+        write!(
+          f,
+          "{port} is not a valid port, which must be from 0 to 65535"
+        ),
+      Self::InvalidHostname(hostname) =>
+        write!(
+          f,
+          "\"{hostname}\" is not a valid hostname; hostnames must not include \
+           a scheme, port, or path"
+        ),
+      Self::Routes(routes) => write!(f, "{}", routes.join("; ")),
+      Self::Io(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::UnreadableTlsMaterial {
+        source, ..
+      }
+      | Self::UnwritableKeylog {
+        source, ..
+      } => Some(source),
+      Self::Tls(e) => Some(e),
+      Self::Io(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for Error {
+  fn from(e: io::Error) -> Self { Self::Io(e) }
+}
+
+impl From<openssl::error::ErrorStack> for Error {
+  fn from(e: openssl::error::ErrorStack) -> Self { Self::Tls(e) }
+}
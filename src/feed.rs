@@ -0,0 +1,290 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Atom and gemsub feeds for gemlogs
+
+use std::{fmt::Write, io, path::Path};
+
+use crate::{
+  gemtext::{self, Line},
+  response::Response,
+  utilities::escape_html as escape,
+};
+
+/// A post in a [`Feed`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+  pub title:   String,
+  /// The absolute URL of the post.
+  pub url:     String,
+  /// The date the post was last updated, as `YYYY-MM-DD` or an RFC 3339
+  /// timestamp.
+  pub updated: String,
+  pub summary: Option<String>,
+}
+
+/// A gemlog feed which can be rendered as Atom or as a gemsub page.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::feed::{Entry, Feed};
+///
+/// let mut feed = Feed::new("My Gemlog", "gemini://example.com/gemlog/");
+///
+/// feed.entry(Entry {
+///   title:   "Hello, World!".to_string(),
+///   url:     "gemini://example.com/gemlog/2023-01-01-hello.gmi".to_string(),
+///   updated: "2023-01-01".to_string(),
+///   summary: None,
+/// });
+///
+/// windmark::router::Router::new()
+///   .mount("/gemlog/atom.xml", move |_| feed.atom_response());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Feed {
+  pub title:   String,
+  /// The absolute URL of the gemlog, also used as the feed's ID.
+  pub url:     String,
+  /// The name of the feed's author. Atom requires one, so the host of `url`,
+  /// or else the feed's title, is named in its place when this is `None`.
+  pub author:  Option<String>,
+  pub entries: Vec<Entry>,
+}
+
+impl Feed {
+  #[must_use]
+  pub fn new(title: impl Into<String>, url: impl Into<String>) -> Self {
+    Self {
+      title:   title.into(),
+      url:     url.into(),
+      author:  None,
+      entries: Vec::new(),
+    }
+  }
+
+  /// Build a feed from the dated gemtext files in `directory`.
+  ///
+  /// Files named like `YYYY-MM-DD-title.gmi` become entries, titled by their
+  /// first heading and linked relative to `url`; other files are ignored.
+  /// Entries are ordered newest first.
+  ///
+  /// # Errors
+  ///
+  /// if `directory`, or a file within it, could not be read.
+  pub fn from_directory(
+    title: impl Into<String>,
+    url: impl Into<String>,
+    directory: impl AsRef<Path>,
+  ) -> io::Result<Self> {
+    let mut feed = Self::new(title, url);
+
+    for file in std::fs::read_dir(directory)? {
+      let path = file?.path();
+      let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        continue;
+      };
+      let Some(date) = dated(&path) else {
+        continue;
+      };
+      let title = gemtext::parse(&std::fs::read_to_string(&path)?)
+        .into_iter()
+        .find_map(|line| {
+          match line {
+            Line::Heading {
+              text, ..
+            } => Some(text),
+            _ => None,
+          }
+        })
+        .unwrap_or_else(|| {
+          path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()[date.len()..]
+            .trim_start_matches(['-', '_', ' '])
+            .to_string()
+        });
+
+      feed.entries.push(Entry {
+        title,
+        url: format!("{}/{name}", feed.url.trim_end_matches('/')),
+        updated: date,
+        summary: None,
+      });
+    }
+
+    feed.sort();
+
+    Ok(feed)
+  }
+
+  /// Set the name of the feed's author.
+  pub fn author(&mut self, author: impl Into<String>) -> &mut Self {
+    self.author = Some(author.into());
+
+    self
+  }
+
+  /// The name of the feed's author, or what is named in its place.
+  fn author_name(&self) -> String {
+    self.author.clone().unwrap_or_else(|| {
+      url::Url::parse(&self.url)
+        .ok()
+        .and_then(|url| url.host_str().map(ToString::to_string))
+        .unwrap_or_else(|| self.title.clone())
+    })
+  }
+
+  /// Add an entry.
+  pub fn entry(&mut self, entry: Entry) -> &mut Self {
+    self.entries.push(entry);
+
+    self
+  }
+
+  /// Order the entries newest first.
+  pub fn sort(&mut self) -> &mut Self {
+    self
+      .entries
+      .sort_by(|left, right| right.updated.cmp(&left.updated));
+
+    self
+  }
+
+  /// Render the feed as an Atom document.
+  #[must_use]
+  pub fn to_atom(&self) -> String {
+    let mut atom = String::new();
+
+    // Writing to a `String` can not fail.
+    let _ = self.write_atom(&mut atom);
+
+    atom
+  }
+
+  fn write_atom(&self, atom: &mut String) -> std::fmt::Result {
+    let updated = self
+      .entries
+      .iter()
+      .map(|entry| expand_date(&entry.updated))
+      .max()
+      .unwrap_or_else(|| crate::utilities::timestamp(std::time::UNIX_EPOCH));
+
+    writeln!(atom, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+    writeln!(atom, "<feed xmlns=\"http://www.w3.org/2005/Atom\">")?;
+    writeln!(atom, "  <title>{}</title>", escape(&self.title))?;
+    writeln!(atom, "  <id>{}</id>", escape(&self.url))?;
+    writeln!(atom, "  <link href=\"{}\"/>", escape(&self.url))?;
+    writeln!(atom, "  <updated>{updated}</updated>")?;
+
+    writeln!(
+      atom,
+      "  <author><name>{}</name></author>",
+      escape(&self.author_name())
+    )?;
+
+    for entry in &self.entries {
+      writeln!(atom, "  <entry>")?;
+      writeln!(atom, "    <title>{}</title>", escape(&entry.title))?;
+      writeln!(atom, "    <id>{}</id>", escape(&entry.url))?;
+      writeln!(atom, "    <link href=\"{}\"/>", escape(&entry.url))?;
+      writeln!(
+        atom,
+        "    <updated>{}</updated>",
+        expand_date(&entry.updated)
+      )?;
+
+      if let Some(summary) = &entry.summary {
+        writeln!(atom, "    <summary>{}</summary>", escape(summary))?;
+      }
+
+      writeln!(atom, "  </entry>")?;
+    }
+
+    writeln!(atom, "</feed>")
+  }
+
+  /// Render the feed as a gemtext page which can be subscribed to, as
+  /// described by the gemsub companion specification.
+  #[must_use]
+  pub fn to_gemsub(&self) -> String {
+    let mut document = gemtext::Document::new();
+
+    document.heading(1, self.title.as_str());
+
+    for entry in &self.entries {
+      document.link(
+        entry.url.as_str(),
+        format!(
+          "{} - {}",
+          entry.updated.get(..10).unwrap_or(&entry.updated),
+          entry.title
+        ),
+      );
+    }
+
+    document.to_string()
+  }
+
+  /// A response serving the feed as Atom, sent without the `Router`'s headers
+  /// and footers, which would make the XML invalid.
+  #[must_use]
+  pub fn atom_response(&self) -> Response {
+    Response::new(20, self.to_atom())
+      .mime("application/atom+xml")
+      .character_set("utf-8")
+      .raw()
+  }
+
+  /// A response serving the feed as a gemsub page.
+  #[must_use]
+  pub fn gemsub_response(&self) -> Response {
+    Response::success(self.to_gemsub())
+  }
+}
+
+/// The `YYYY-MM-DD` date the name of a gemtext file begins with, if any.
+pub(crate) fn dated(path: &Path) -> Option<String> {
+  if !path
+    .extension()
+    .is_some_and(|extension| extension.eq_ignore_ascii_case("gmi"))
+  {
+    return None;
+  }
+
+  let date = path.file_name()?.to_str()?.get(..10)?;
+  let is_date = date.char_indices().all(|(index, character)| {
+    if index == 4 || index == 7 {
+      character == '-'
+    } else {
+      character.is_ascii_digit()
+    }
+  });
+
+  is_date.then(|| date.to_string())
+}
+
+/// Expand a bare `YYYY-MM-DD` date into an RFC 3339 timestamp.
+fn expand_date(date: &str) -> String {
+  if date.len() == 10 {
+    format!("{date}T00:00:00Z")
+  } else {
+    date.to_string()
+  }
+}
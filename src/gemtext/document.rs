@@ -0,0 +1,136 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::fmt;
+
+use super::Line;
+use crate::response::Response;
+
+/// A gemtext document builder.
+///
+/// Each method appends a line, taking care of the markup so that, for
+/// example, a label containing a newline can not break a link line.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::gemtext::Document;
+///
+/// let mut document = Document::new();
+///
+/// document
+///   .heading(1, "Hello, World!")
+///   .text("Welcome to my capsule.")
+///   .link("/about", "About me");
+///
+/// assert_eq!(
+///   document.to_string(),
+///   "# Hello, World!\nWelcome to my capsule.\n=> /about About me\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Document {
+  lines: Vec<Line>,
+}
+
+impl Document {
+  #[must_use]
+  pub fn new() -> Self { Self::default() }
+
+  /// The lines of the document.
+  #[must_use]
+  pub fn lines(&self) -> &[Line] { &self.lines }
+
+  /// Append a line of any type.
+  pub fn line(&mut self, line: Line) -> &mut Self {
+    self.lines.push(line);
+
+    self
+  }
+
+  /// Append text, one text line per line of `text`.
+  ///
+  /// Lines which would otherwise be read as markup are prefixed with a space.
+  pub fn text(&mut self, text: impl AsRef<str>) -> &mut Self {
+    for line in text.as_ref().lines() {
+      self.lines.push(Line::Text(line.to_string()));
+    }
+
+    self
+  }
+
+  /// Append an empty line.
+  pub fn blank(&mut self) -> &mut Self { self.line(Line::Text(String::new())) }
+
+  /// Append a heading; `level` is clamped to between 1 and 3.
+  pub fn heading(&mut self, level: u8, text: impl Into<String>) -> &mut Self {
+    self.line(Line::Heading {
+      level: level.clamp(1, 3),
+      text:  text.into(),
+    })
+  }
+
+  /// Append a link with a label; an empty label is omitted.
+  pub fn link(
+    &mut self,
+    url: impl Into<String>,
+    label: impl Into<String>,
+  ) -> &mut Self {
+    self.line(Line::Link {
+      url:   url.into(),
+      label: Some(label.into()),
+    })
+  }
+
+  pub fn list_item(&mut self, text: impl Into<String>) -> &mut Self {
+    self.line(Line::ListItem(text.into()))
+  }
+
+  pub fn quote(&mut self, text: impl Into<String>) -> &mut Self {
+    self.line(Line::Quote(text.into()))
+  }
+
+  /// Append a preformatted block with an optional alt text.
+  pub fn preformatted(
+    &mut self,
+    alt: Option<&str>,
+    content: impl AsRef<str>,
+  ) -> &mut Self {
+    self.line(Line::Preformatted {
+      alt:   alt.map(ToString::to_string),
+      lines: content.as_ref().lines().map(ToString::to_string).collect(),
+    })
+  }
+}
+
+impl fmt::Display for Document {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for line in &self.lines {
+      writeln!(f, "{line}")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl From<Document> for Response {
+  fn from(document: Document) -> Self { Self::success(document) }
+}
+
+impl From<&Document> for Response {
+  fn from(document: &Document) -> Self { Self::success(document) }
+}
@@ -0,0 +1,129 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::fmt::Write;
+
+use super::Line;
+use crate::utilities::escape_html as escape;
+
+/// Render gemtext lines as an HTML fragment.
+///
+/// Consecutive list items are grouped into a single list and empty text lines
+/// are dropped. Links keep their URLs as they are, unless their scheme is not
+/// `gemini`, `http`, or `https`, e.g., `javascript:`, in which case they are
+/// rendered as text.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::gemtext::{parse, to_html};
+///
+/// assert_eq!(
+///   to_html(&parse("# Hello\n* <one>\n* two\n=> /about")),
+///   "<h1>Hello</h1>\n<ul>\n<li>&lt;one&gt;</li>\n<li>two</li>\n</ul>\n<p><a \
+///    href=\"/about\">/about</a></p>\n"
+/// );
+/// ```
+#[must_use]
+pub fn to_html(lines: &[Line]) -> String {
+  let mut html = String::new();
+  let mut in_list = false;
+
+  for line in lines {
+    let is_list_item = matches!(line, Line::ListItem(_));
+
+    if in_list && !is_list_item {
+      html.push_str("</ul>\n");
+    } else if !in_list && is_list_item {
+      html.push_str("<ul>\n");
+    }
+
+    in_list = is_list_item;
+
+    // Writing to a `String` can not fail.
+    let _ = match line {
+      Line::Text(text) if text.trim().is_empty() => Ok(()),
+      Line::Text(text) => writeln!(html, "<p>{}</p>", escape(text)),
+      Line::Link {
+        url,
+        label,
+      } if !is_safe_link(url) =>
+        writeln!(
+          html,
+          "<p>{}</p>",
+          escape(
+            label
+              .as_deref()
+              .filter(|label| !label.is_empty())
+              .unwrap_or(url)
+          )
+        ),
+      Line::Link {
+        url,
+        label,
+      } =>
+        writeln!(
+          html,
+          "<p><a href=\"{}\">{}</a></p>",
+          escape(url),
+          escape(
+            label
+              .as_deref()
+              .filter(|label| !label.is_empty())
+              .unwrap_or(url)
+          )
+        ),
+      Line::Heading {
+        level,
+        text,
+      } => {
+        let level = (*level).clamp(1, 3);
+
+        writeln!(html, "<h{level}>{}</h{level}>", escape(text))
+      }
+      Line::ListItem(text) => writeln!(html, "<li>{}</li>", escape(text)),
+      Line::Quote(text) =>
+        writeln!(html, "<blockquote>{}</blockquote>", escape(text)),
+      Line::Preformatted {
+        alt,
+        lines,
+      } =>
+        match alt {
+          Some(alt) =>
+            write!(html, "<pre role=\"img\" aria-label=\"{}\">", escape(alt)),
+          None => write!(html, "<pre>"),
+        }
+        .and_then(|()| writeln!(html, "{}</pre>", escape(&lines.join("\n")))),
+    };
+  }
+
+  if in_list {
+    html.push_str("</ul>\n");
+  }
+
+  html
+}
+
+/// Whether a browser following `url` will only navigate, i.e., it is relative
+/// or its scheme is `gemini`, `http`, or `https`.
+fn is_safe_link(url: &str) -> bool {
+  match url::Url::parse(url) {
+    Ok(url) => matches!(url.scheme(), "gemini" | "http" | "https"),
+    Err(url::ParseError::RelativeUrlWithoutBase) => true,
+    Err(_) => false,
+  }
+}
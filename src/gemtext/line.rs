@@ -0,0 +1,126 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::fmt::{self, Write};
+
+/// A single line, or preformatted block, of a gemtext document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Line {
+  Text(String),
+  Link {
+    url:   String,
+    label: Option<String>,
+  },
+  /// A heading of level 1 to 3.
+  Heading {
+    level: u8,
+    text:  String,
+  },
+  ListItem(String),
+  Quote(String),
+  /// A preformatted block, with an optional alt text.
+  Preformatted {
+    alt:   Option<String>,
+    lines: Vec<String>,
+  },
+}
+
+impl fmt::Display for Line {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Text(text) =>
+        if starts_with_markup(text) {
+          write!(f, " {text}")
+        } else {
+          f.write_str(text)
+        },
+      Self::Link {
+        url,
+        label,
+      } => {
+        write!(f, "=> {}", link_url(url))?;
+
+        match label {
+          Some(label) if !label.is_empty() =>
+            write!(f, " {}", single_line(label)),
+          _ => Ok(()),
+        }
+      }
+      Self::Heading {
+        level,
+        text,
+      } =>
+        write!(
+          f,
+          "{} {}",
+          "#".repeat(usize::from((*level).clamp(1, 3))),
+          single_line(text)
+        ),
+      Self::ListItem(text) => write!(f, "* {}", single_line(text)),
+      Self::Quote(text) => write!(f, "> {}", single_line(text)),
+      Self::Preformatted {
+        alt,
+        lines,
+      } => {
+        writeln!(
+          f,
+          "```{}",
+          alt.as_deref().map_or(String::new(), single_line)
+        )?;
+
+        for line in lines {
+          // A line opening with a toggle would end the block early.
+          if line.starts_with("```") {
+            writeln!(f, " {line}")?;
+          } else {
+            writeln!(f, "{line}")?;
+          }
+        }
+
+        f.write_str("```")
+      }
+    }
+  }
+}
+
+/// Whether a text line would be mistaken for another line type.
+fn starts_with_markup(text: &str) -> bool {
+  ["=>", "#", "*", ">", "```"]
+    .iter()
+    .any(|markup| text.starts_with(markup))
+}
+
+fn single_line(text: &str) -> String { text.replace(['\r', '\n'], " ") }
+
+/// Percent-encode the spaces, which would end the URL early, and control
+/// characters, such as line breaks, of a link's URL.
+fn link_url(url: &str) -> String {
+  let mut encoded = String::with_capacity(url.len());
+
+  for character in url.chars() {
+    if character == ' ' || character.is_control() {
+      for byte in character.encode_utf8(&mut [0; 4]).bytes() {
+        // Writing to a `String` can not fail.
+        let _ = write!(encoded, "%{byte:02X}");
+      }
+    } else {
+      encoded.push(character);
+    }
+  }
+
+  encoded
+}
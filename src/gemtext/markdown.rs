@@ -0,0 +1,565 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::HashMap;
+
+use super::{Document, Line};
+
+/// Convert Markdown into gemtext.
+///
+/// Headings deeper than three levels become level three headings, list items
+/// of any kind become gemtext list items, and fenced or indented code blocks
+/// and tables become preformatted blocks. Inline links and images are
+/// hoisted into link lines following the block they appear in, leaving their
+/// labels in place, and emphasis and code span markers are removed.
+///
+/// # Examples
+///
+/// ```rust
+/// let document = windmark::gemtext::from_markdown(
+///   "## Hello\n\nRead [my gemlog](/gemlog/) for **more**.",
+/// );
+///
+/// assert_eq!(
+///   document.to_string(),
+///   "## Hello\n\nRead my gemlog for more.\n=> /gemlog/ my gemlog\n"
+/// );
+/// ```
+#[must_use]
+pub fn from_markdown(markdown: &str) -> Document {
+  let (lines, references) = references(markdown);
+  let mut converter = Converter {
+    document: Document::new(),
+    references,
+    paragraph: Vec::new(),
+    links: Vec::new(),
+  };
+
+  converter.convert(&mut lines.into_iter().peekable());
+  converter.flush();
+
+  // Blocks are separated by a blank line, so the last one is left trailing.
+  let mut lines = converter.document.lines().to_vec();
+
+  if lines.last() == Some(&Line::Text(String::new())) {
+    lines.pop();
+  }
+
+  let mut document = Document::new();
+
+  for line in lines {
+    document.line(line);
+  }
+
+  document
+}
+
+type Lines<'a> = std::iter::Peekable<std::vec::IntoIter<&'a str>>;
+
+struct Converter {
+  document:   Document,
+  references: HashMap<String, String>,
+  /// The lines of the paragraph being read.
+  paragraph:  Vec<String>,
+  /// The links of the block being converted, as URLs and labels.
+  links:      Vec<(String, String)>,
+}
+
+impl Converter {
+  fn convert(&mut self, lines: &mut Lines<'_>) {
+    while let Some(line) = lines.next() {
+      let trimmed = line.trim();
+
+      if let Some(fence) = fence(trimmed) {
+        self.flush();
+        self.fenced(fence, trimmed, lines);
+      } else if trimmed.is_empty() || is_rule(trimmed) {
+        self.flush();
+      } else if let Some((level, text)) = heading(trimmed) {
+        self.flush();
+        self.inline_block(text, |text| {
+          Line::Heading {
+            level,
+            text,
+          }
+        });
+      } else if !self.paragraph.is_empty()
+        && (is_underline(trimmed, '=') || is_underline(trimmed, '-'))
+      {
+        let level = if trimmed.starts_with('=') { 1 } else { 2 };
+        let text = self.paragraph.join(" ");
+
+        self.paragraph.clear();
+        self.inline_block(&text, |text| {
+          Line::Heading {
+            level,
+            text,
+          }
+        });
+      } else if let Some(item) = list_item(trimmed) {
+        self.flush();
+        self.list(item, lines);
+      } else if let Some(quote) = trimmed.strip_prefix('>') {
+        self.flush();
+        self.quote(quote, lines);
+      } else if trimmed.starts_with('|') {
+        self.flush();
+        self.table(trimmed, lines);
+      } else if self.paragraph.is_empty()
+        && (line.starts_with("    ") || line.starts_with('\t'))
+      {
+        self.indented(line, lines);
+      } else {
+        self.paragraph.push(trimmed.to_string());
+      }
+    }
+  }
+
+  fn fenced(&mut self, fence: &str, opening: &str, lines: &mut Lines<'_>) {
+    let alt = opening.trim_start_matches(fence.chars().next().unwrap_or('`'));
+    let mut code = Vec::new();
+
+    for line in lines.by_ref() {
+      if line.trim().starts_with(fence) {
+        break;
+      }
+
+      code.push(line.to_string());
+    }
+
+    self.block(Line::Preformatted {
+      alt:   Some(alt.trim().to_string()).filter(|alt| !alt.is_empty()),
+      lines: code,
+    });
+  }
+
+  /// Convert the items of a list, whose links follow the whole list.
+  fn list(&mut self, item: &str, lines: &mut Lines<'_>) {
+    let mut item = item.to_string();
+
+    loop {
+      // Indented lines continue the item.
+      while let Some(next) = lines.peek() {
+        if next.starts_with([' ', '\t'])
+          && !next.trim().is_empty()
+          && list_item(next.trim()).is_none()
+        {
+          item.push(' ');
+          item.push_str(next.trim());
+          lines.next();
+        } else {
+          break;
+        }
+      }
+
+      let converted = self.inline(&item);
+
+      self.document.list_item(converted);
+
+      match lines.peek().and_then(|next| list_item(next.trim())) {
+        Some(next) => {
+          item = next.to_string();
+          lines.next();
+        }
+        None => break,
+      }
+    }
+
+    self.finish_block();
+  }
+
+  fn quote(&mut self, quote: &str, lines: &mut Lines<'_>) {
+    let mut quote = quote.trim().to_string();
+
+    while let Some(next) =
+      lines.peek().and_then(|next| next.trim().strip_prefix('>'))
+    {
+      if next.trim().is_empty() {
+        break;
+      }
+
+      quote.push(' ');
+      quote.push_str(next.trim());
+      lines.next();
+    }
+
+    self.inline_block(&quote, Line::Quote);
+  }
+
+  fn table(&mut self, row: &str, lines: &mut Lines<'_>) {
+    let mut table = vec![row.to_string()];
+
+    while let Some(next) =
+      lines.peek().filter(|next| next.trim().starts_with('|'))
+    {
+      table.push(next.trim().to_string());
+      lines.next();
+    }
+
+    self.block(Line::Preformatted {
+      alt:   Some("table".to_string()),
+      lines: table,
+    });
+  }
+
+  fn indented(&mut self, line: &str, lines: &mut Lines<'_>) {
+    let mut code = vec![unindent(line).to_string()];
+
+    while let Some(next) = lines.peek() {
+      if next.starts_with("    ") || next.starts_with('\t') {
+        code.push(unindent(next).to_string());
+        lines.next();
+      } else {
+        break;
+      }
+    }
+
+    self.block(Line::Preformatted {
+      alt:   None,
+      lines: code,
+    });
+  }
+
+  /// Convert the paragraph being read, if any.
+  fn flush(&mut self) {
+    if self.paragraph.is_empty() {
+      return;
+    }
+
+    let text = self.paragraph.join(" ");
+
+    self.paragraph.clear();
+
+    let converted = self.inline(&text);
+
+    // A paragraph of nothing but a link becomes just a link line.
+    if !(self.links.len() == 1 && self.links[0].1 == converted) {
+      self.document.text(converted);
+    }
+
+    self.finish_block();
+  }
+
+  /// Append a block whose text may contain inline markup.
+  fn inline_block(&mut self, text: &str, line: impl FnOnce(String) -> Line) {
+    let converted = self.inline(text);
+
+    self.document.line(line(converted));
+    self.finish_block();
+  }
+
+  fn block(&mut self, line: Line) {
+    self.document.line(line);
+    self.finish_block();
+  }
+
+  /// Append the links hoisted from the last block, followed by a blank line.
+  fn finish_block(&mut self) {
+    for (url, label) in self.links.drain(..) {
+      self.document.link(url, label);
+    }
+
+    self.document.blank();
+  }
+
+  /// Remove the inline markup of `text`, collecting its links.
+  fn inline(&mut self, text: &str) -> String {
+    let characters = text.chars().collect::<Vec<_>>();
+    let mut output = String::new();
+    let mut index = 0;
+
+    while index < characters.len() {
+      let character = characters[index];
+
+      match character {
+        '\\'
+          if characters
+            .get(index + 1)
+            .is_some_and(char::is_ascii_punctuation) =>
+        {
+          output.push(characters[index + 1]);
+          index += 2;
+        }
+        '`' => {
+          let ticks = count(&characters[index..], '`');
+          let close = (index + ticks..characters.len())
+            .find(|&end| count(&characters[end..], '`') == ticks);
+
+          if let Some(close) = close {
+            output.extend(&characters[index + ticks..close]);
+            index = close + ticks;
+          } else {
+            output.extend(&characters[index..index + ticks]);
+            index += ticks;
+          }
+        }
+        '!' if characters.get(index + 1) == Some(&'[') => {
+          if let Some((label, url, end)) = self.link(&characters, index + 1) {
+            let label = self.inline(&label);
+
+            output.push_str(&label);
+            self
+              .links
+              .push((url.clone(), if label.is_empty() { url } else { label }));
+            index = end;
+          } else {
+            output.push(character);
+            index += 1;
+          }
+        }
+        '[' =>
+          if let Some((label, url, end)) = self.link(&characters, index) {
+            let label = self.inline(&label);
+
+            output.push_str(&label);
+            self.links.push((url, label));
+            index = end;
+          } else {
+            output.push(character);
+            index += 1;
+          },
+        '<' => {
+          let end = characters[index..].iter().position(|c| *c == '>');
+          let url = end
+            .map(|end| {
+              characters[index + 1..index + end]
+                .iter()
+                .collect::<String>()
+            })
+            .filter(|url| {
+              url.contains(':') && !url.contains(char::is_whitespace)
+            });
+
+          if let (Some(end), Some(url)) = (end, url) {
+            output.push_str(&url);
+            self.links.push((url.clone(), url));
+            index += end + 1;
+          } else {
+            output.push(character);
+            index += 1;
+          }
+        }
+        '*' | '_' => {
+          let run = count(&characters[index..], character);
+          let before = index.checked_sub(1).map(|before| characters[before]);
+          let after = characters.get(index + run).copied();
+          let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+          let is_space = |c: Option<char>| matches!(c, None | Some(' ' | '\t'));
+
+          // Markers within a word, like in `snake_case`, or standing alone,
+          // like in `2 * 3`, are left alone.
+          if (character == '_' && is_word(before) && is_word(after))
+            || (is_space(before) && is_space(after))
+          {
+            output.extend(&characters[index..index + run]);
+          }
+
+          index += run;
+        }
+        _ => {
+          output.push(character);
+          index += 1;
+        }
+      }
+    }
+
+    output
+  }
+
+  /// Parse an inline or reference link starting at the `[` at `start`,
+  /// returning its label, URL, and the index following it.
+  fn link(
+    &self,
+    characters: &[char],
+    start: usize,
+  ) -> Option<(String, String, usize)> {
+    let close = matching(characters, start, '[', ']')?;
+    let label = characters[start + 1..close].iter().collect::<String>();
+
+    match characters.get(close + 1) {
+      Some('(') => {
+        let end = matching(characters, close + 1, '(', ')')?;
+        let destination = characters[close + 2..end].iter().collect::<String>();
+        // A title may follow the URL, e.g. `[label](url "title")`.
+        let url = destination
+          .split_whitespace()
+          .next()
+          .unwrap_or_default()
+          .trim_start_matches('<')
+          .trim_end_matches('>')
+          .to_string();
+
+        Some((label, url, end + 1))
+      }
+      Some('[') => {
+        let end = matching(characters, close + 1, '[', ']')?;
+        let reference = characters[close + 2..end].iter().collect::<String>();
+        let reference = if reference.is_empty() {
+          &label
+        } else {
+          &reference
+        };
+
+        self
+          .references
+          .get(&reference.to_lowercase())
+          .map(|url| (label.clone(), url.clone(), end + 1))
+      }
+      _ =>
+        self
+          .references
+          .get(&label.to_lowercase())
+          .map(|url| (label.clone(), url.clone(), close + 1)),
+    }
+  }
+}
+
+/// Split the reference definitions, like `[label]: url`, from the rest of
+/// `markdown`.
+fn references(markdown: &str) -> (Vec<&str>, HashMap<String, String>) {
+  let mut references = HashMap::new();
+  let mut in_fence = None;
+  let lines = markdown
+    .lines()
+    .filter(|line| {
+      let trimmed = line.trim();
+
+      if let Some(fence) = fence(trimmed) {
+        match in_fence {
+          Some(open) if trimmed.starts_with(open) => in_fence = None,
+          None => in_fence = Some(fence),
+          Some(_) => {}
+        }
+
+        return true;
+      }
+
+      if in_fence.is_some() {
+        return true;
+      }
+
+      let definition = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once("]:"))
+        .filter(|(label, _)| !label.is_empty() && !label.starts_with('^'));
+
+      let Some((label, destination)) = definition else {
+        return true;
+      };
+      let url = destination
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('<')
+        .trim_end_matches('>');
+
+      references.insert(label.to_lowercase(), url.to_string());
+
+      false
+    })
+    .collect();
+
+  (lines, references)
+}
+
+/// The fence which opens a fenced code block, if `line` is one.
+fn fence(line: &str) -> Option<&'static str> {
+  if line.starts_with("```") {
+    Some("```")
+  } else if line.starts_with("~~~") {
+    Some("~~~")
+  } else {
+    None
+  }
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+  let level = line.chars().take_while(|c| *c == '#').count();
+  let text = &line[level..];
+
+  if !(1..=6).contains(&level) || !(text.is_empty() || text.starts_with(' ')) {
+    return None;
+  }
+
+  let level = u8::try_from(level.min(3)).unwrap_or(3);
+
+  Some((level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_underline(line: &str, character: char) -> bool {
+  !line.is_empty() && line.chars().all(|c| c == character)
+}
+
+fn is_rule(line: &str) -> bool {
+  ['-', '*', '_'].iter().any(|rule| {
+    line.chars().filter(|c| c == rule).count() >= 3
+      && line.chars().all(|c| c == *rule || c == ' ')
+  })
+}
+
+fn list_item(line: &str) -> Option<&str> {
+  if let Some(item) = line
+    .strip_prefix(['-', '*', '+'])
+    .and_then(|item| item.strip_prefix(' '))
+  {
+    return Some(item.trim());
+  }
+
+  let digits = line.chars().take_while(char::is_ascii_digit).count();
+
+  (digits > 0)
+    .then(|| &line[digits..])
+    .and_then(|rest| rest.strip_prefix(['.', ')']))
+    .and_then(|item| item.strip_prefix(' '))
+    .map(str::trim)
+}
+
+fn unindent(line: &str) -> &str {
+  line
+    .strip_prefix('\t')
+    .or_else(|| line.strip_prefix("    "))
+    .unwrap_or(line)
+}
+
+/// The number of consecutive `character`s at the start of `characters`.
+fn count(characters: &[char], character: char) -> usize {
+  characters.iter().take_while(|c| **c == character).count()
+}
+
+/// The index of the `close` which matches the `open` at `start`.
+fn matching(
+  characters: &[char],
+  start: usize,
+  open: char,
+  close: char,
+) -> Option<usize> {
+  let mut depth = 0;
+
+  for (index, character) in characters.iter().enumerate().skip(start) {
+    if *character == open {
+      depth += 1;
+    } else if *character == close {
+      depth -= 1;
+
+      if depth == 0 {
+        return Some(index);
+      }
+    }
+  }
+
+  None
+}
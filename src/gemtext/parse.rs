@@ -0,0 +1,124 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::Line;
+
+/// Parse gemtext into its lines.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::gemtext::{parse, Line};
+///
+/// assert_eq!(
+///   parse("# Hello\n=> /about About me"),
+///   vec![
+///     Line::Heading {
+///       level: 1,
+///       text:  "Hello".to_string(),
+///     },
+///     Line::Link {
+///       url:   "/about".to_string(),
+///       label: Some("About me".to_string()),
+///     },
+///   ]
+/// );
+/// ```
+#[must_use]
+pub fn parse(gemtext: &str) -> Vec<Line> {
+  let mut lines = Vec::new();
+  let mut preformatted: Option<(Option<String>, Vec<String>)> = None;
+
+  for line in gemtext.lines() {
+    if let Some(alt) = line.strip_prefix("```") {
+      match preformatted.take() {
+        Some((alt, block)) =>
+          lines.push(Line::Preformatted {
+            alt,
+            lines: block,
+          }),
+        None => preformatted = Some((non_empty(alt), Vec::new())),
+      }
+
+      continue;
+    }
+
+    if let Some((_, block)) = &mut preformatted {
+      block.push(line.to_string());
+
+      continue;
+    }
+
+    lines.push(parse_line(line));
+  }
+
+  // An unterminated block runs to the end of the document.
+  if let Some((alt, block)) = preformatted {
+    lines.push(Line::Preformatted {
+      alt,
+      lines: block,
+    });
+  }
+
+  lines
+}
+
+fn parse_line(line: &str) -> Line {
+  if let Some(target) = line.strip_prefix("=>") {
+    let target = target.trim_start();
+    let (url, label) = target
+      .split_once(char::is_whitespace)
+      .unwrap_or((target, ""));
+
+    if !url.is_empty() {
+      return Line::Link {
+        url:   url.to_string(),
+        label: non_empty(label),
+      };
+    }
+  }
+
+  for level in (1..=3).rev() {
+    if let Some(text) = line.strip_prefix(&"#".repeat(level)) {
+      return Line::Heading {
+        #[allow(clippy::cast_possible_truncation)]
+        level: level as u8,
+        text: text.trim().to_string(),
+      };
+    }
+  }
+
+  if let Some(text) = line.strip_prefix("* ") {
+    return Line::ListItem(text.trim().to_string());
+  }
+
+  if let Some(text) = line.strip_prefix('>') {
+    return Line::Quote(text.trim().to_string());
+  }
+
+  Line::Text(line.to_string())
+}
+
+fn non_empty(text: &str) -> Option<String> {
+  let text = text.trim();
+
+  if text.is_empty() {
+    None
+  } else {
+    Some(text.to_string())
+  }
+}
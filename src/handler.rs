@@ -16,11 +16,23 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 mod hooks;
+mod middleware;
 mod partial;
 mod response;
 
 pub use self::{
-  hooks::{PostRouteHook, PreRouteHook},
-  partial::Partial,
-  response::{ErrorResponse, RouteResponse},
+  hooks::{
+    AcceptErrorHook,
+    CertificateExpiryHook,
+    ConnectionHook,
+    GemtextHook,
+    HandshakeErrorHook,
+    ListenHook,
+    PostRouteHook,
+    PreRouteHook,
+    RejectionHook,
+  },
+  middleware::{Middleware, Next},
+  partial::{Partial, PartialInfo, PartialScope},
+  response::{ErrorResponse, NotFoundResponse, RouteResponse},
 };
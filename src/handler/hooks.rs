@@ -15,8 +15,22 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+mod accept_error;
+mod certificate_expiry;
+mod connection;
+mod gemtext;
+mod handshake_error;
+mod listen;
 mod post_route;
 mod pre_route;
+mod rejection;
 
+pub use accept_error::AcceptErrorHook;
+pub use certificate_expiry::CertificateExpiryHook;
+pub use connection::ConnectionHook;
+pub use gemtext::GemtextHook;
+pub use handshake_error::HandshakeErrorHook;
+pub use listen::ListenHook;
 pub use post_route::PostRouteHook;
 pub use pre_route::PreRouteHook;
+pub use rejection::RejectionHook;
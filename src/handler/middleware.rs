@@ -0,0 +1,77 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{context::RouteContext, response::Response};
+
+type BoxFuture = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+/// Code run around a route handler, which may inspect or modify the
+/// `RouteContext`, answer on the handler's behalf, or alter the `Response`
+/// produced by the rest of the chain.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+  async fn call(&self, context: RouteContext, next: Next) -> Response;
+}
+
+#[async_trait]
+impl<T, F> Middleware for T
+where
+  T: Fn(RouteContext, Next) -> F + Send + Sync,
+  F: Future<Output = Response> + Send + 'static,
+{
+  async fn call(&self, context: RouteContext, next: Next) -> Response {
+    (*self)(context, next).await
+  }
+}
+
+/// The remainder of a middleware chain, ending with the route handler.
+pub struct Next {
+  run: Box<dyn FnOnce(RouteContext) -> BoxFuture + Send>,
+}
+
+impl Next {
+  pub(crate) fn new(
+    run: impl FnOnce(RouteContext) -> BoxFuture + Send + 'static,
+  ) -> Self {
+    Self {
+      run: Box::new(run)
+    }
+  }
+
+  /// Wrap `endpoint` in `middleware`, the first of which runs outermost.
+  pub(crate) fn chain(
+    middleware: &[Arc<dyn Middleware>],
+    endpoint: Self,
+  ) -> Self {
+    middleware.iter().rev().fold(endpoint, |next, middleware| {
+      let middleware = middleware.clone();
+
+      Self::new(move |context| {
+        Box::pin(async move { middleware.call(context, next).await })
+      })
+    })
+  }
+
+  /// Run the rest of the chain.
+  pub async fn run(self, context: RouteContext) -> Response {
+    (self.run)(context).await
+  }
+}
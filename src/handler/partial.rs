@@ -15,6 +15,8 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::sync::Arc;
+
 use crate::context::RouteContext;
 
 #[allow(clippy::module_name_repetitions)]
@@ -27,3 +29,69 @@ where T: FnMut(RouteContext) -> String + Send + Sync
 {
   fn call(&mut self, context: RouteContext) -> String { (*self)(context) }
 }
+
+/// Which requests a header or footer is displayed on.
+///
+/// A prefix, such as `"/blog"`, matches itself and every path beneath it, and
+/// a closure is called with the context of each request, which includes its
+/// [`response`](crate::context::RouteContext::response).
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{context::RouteContext, handler::PartialScope};
+///
+/// let blog = PartialScope::from("/blog");
+/// let gemtext = PartialScope::from(|context: &RouteContext| {
+///   context.url.path().ends_with(".gmi")
+/// });
+/// ```
+#[derive(Clone)]
+pub enum PartialScope {
+  Everywhere,
+  Beneath(String),
+  Matching(Arc<dyn Fn(&RouteContext) -> bool + Send + Sync>),
+}
+
+impl std::fmt::Debug for PartialScope {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Everywhere => write!(f, "Everywhere"),
+      Self::Beneath(prefix) => f.debug_tuple("Beneath").field(prefix).finish(),
+      Self::Matching(_) => write!(f, "Matching(..)"),
+    }
+  }
+}
+
+impl PartialScope {
+  pub(crate) fn includes(&self, context: &RouteContext) -> bool {
+    match self {
+      Self::Everywhere => true,
+      Self::Beneath(prefix) =>
+        crate::router::is_beneath(context.url.path(), prefix),
+      Self::Matching(predicate) => predicate(context),
+    }
+  }
+}
+
+impl From<&str> for PartialScope {
+  fn from(prefix: &str) -> Self { Self::Beneath(prefix.to_string()) }
+}
+
+impl From<String> for PartialScope {
+  fn from(prefix: String) -> Self { Self::Beneath(prefix) }
+}
+
+impl<T> From<T> for PartialScope
+where T: Fn(&RouteContext) -> bool + Send + Sync + 'static
+{
+  fn from(predicate: T) -> Self { Self::Matching(Arc::new(predicate)) }
+}
+
+/// A description of a header or footer added to a `Router`.
+#[derive(Clone, Debug)]
+pub struct PartialInfo {
+  /// Where the partial was added from, e.g., within a module.
+  pub location: &'static std::panic::Location<'static>,
+  pub scope:    PartialScope,
+}
@@ -18,7 +18,9 @@
 #![allow(clippy::module_name_repetitions)]
 
 mod error;
+mod not_found;
 mod route;
 
 pub use error::ErrorResponse;
+pub use not_found::NotFoundResponse;
 pub use route::RouteResponse;
@@ -0,0 +1,272 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Trust-on-first-use records of the certificates clients identify with
+
+#[cfg(feature = "file-store")]
+use std::path::Path;
+use std::{io, sync::Arc};
+
+use openssl::{hash::MessageDigest, nid::Nid, x509::X509Ref};
+
+use crate::{
+  store::{KeyValueStore, MemoryStore},
+  utilities::hex,
+};
+
+/// The prefix of the keys known clients are stored under.
+const PREFIX: &str = "known-clients/";
+
+/// The certificate first recorded for an identity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KnownClient {
+  pub identity:    String,
+  /// The hexadecimal SHA-256 fingerprint of the certificate.
+  pub fingerprint: String,
+  /// When the certificate was recorded, in seconds since the Unix epoch.
+  pub first_seen:  u64,
+}
+
+/// How a client certificate compares to the one recorded for its identity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Recognition {
+  /// The identity had not been seen before, and the certificate has been
+  /// recorded for it.
+  New,
+  /// The certificate is the one recorded for the identity.
+  Known(KnownClient),
+  /// The identity was recorded with a different certificate, which is left in
+  /// place until the new one is trusted with [`KnownClients::trust`].
+  Changed(KnownClient),
+}
+
+/// The certificates clients were first seen identifying with.
+///
+/// Gemini clients identify themselves with self-signed certificates, so a
+/// capsule can only trust the first certificate it sees for an identity. A
+/// `KnownClients` records that certificate and flags any other certificate
+/// presented for the same identity, so that a capsule can ask the client to
+/// prove who it is before accepting a rotated certificate.
+///
+/// Records are kept in a [`KeyValueStore`], under keys prefixed with
+/// `known-clients/`. Every clone shares the same records.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{
+///   known_clients::{KnownClients, Recognition},
+///   response::Response,
+/// };
+///
+/// let known_clients = KnownClients::new();
+///
+/// windmark::router::Router::new().mount("/account", move |context| {
+///   let known_clients = known_clients.clone();
+///
+///   async move {
+///     let Some(certificate) = &context.certificate else {
+///       return Response::client_certificate_required("Who are you?");
+///     };
+///
+///     match known_clients.recognise(certificate).await {
+///       Ok(Some(Recognition::New)) => Response::success("Welcome!"),
+///       Ok(Some(Recognition::Known(_))) => Response::success("Welcome back!"),
+///       Ok(Some(Recognition::Changed(_))) =>
+///         Response::certificate_not_valid("That name is taken."),
+///       Ok(None) =>
+///         Response::certificate_not_valid("Your certificate has no name."),
+///       Err(_) => Response::temporary_failure("Please try again later."),
+///     }
+///   }
+/// });
+/// ```
+#[derive(Clone)]
+pub struct KnownClients {
+  store: Arc<dyn KeyValueStore>,
+}
+
+impl Default for KnownClients {
+  fn default() -> Self { Self::new() }
+}
+
+impl KnownClients {
+  /// Create an empty, in-memory set of known clients.
+  #[must_use]
+  pub fn new() -> Self { Self::with_store(Arc::new(MemoryStore::new())) }
+
+  /// Load known clients from the file at `path`, as described by
+  /// [`FileStore`](crate::store::FileStore).
+  ///
+  /// Only available with the `file-store` feature.
+  ///
+  /// # Errors
+  ///
+  /// if the file exists but could not be read.
+  #[cfg(feature = "file-store")]
+  pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+    Ok(Self::with_store(Arc::new(crate::store::FileStore::open(
+      path,
+    )?)))
+  }
+
+  /// Keep known clients in `store`, which may be shared with other users.
+  pub fn with_store(store: Arc<dyn KeyValueStore>) -> Self {
+    Self {
+      store,
+    }
+  }
+
+  /// The identity a certificate claims, its subject's common name.
+  #[must_use]
+  pub fn identity(certificate: &X509Ref) -> Option<String> {
+    certificate
+      .subject_name()
+      .entries_by_nid(Nid::COMMONNAME)
+      .next()
+      .and_then(|entry| {
+        String::from_utf8(entry.data().as_slice().to_vec()).ok()
+      })
+  }
+
+  /// The hexadecimal SHA-256 fingerprint of a certificate.
+  #[must_use]
+  pub fn fingerprint(certificate: &X509Ref) -> Option<String> {
+    certificate
+      .digest(MessageDigest::sha256())
+      .ok()
+      .map(|digest| hex(&digest))
+  }
+
+  /// Compare `certificate` to the one recorded for the identity it claims,
+  /// recording it if the identity is new. `None` if the certificate claims no
+  /// identity.
+  ///
+  /// # Errors
+  ///
+  /// if the store could not be read or written.
+  pub async fn recognise(
+    &self,
+    certificate: &X509Ref,
+  ) -> io::Result<Option<Recognition>> {
+    let Some(identity) = Self::identity(certificate) else {
+      return Ok(None);
+    };
+
+    self.recognise_as(&identity, certificate).await
+  }
+
+  /// Compare `certificate` to the one recorded for `identity`, e.g., an
+  /// account name, recording it if the identity is new. `None` if the
+  /// certificate could not be fingerprinted.
+  ///
+  /// # Errors
+  ///
+  /// if the store could not be read or written.
+  pub async fn recognise_as(
+    &self,
+    identity: &str,
+    certificate: &X509Ref,
+  ) -> io::Result<Option<Recognition>> {
+    let Some(fingerprint) = Self::fingerprint(certificate) else {
+      return Ok(None);
+    };
+
+    loop {
+      match self.get(identity).await? {
+        Some(known) if known.fingerprint == fingerprint =>
+          return Ok(Some(Recognition::Known(known))),
+        Some(known) => return Ok(Some(Recognition::Changed(known))),
+        // Only the first of several concurrent first connections records its
+        // certificate; the others compare against it on the next iteration.
+        None =>
+          if self
+            .store
+            .set_if_absent(&format!("{PREFIX}{identity}"), entry(&fingerprint))
+            .await?
+          {
+            return Ok(Some(Recognition::New));
+          },
+      }
+    }
+  }
+
+  /// Record `certificate` for `identity`, replacing any certificate recorded
+  /// before, e.g., once a client has proven a rotated certificate is theirs.
+  ///
+  /// # Errors
+  ///
+  /// if the store could not be written.
+  pub async fn trust(
+    &self,
+    identity: &str,
+    certificate: &X509Ref,
+  ) -> io::Result<()> {
+    match Self::fingerprint(certificate) {
+      Some(fingerprint) => self.record(identity, &fingerprint).await,
+      None => Ok(()),
+    }
+  }
+
+  /// The certificate recorded for `identity`, if any.
+  ///
+  /// # Errors
+  ///
+  /// if the store could not be read.
+  pub async fn get(&self, identity: &str) -> io::Result<Option<KnownClient>> {
+    let Some(value) = self.store.get(&format!("{PREFIX}{identity}")).await?
+    else {
+      return Ok(None);
+    };
+    let value = String::from_utf8_lossy(&value);
+    let (fingerprint, first_seen) =
+      value.split_once(' ').unwrap_or((&value, "0"));
+
+    Ok(Some(KnownClient {
+      identity:    identity.to_string(),
+      fingerprint: fingerprint.to_string(),
+      first_seen:  first_seen.parse().unwrap_or_default(),
+    }))
+  }
+
+  /// Remove the record of `identity`, so that the next certificate presented
+  /// for it is recorded. Returns whether the identity was known.
+  ///
+  /// # Errors
+  ///
+  /// if the store could not be written.
+  pub async fn forget(&self, identity: &str) -> io::Result<bool> {
+    self.store.remove(&format!("{PREFIX}{identity}")).await
+  }
+
+  async fn record(&self, identity: &str, fingerprint: &str) -> io::Result<()> {
+    self
+      .store
+      .set(&format!("{PREFIX}{identity}"), entry(fingerprint))
+      .await
+  }
+}
+
+/// The stored record of a certificate with `fingerprint`, first seen now.
+fn entry(fingerprint: &str) -> Vec<u8> {
+  let first_seen = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs();
+
+  format!("{fingerprint} {first_seen}").into_bytes()
+}
@@ -29,13 +29,24 @@
 #![doc = include_str!("../README.md")]
 #![recursion_limit = "128"]
 
+#[cfg(feature = "client")]
+pub mod client;
 pub mod context;
+mod error;
+pub mod feed;
+pub mod gemtext;
 pub mod handler;
+pub mod known_clients;
+pub mod mime;
 pub mod module;
 #[cfg(feature = "prelude")]
 pub mod prelude;
 pub mod response;
 pub mod router;
+pub mod secret;
+pub mod store;
+#[cfg(feature = "templates")]
+pub mod template;
 pub mod utilities;
 
 #[macro_use]
@@ -43,5 +54,6 @@ extern crate log;
 
 #[cfg(feature = "async-std")]
 pub use async_std::main;
+pub use error::Error;
 #[cfg(feature = "tokio")]
 pub use tokio::main;
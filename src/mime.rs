@@ -0,0 +1,161 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A registry mapping file extensions to MIME types
+
+use std::{
+  collections::HashMap,
+  path::Path,
+  sync::{Arc, OnceLock, PoisonError, RwLock},
+};
+
+const DEFAULTS: &[(&str, &str)] = &[
+  ("gmi", "text/gemini"),
+  ("gemini", "text/gemini"),
+  ("txt", "text/plain"),
+  ("md", "text/markdown"),
+  ("html", "text/html"),
+  ("htm", "text/html"),
+  ("css", "text/css"),
+  ("csv", "text/csv"),
+  ("xml", "text/xml"),
+  ("atom", "application/atom+xml"),
+  ("rss", "application/rss+xml"),
+  ("json", "application/json"),
+  ("js", "text/javascript"),
+  ("pdf", "application/pdf"),
+  ("zip", "application/zip"),
+  ("gz", "application/gzip"),
+  ("tar", "application/x-tar"),
+  ("png", "image/png"),
+  ("jpg", "image/jpeg"),
+  ("jpeg", "image/jpeg"),
+  ("gif", "image/gif"),
+  ("webp", "image/webp"),
+  ("svg", "image/svg+xml"),
+  ("ico", "image/vnd.microsoft.icon"),
+  ("mp3", "audio/mpeg"),
+  ("ogg", "audio/ogg"),
+  ("flac", "audio/flac"),
+  ("wav", "audio/wav"),
+  ("mp4", "video/mp4"),
+  ("webm", "video/webm"),
+];
+
+/// A shared mapping of file extensions to MIME types.
+///
+/// The default registry knows Gemini documents and a set of common text,
+/// image, audio, video, and archive formats. Extensions are matched without
+/// regard to case.
+///
+/// Cloning a `MimeTypes` is cheap and every clone observes changes.
+///
+/// # Examples
+///
+/// ```rust
+/// let mime_types = windmark::mime::MimeTypes::default();
+///
+/// mime_types.insert("ts", "video/mp2t");
+///
+/// assert_eq!(mime_types.get("TS").as_deref(), Some("video/mp2t"));
+/// assert_eq!(
+///   mime_types.for_path("index.gmi").as_deref(),
+///   Some("text/gemini")
+/// );
+/// ```
+#[derive(Clone)]
+pub struct MimeTypes {
+  types: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl Default for MimeTypes {
+  fn default() -> Self {
+    let types = Self::empty();
+
+    for (extension, mime) in DEFAULTS {
+      types.insert(*extension, *mime);
+    }
+
+    types
+  }
+}
+
+impl MimeTypes {
+  /// Create a registry without any mappings.
+  #[must_use]
+  pub fn empty() -> Self {
+    Self {
+      types: Arc::new(RwLock::new(HashMap::new())),
+    }
+  }
+
+  /// Map `extension`, with or without its leading dot, to `mime`, replacing
+  /// any existing mapping.
+  pub fn insert(
+    &self,
+    extension: impl AsRef<str>,
+    mime: impl Into<String> + AsRef<str>,
+  ) {
+    self
+      .types
+      .write()
+      .unwrap_or_else(PoisonError::into_inner)
+      .insert(Self::normalise(extension.as_ref()), mime.into());
+  }
+
+  /// Remove the mapping of `extension`, returning its MIME type.
+  #[allow(clippy::must_use_candidate)]
+  pub fn remove(&self, extension: impl AsRef<str>) -> Option<String> {
+    self
+      .types
+      .write()
+      .unwrap_or_else(PoisonError::into_inner)
+      .remove(&Self::normalise(extension.as_ref()))
+  }
+
+  /// The MIME type `extension` maps to.
+  #[must_use]
+  pub fn get(&self, extension: impl AsRef<str>) -> Option<String> {
+    self
+      .types
+      .read()
+      .unwrap_or_else(PoisonError::into_inner)
+      .get(&Self::normalise(extension.as_ref()))
+      .cloned()
+  }
+
+  /// The MIME type the extension of `path` maps to.
+  #[must_use]
+  pub fn for_path(&self, path: impl AsRef<Path>) -> Option<String> {
+    path
+      .as_ref()
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .and_then(|extension| self.get(extension))
+  }
+
+  fn normalise(extension: &str) -> String {
+    extension.trim_start_matches('.').to_ascii_lowercase()
+  }
+}
+
+/// The default registry, shared by responses built without a `Router`.
+pub(crate) fn defaults() -> &'static MimeTypes {
+  static DEFAULTS: OnceLock<MimeTypes> = OnceLock::new();
+
+  DEFAULTS.get_or_init(MimeTypes::default)
+}
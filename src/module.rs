@@ -15,9 +15,50 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+mod access_log;
+#[cfg(any(feature = "http-gateway", feature = "gopher"))]
+mod adapter;
 mod asynchronous;
+#[cfg(feature = "comments")]
+mod comments;
+#[cfg(feature = "content")]
+mod content;
+#[cfg(feature = "gopher")]
+mod gopher;
+mod health;
+#[cfg(feature = "http-gateway")]
+mod http_gateway;
+mod mirror;
+mod robots;
+#[cfg(feature = "search")]
+mod search;
+mod sitemap;
 mod sync;
+mod tarpit;
+mod watch;
 
+pub use access_log::AccessLog;
 #[allow(clippy::module_name_repetitions)]
 pub use asynchronous::AsyncModule;
+#[cfg(feature = "comments")]
+pub use comments::{Comment, Comments};
+#[cfg(feature = "content")]
+pub use content::{Content, Page};
+#[cfg(feature = "gopher")]
+pub use gopher::Gopher;
+pub use health::{Health, HealthCheck};
+#[cfg(feature = "http-gateway")]
+pub use http_gateway::HttpGateway;
+#[cfg(feature = "client")]
+pub use mirror::Upstream;
+pub use mirror::{Mirror, MirrorSink, MirroredRequest};
+pub use robots::Robots;
+#[cfg(feature = "search")]
+pub use search::{Search, SearchResult};
+pub use sitemap::Sitemap;
 pub use sync::Module;
+pub use tarpit::Tarpit;
+pub use watch::Watch;
+
+/// The result of an [`AsyncModule`] hook.
+pub type ModuleResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
@@ -0,0 +1,222 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  fs::OpenOptions,
+  io::{self, BufWriter, Write},
+  path::Path,
+  sync::mpsc,
+  time::SystemTime,
+};
+
+use openssl::hash::MessageDigest;
+
+use crate::{
+  context::HookContext,
+  module::Module,
+  utilities::{hex, timestamp},
+};
+
+/// A message to the thread which writes an access log.
+enum Message {
+  Line(String),
+  /// Flush every line sent before, then acknowledge.
+  Flush(mpsc::Sender<()>),
+}
+
+/// A `Module` which writes one line per request to stdout or a file.
+///
+/// Lines are written on a thread of their own, through a buffer which is
+/// flushed whenever no more lines are waiting, and once more on shutdown.
+///
+/// The line is produced from a format string in which the following
+/// placeholders are replaced, with `-` standing in for unavailable values:
+///
+/// - `{timestamp}`: the time the response was produced, in RFC 3339 (UTC)
+/// - `{request_id}`: the ID of the request
+/// - `{peer}`: the IP address of the peer
+/// - `{url}`: the requested URL
+/// - `{status}`: the status of the response
+/// - `{bytes}`: the size of the response body, in bytes
+/// - `{duration}`: the time taken to produce the response, in milliseconds
+/// - `{fingerprint}`: the SHA-256 fingerprint of the peer's certificate
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::module::AccessLog;
+///
+/// windmark::router::Router::new()
+///   .attach(AccessLog::stdout().format("{peer} {url} {status}"));
+/// ```
+pub struct AccessLog {
+  lines:  mpsc::Sender<Message>,
+  format: String,
+}
+
+impl AccessLog {
+  /// The format used unless another is set with [`AccessLog::format`].
+  pub const DEFAULT_FORMAT: &'static str =
+    "{timestamp} {peer} \"{url}\" {status} {bytes} {duration}ms {fingerprint}";
+
+  /// Create an access log which writes to stdout.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the thread which writes the log could not be spawned.
+  #[must_use]
+  pub fn stdout() -> Self {
+    Self {
+      lines:  spawn(io::stdout())
+        .expect("could not spawn the access log writer"),
+      format: Self::DEFAULT_FORMAT.to_string(),
+    }
+  }
+
+  /// Create an access log which appends to the file at `path`, creating it
+  /// if it does not exist.
+  ///
+  /// # Errors
+  ///
+  /// if the file could not be opened, or the thread which writes the log
+  /// could not be spawned.
+  pub fn file(path: impl AsRef<Path>) -> io::Result<Self> {
+    Ok(Self {
+      lines:  spawn(OpenOptions::new().create(true).append(true).open(path)?)?,
+      format: Self::DEFAULT_FORMAT.to_string(),
+    })
+  }
+
+  /// Set the format of each line.
+  #[must_use]
+  pub fn format(mut self, format: impl Into<String>) -> Self {
+    self.format = format.into();
+
+    self
+  }
+
+  /// Fill the format's placeholders in a single pass, so that values, such
+  /// as a URL containing `{status}`, are never expanded themselves.
+  fn line(&self, context: &HookContext) -> String {
+    let mut line = String::with_capacity(self.format.len());
+    let mut rest = self.format.as_str();
+
+    while let Some(start) = rest.find('{') {
+      line.push_str(&rest[..start]);
+      rest = &rest[start..];
+
+      let value = rest
+        .find('}')
+        .and_then(|end| Some((end, placeholder(&rest[1..end], context)?)));
+
+      if let Some((end, value)) = value {
+        line.push_str(&value.unwrap_or_else(|| "-".to_string()));
+        rest = &rest[end + 1..];
+      } else {
+        line.push('{');
+        rest = &rest[1..];
+      }
+    }
+
+    line.push_str(rest);
+
+    line
+  }
+}
+
+/// Spawn the thread which writes the lines it is sent to `output`.
+fn spawn(
+  output: impl Write + Send + 'static,
+) -> io::Result<mpsc::Sender<Message>> {
+  let (sender, receiver) = mpsc::channel();
+  let mut output = BufWriter::new(output);
+
+  std::thread::Builder::new()
+    .name("windmark-access-log".to_string())
+    .spawn(move || {
+      while let Ok(message) = receiver.recv() {
+        for message in std::iter::once(message).chain(receiver.try_iter()) {
+          match message {
+            Message::Line(line) =>
+              if let Err(e) = writeln!(output, "{line}") {
+                warn!("could not write access log: {e}");
+              },
+            Message::Flush(flushed) => {
+              flush(&mut output);
+
+              let _ = flushed.send(());
+            }
+          }
+        }
+
+        flush(&mut output);
+      }
+    })?;
+
+  Ok(sender)
+}
+
+fn flush(output: &mut impl Write) {
+  if let Err(e) = output.flush() {
+    warn!("could not flush access log: {e}");
+  }
+}
+
+/// The value of the placeholder `name`, which is `Some(None)` if it is not
+/// known for the request, or `None` if there is no such placeholder.
+#[allow(clippy::option_option)]
+fn placeholder(name: &str, context: &HookContext) -> Option<Option<String>> {
+  Some(match name {
+    "timestamp" => Some(timestamp(SystemTime::now())),
+    "request_id" => Some(context.request_id.clone()),
+    "peer" => context.peer_address.map(|peer| peer.ip().to_string()),
+    "url" => Some(context.url.to_string()),
+    "status" => context.status.map(|status| status.to_string()),
+    "bytes" => context.bytes.map(|bytes| bytes.to_string()),
+    "duration" =>
+      context
+        .elapsed
+        .map(|elapsed| format!("{:.3}", elapsed.as_secs_f64() * 1000.0)),
+    "fingerprint" =>
+      context.certificate.as_ref().and_then(|certificate| {
+        certificate
+          .digest(MessageDigest::sha256())
+          .ok()
+          .map(|digest| hex(&digest))
+      }),
+    _ => return None,
+  })
+}
+
+impl Module for AccessLog {
+  fn on_post_route(&mut self, context: HookContext) {
+    if self.lines.send(Message::Line(self.line(&context))).is_err() {
+      warn!(
+        "[{}] could not write access log, as its writer has stopped",
+        context.request_id
+      );
+    }
+  }
+
+  fn on_shutdown(&mut self) {
+    let (flushed, waiting) = mpsc::channel();
+
+    if self.lines.send(Message::Flush(flushed)).is_ok() {
+      let _ = waiting.recv();
+    }
+  }
+}
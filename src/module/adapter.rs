@@ -0,0 +1,73 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Listeners which serve a `Router`'s routes over protocols other than Gemini
+
+use std::{future::Future, net::SocketAddr};
+
+use crate::router::{runtime, Router};
+
+/// Bind a listener for an adapter to `address`.
+pub fn bind(address: &str) -> std::io::Result<runtime::TcpListener> {
+  let listener = std::net::TcpListener::bind(address)?;
+
+  listener.set_nonblocking(true)?;
+
+  runtime::listener_from_std(listener)
+}
+
+/// Accept connections on `listener` until `router` is shut down, handling
+/// each with `handle`, which is given the peer's and the listener's address.
+///
+/// Connections are admitted as the `Router`'s own are: through its guards,
+/// connection callback, and connection limits, with failures to accept
+/// handled by its accept policy.
+pub async fn serve<F, Fut>(
+  protocol: &'static str,
+  router: Router,
+  listener: runtime::TcpListener,
+  handle: F,
+) where
+  F: Fn(Router, runtime::TcpStream, SocketAddr, SocketAddr) -> Fut
+    + Send
+    + Sync
+    + 'static,
+  Fut: Future<Output = std::io::Result<()>> + Send + 'static,
+{
+  let Ok(local_address) = listener.local_addr() else {
+    return;
+  };
+  let accepting = router.accept_with(
+    listener,
+    local_address,
+    move |router, stream, peer_address, permits| {
+      let connection = handle(router, stream, peer_address, local_address);
+
+      async move {
+        let _permits = permits;
+
+        if let Err(e) = connection.await {
+          debug!("{protocol} connection error: {e}");
+        }
+      }
+    },
+  );
+
+  if let Err(e) = accepting.await {
+    error!("{protocol} stopped accepting connections: {e}");
+  }
+}
@@ -15,16 +15,41 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+use super::ModuleResult;
 use crate::context::HookContext;
 
+/// An asynchronous stateful module.
+///
+/// Hooks other than `on_attach` may fail by returning an error, which is
+/// logged along with the location the module was attached from. A failing or
+/// panicking hook does not affect other modules or the request being handled.
 #[async_trait::async_trait]
 pub trait AsyncModule: Send + Sync {
   /// Called right after the module is attached.
   async fn on_attach(&mut self, _: &mut crate::router::Router) {}
 
+  /// Called once when the `Router` starts accepting connections, with the
+  /// address of its first listener. The address of every listener is passed
+  /// to the callback set with
+  /// [`Router::set_on_listen`](crate::router::Router::set_on_listen).
+  ///
+  /// A module attached to a running `Router` is called right after
+  /// `on_attach`.
+  async fn on_start(&mut self, _: std::net::SocketAddr) -> ModuleResult {
+    Ok(())
+  }
+
+  /// Called after a shutdown requested with `Router::shutdown`, once every
+  /// open connection has been handled, or when the module is detached from a
+  /// running `Router`.
+  async fn on_shutdown(&mut self) -> ModuleResult { Ok(()) }
+
   /// Called before a route is mounted.
-  async fn on_pre_route(&mut self, _: HookContext) {}
+  async fn on_pre_route(&mut self, _: HookContext) -> ModuleResult { Ok(()) }
 
   /// Called after a route is mounted.
-  async fn on_post_route(&mut self, _: HookContext) {}
+  ///
+  /// The context includes the elapsed time, status, and size of the
+  /// response.
+  async fn on_post_route(&mut self, _: HookContext) -> ModuleResult { Ok(()) }
 }
@@ -0,0 +1,411 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+use std::{
+  collections::BTreeMap,
+  io,
+  sync::{Arc, RwLock},
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+  context::RouteContext,
+  handler::{Partial, PartialScope},
+  known_clients::KnownClients,
+  module::Module,
+  response::Response,
+  router::{runtime, RouteMetadata, Router},
+  store::{KeyValueStore, MemoryStore},
+};
+
+/// The prefix of the keys comments are stored under.
+const PREFIX: &str = "comments/";
+
+/// A comment left on a page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+  /// The path of the page the comment was left on.
+  pub page:        String,
+  /// The common name of the commenter's certificate, or `Anonymous`.
+  pub author:      String,
+  /// The hexadecimal SHA-256 fingerprint of the commenter's certificate.
+  pub fingerprint: String,
+  /// When the comment was left, in seconds since the Unix epoch.
+  pub posted:      u64,
+  pub message:     String,
+}
+
+impl Comment {
+  fn key(&self) -> String {
+    format!(
+      "{PREFIX}{}/{:020}-{}",
+      self.page.trim_start_matches('/'),
+      self.posted,
+      &self.fingerprint[..self.fingerprint.len().min(16)]
+    )
+  }
+
+  fn parse(key: &str, value: &[u8]) -> Option<Self> {
+    let (page, id) = key.strip_prefix(PREFIX)?.rsplit_once('/')?;
+    let value = String::from_utf8_lossy(value);
+    let mut fields = value.splitn(3, '\n');
+
+    Some(Self {
+      page:        format!("/{page}"),
+      posted:      id.split_once('-')?.0.parse().ok()?,
+      author:      fields.next()?.to_string(),
+      fingerprint: fields.next()?.to_string(),
+      message:     fields.next()?.to_string(),
+    })
+  }
+}
+
+/// A `Module` which lets clients leave comments on pages, such as a
+/// guestbook, identified by their certificates.
+///
+/// Comments are left on a page, e.g., `/guestbook`, by requesting it beneath
+/// the module's route, e.g., `/comment/guestbook`, which asks for a client
+/// certificate, then for the message. Comments are listed, with a link to
+/// leave one, in a footer of the pages within the scopes added with
+/// [`Comments::on`].
+///
+/// Comments are kept in a [`KeyValueStore`] under keys prefixed with
+/// `comments/`, and are read from it when the `Router` starts accepting
+/// connections. Every clone shares the same comments.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{module::Comments, response::Response};
+///
+/// windmark::router::Router::new()
+///   .attach(Comments::default().on("/guestbook"))
+///   .mount("/guestbook", |_| Response::success("# Guestbook"));
+/// ```
+#[derive(Clone)]
+pub struct Comments {
+  route:      String,
+  scopes:     Vec<PartialScope>,
+  max_length: usize,
+  cooldown:   Duration,
+  store:      Arc<dyn KeyValueStore>,
+  pages:      Arc<RwLock<BTreeMap<String, Vec<Comment>>>>,
+  loaded:     bool,
+}
+
+impl Default for Comments {
+  fn default() -> Self { Self::new("/comment") }
+}
+
+impl Comments {
+  /// Create comments which are left beneath `route`, kept in memory.
+  #[must_use]
+  pub fn new(route: impl Into<String>) -> Self {
+    Self {
+      route:      route.into().trim_end_matches('/').to_string(),
+      scopes:     Vec::new(),
+      max_length: 500,
+      cooldown:   Duration::from_secs(30),
+      store:      Arc::new(MemoryStore::new()),
+      pages:      Arc::default(),
+      loaded:     false,
+    }
+  }
+
+  /// Keep comments in `store`, which may be shared with other users.
+  #[must_use]
+  pub fn store(mut self, store: Arc<dyn KeyValueStore>) -> Self {
+    self.store = store;
+
+    self
+  }
+
+  /// List comments on the pages within `scope`: a path prefix, such as
+  /// `"/guestbook"`, or a predicate.
+  #[must_use]
+  pub fn on(mut self, scope: impl Into<PartialScope>) -> Self {
+    self.scopes.push(scope.into());
+
+    self
+  }
+
+  /// Set the maximum number of characters in a comment, 500 by default.
+  #[must_use]
+  pub const fn max_length(mut self, max_length: usize) -> Self {
+    self.max_length = max_length;
+
+    self
+  }
+
+  /// Set how long a certificate must wait between comments, 30 seconds by
+  /// default.
+  #[must_use]
+  pub const fn cooldown(mut self, cooldown: Duration) -> Self {
+    self.cooldown = cooldown;
+
+    self
+  }
+
+  /// The comments left on `page`, oldest first.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the comment list's lock is poisoned.
+  #[must_use]
+  pub fn comments(&self, page: &str) -> Vec<Comment> {
+    self
+      .pages
+      .read()
+      .unwrap()
+      .get(page)
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  /// Remove `comment`, e.g., as moderation. Returns whether it was found.
+  ///
+  /// # Errors
+  ///
+  /// if the store could not be written.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the comment list's lock is poisoned.
+  pub async fn remove(&self, comment: &Comment) -> io::Result<bool> {
+    let removed = self.store.remove(&comment.key()).await?;
+
+    if let Some(comments) = self.pages.write().unwrap().get_mut(&comment.page) {
+      comments.retain(|other| other != comment);
+    }
+
+    Ok(removed)
+  }
+
+  /// A partial which lists the comments on the requested page, followed by a
+  /// link to leave one.
+  ///
+  /// The partial is added to the pages within the scopes added with
+  /// [`Comments::on`] when the module is attached.
+  #[must_use]
+  pub fn partial(&self) -> impl Partial + 'static {
+    let comments = self.clone();
+
+    move |context: RouteContext| comments.render(context.url.path())
+  }
+
+  fn render(&self, page: &str) -> String {
+    let mut document = crate::gemtext::Document::new();
+
+    document.heading(2, "Comments");
+
+    for comment in self.comments(page) {
+      document.blank();
+
+      for line in comment.message.lines() {
+        document.quote(line);
+      }
+
+      document.text(format!(
+        "— {}, {}",
+        comment.author,
+        &crate::utilities::timestamp(
+          UNIX_EPOCH + Duration::from_secs(comment.posted)
+        )[..10]
+      ));
+    }
+
+    document.blank().link(
+      format!("{}{}", self.route, if page == "/" { "/" } else { page }),
+      "Leave a comment",
+    );
+
+    document.to_string()
+  }
+
+  async fn post(&self, context: RouteContext) -> Response {
+    let page = format!(
+      "/{}",
+      context
+        .parameters
+        .get("page")
+        .map_or("", |page| page.trim_start_matches('/'))
+    );
+
+    let mut target = context.clone();
+
+    match context.url.join(&page) {
+      Ok(url) => target.url = url,
+      Err(_) => return Response::not_found("This page does not exist."),
+    }
+
+    if !self.scopes.iter().any(|scope| scope.includes(&target)) {
+      return Response::not_found("Comments can not be left on this page.");
+    }
+
+    let Some(certificate) = &context.certificate else {
+      return Response::client_certificate_required(
+        "A certificate is required to leave a comment.",
+      );
+    };
+    let Some(fingerprint) = KnownClients::fingerprint(certificate) else {
+      return Response::certificate_not_valid("Your certificate is not valid.");
+    };
+    let Some(message) =
+      context.input().filter(|message| !message.trim().is_empty())
+    else {
+      return Response::input("Your comment");
+    };
+    let message = message.trim();
+
+    if message.chars().count() > self.max_length {
+      return Response::bad_request(format!(
+        "Comments may be at most {} characters long.",
+        self.max_length
+      ));
+    }
+
+    let posted = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_secs();
+
+    if self
+      .pages
+      .read()
+      .unwrap()
+      .values()
+      .flatten()
+      .any(|comment| {
+        comment.fingerprint == fingerprint
+          && posted.saturating_sub(comment.posted) < self.cooldown.as_secs()
+      })
+    {
+      return Response::slow_down(self.cooldown.as_secs().to_string());
+    }
+
+    let comment = Comment {
+      page,
+      author: KnownClients::identity(certificate)
+        .filter(|author| !author.trim().is_empty())
+        .unwrap_or_else(|| "Anonymous".to_string()),
+      fingerprint,
+      posted,
+      message: message.to_string(),
+    };
+
+    if let Err(e) = self
+      .store
+      .set(
+        &comment.key(),
+        format!(
+          "{}\n{}\n{}",
+          comment.author, comment.fingerprint, comment.message
+        )
+        .into_bytes(),
+      )
+      .await
+    {
+      warn!("[{}] could not save comment: {}", context.request_id, e);
+
+      return Response::temporary_failure("Please try again later.");
+    }
+
+    let redirect = comment.page.clone();
+
+    self
+      .pages
+      .write()
+      .unwrap()
+      .entry(comment.page.clone())
+      .or_default()
+      .push(comment);
+
+    Response::temporary_redirect(redirect)
+  }
+}
+
+impl Module for Comments {
+  fn on_attach(&mut self, router: &mut Router) {
+    let comments = self.clone();
+
+    router.mount_with_metadata(
+      format!("{}/*page", self.route),
+      RouteMetadata::new().hidden(),
+      move |context: RouteContext| {
+        let comments = comments.clone();
+
+        async move { comments.post(context).await }
+      },
+    );
+
+    for scope in &self.scopes {
+      router.add_footer_for(scope.clone(), self.partial());
+    }
+  }
+
+  fn on_start(&mut self, _: std::net::SocketAddr) {
+    if self.loaded {
+      return;
+    }
+
+    self.loaded = true;
+
+    let store = self.store.clone();
+    let comments = self.pages.clone();
+
+    runtime::detach(async move {
+      let mut loaded = BTreeMap::<String, Vec<Comment>>::new();
+      let keys = match store.keys(PREFIX).await {
+        Ok(keys) => keys,
+        Err(e) => {
+          warn!("could not read comments: {e}");
+
+          return;
+        }
+      };
+
+      for key in keys {
+        if let Ok(Some(value)) = store.get(&key).await {
+          if let Some(comment) = Comment::parse(&key, &value) {
+            loaded
+              .entry(comment.page.clone())
+              .or_default()
+              .push(comment);
+          }
+        }
+      }
+
+      for comments in loaded.values_mut() {
+        comments.sort_by_key(|comment| comment.posted);
+      }
+
+      let mut current = comments.write().unwrap();
+
+      // Keep comments left while the store was being read.
+      for (page, left) in std::mem::take(&mut *current) {
+        let page = loaded.entry(page).or_default();
+
+        for comment in left {
+          if !page.contains(&comment) {
+            page.push(comment);
+          }
+        }
+      }
+
+      *current = loaded;
+    });
+  }
+}
@@ -0,0 +1,490 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+use std::{
+  io,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+    RwLock,
+  },
+  time::Duration,
+};
+
+use crate::{
+  context::RouteContext,
+  feed::{self, Entry, Feed},
+  gemtext::{self, Line},
+  handler::Partial,
+  module::Module,
+  response::Response,
+  router::{RouteMetadata, Router},
+};
+
+/// A gemtext page served by [`Content`], along with its front matter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page {
+  /// The path the page is served at, e.g. `/gemlog/2023-01-01-hello.gmi`, or
+  /// `/gemlog/` for an `index.gmi`.
+  pub path:    String,
+  /// The `title` of the front matter, falling back to the page's first
+  /// heading, then to its file name.
+  pub title:   String,
+  /// The `date` of the front matter, falling back to the `YYYY-MM-DD` date
+  /// the file name begins with.
+  pub date:    Option<String>,
+  /// The `summary` of the front matter.
+  pub summary: Option<String>,
+  /// Whether the front matter marks the page as a `draft`.
+  pub draft:   bool,
+  /// The page's gemtext, without its front matter.
+  pub body:    String,
+}
+
+/// A `Module` which serves a directory of gemtext files, each of which may
+/// begin with TOML front matter between `+++` lines.
+///
+/// The `title`, `date`, `summary`, and `draft` keys of the front matter are
+/// understood, and drafts are not served unless [`Content::drafts`] is
+/// enabled. The pages can be built into a [`Feed`], listed by a
+/// [`Sitemap`](crate::module::Sitemap) or a
+/// [`navigation`](Content::navigation) partial, or looked up by other
+/// partials with [`Content::page`].
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{
+///   context::RouteContext,
+///   module::{Content, Sitemap},
+/// };
+///
+/// // `./gemlog/2023-01-01-hello.gmi`:
+/// //
+/// // +++
+/// // title = "Hello, World!"
+/// // summary = "My first post"
+/// // +++
+/// //
+/// // Welcome to my gemlog!
+/// let gemlog = Content::new("/gemlog", "./gemlog")
+///   .atom(
+///     "/gemlog/atom.xml",
+///     "My Gemlog",
+///     "gemini://example.com/gemlog/",
+///   )
+///   .watch(std::time::Duration::from_secs(2));
+/// let pages = gemlog.clone();
+///
+/// windmark::router::Router::new()
+///   .add_footer(gemlog.navigation())
+///   .attach(Sitemap::default().content(&gemlog))
+///   .add_header(move |context: RouteContext| {
+///     pages
+///       .page(context.url.path())
+///       .and_then(|page| page.date)
+///       .map(|date| format!("Posted on {date}"))
+///       .unwrap_or_default()
+///   })
+///   .attach(gemlog);
+/// ```
+#[derive(Clone)]
+pub struct Content {
+  route:     String,
+  directory: PathBuf,
+  drafts:    bool,
+  feeds:     Vec<(String, String, String)>,
+  pages:     Arc<RwLock<Vec<Page>>>,
+  watch:     Option<Duration>,
+  stop:      Option<Arc<AtomicBool>>,
+}
+
+impl Content {
+  /// Serve the gemtext files within `directory` at `route`.
+  #[must_use]
+  pub fn new(route: impl Into<String>, directory: impl Into<PathBuf>) -> Self {
+    Self {
+      route:     route.into().trim_end_matches('/').to_string(),
+      directory: directory.into(),
+      drafts:    false,
+      feeds:     Vec::new(),
+      pages:     Arc::new(RwLock::new(Vec::new())),
+      watch:     None,
+      stop:      None,
+    }
+  }
+
+  /// Serve and list pages which are marked as drafts.
+  #[must_use]
+  pub const fn drafts(mut self, drafts: bool) -> Self {
+    self.drafts = drafts;
+
+    self
+  }
+
+  /// Check the directory for changes every `interval` once the `Router`
+  /// starts accepting connections, reloading the pages, and so the feeds,
+  /// sitemaps, and partials built from them, when a file changes.
+  #[must_use]
+  pub const fn watch(mut self, interval: Duration) -> Self {
+    self.watch = Some(interval);
+
+    self
+  }
+
+  /// Mount an Atom feed of the dated pages at `route`, as built by
+  /// [`Content::feed`].
+  #[must_use]
+  pub fn atom(
+    mut self,
+    route: impl Into<String>,
+    title: impl Into<String>,
+    url: impl Into<String>,
+  ) -> Self {
+    self.feeds.push((route.into(), title.into(), url.into()));
+
+    self
+  }
+
+  /// Read the pages within the directory, replacing any read before.
+  ///
+  /// This is done when the module is attached.
+  ///
+  /// # Errors
+  ///
+  /// if the directory, or a file within it, could not be read, or if the
+  /// front matter of a file is not valid TOML.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the page list's lock is poisoned.
+  pub fn load(&self) -> io::Result<()> {
+    let mut pages = Vec::new();
+
+    read(&self.directory, &self.route, &mut pages)?;
+    pages.sort_by(|left, right| {
+      right
+        .date
+        .cmp(&left.date)
+        .then_with(|| left.title.cmp(&right.title))
+    });
+
+    *self.pages.write().unwrap() = pages;
+
+    Ok(())
+  }
+
+  /// The pages which are served, newest first, with undated pages last.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the page list's lock is poisoned.
+  #[must_use]
+  pub fn pages(&self) -> Vec<Page> {
+    self
+      .pages
+      .read()
+      .unwrap()
+      .iter()
+      .filter(|page| self.drafts || !page.draft)
+      .cloned()
+      .collect()
+  }
+
+  /// The page served at `path`, if any.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the page list's lock is poisoned.
+  #[must_use]
+  pub fn page(&self, path: &str) -> Option<Page> {
+    let path = path.strip_suffix("index.gmi").unwrap_or(path);
+    let directory = format!("{}/", path.trim_end_matches('/'));
+
+    self
+      .pages
+      .read()
+      .unwrap()
+      .iter()
+      .find(|page| {
+        (self.drafts || !page.draft)
+          && (page.path == path || page.path == directory)
+      })
+      .cloned()
+  }
+
+  /// Build a feed of the dated pages, linked relative to `url`, the absolute
+  /// URL of the directory's route.
+  #[must_use]
+  pub fn feed(&self, title: impl Into<String>, url: impl Into<String>) -> Feed {
+    let mut feed = Feed::new(title, url);
+    let base = feed.url.trim_end_matches('/').to_string();
+
+    for page in self.pages() {
+      let Some(date) = page.date else {
+        continue;
+      };
+
+      feed.entry(Entry {
+        title:   page.title,
+        url:     format!("{base}{}", &page.path[self.route.len()..]),
+        updated: date,
+        summary: page.summary,
+      });
+    }
+
+    feed
+  }
+
+  /// A partial which links to every page other than the one being
+  /// requested, newest first.
+  #[must_use]
+  pub fn navigation(&self) -> impl Partial + 'static {
+    let content = self.clone();
+
+    move |context: RouteContext| {
+      let mut document = gemtext::Document::new();
+      let current = content.page(context.url.path()).map(|page| page.path);
+
+      for page in content.pages() {
+        if current.as_ref() == Some(&page.path) {
+          continue;
+        }
+
+        match &page.date {
+          Some(date) =>
+            document.link(
+              page.path.as_str(),
+              format!("{} - {}", date.get(..10).unwrap_or(date), page.title),
+            ),
+          None => document.link(page.path.as_str(), page.title.as_str()),
+        };
+      }
+
+      document.to_string()
+    }
+  }
+
+  fn serve(&self, path: &str) -> Response {
+    if let Some((_, title, url)) =
+      self.feeds.iter().find(|(route, ..)| route == path)
+    {
+      return self.feed(title.as_str(), url.as_str()).atom_response();
+    }
+
+    self.page(path).map_or_else(
+      || Response::not_found("The requested resource could not be found."),
+      |page| Response::success(page.body),
+    )
+  }
+}
+
+impl Module for Content {
+  fn on_attach(&mut self, router: &mut Router) {
+    if let Err(e) = self.load() {
+      warn!("could not read {}: {}", self.directory.display(), e);
+    }
+
+    let content = self.clone();
+    let index = self.clone();
+
+    router.mount_with_metadata(
+      format!("{}/*path", self.route),
+      RouteMetadata::new().hidden(),
+      move |context: RouteContext| content.serve(context.url.path()),
+    );
+    router.mount_with_metadata(
+      if self.route.is_empty() {
+        "/"
+      } else {
+        self.route.as_str()
+      },
+      RouteMetadata::new().hidden(),
+      move |context: RouteContext| index.serve(context.url.path()),
+    );
+
+    for (route, title, _) in &self.feeds {
+      // Feeds beneath the directory's route are served by its wildcard.
+      if crate::router::is_beneath(route, &self.route) {
+        continue;
+      }
+
+      let content = self.clone();
+
+      router.mount_with_metadata(
+        route.as_str(),
+        RouteMetadata::new().title(title.as_str()),
+        move |context: RouteContext| content.serve(context.url.path()),
+      );
+    }
+  }
+
+  fn on_start(&mut self, _: std::net::SocketAddr) {
+    let Some(interval) = self.watch else {
+      return;
+    };
+
+    if self.stop.is_some() {
+      return;
+    }
+
+    let content = self.clone();
+
+    self.stop = Some(super::watch::poll(
+      vec![self.directory.clone()],
+      interval,
+      move || {
+        match content.load() {
+          Ok(()) => info!("reloaded {}", content.directory.display()),
+          Err(e) =>
+            warn!("could not reload {}: {}", content.directory.display(), e),
+        }
+      },
+    ));
+  }
+
+  fn on_shutdown(&mut self) {
+    if let Some(stop) = self.stop.take() {
+      stop.store(true, Ordering::Relaxed);
+    }
+  }
+}
+
+/// Read the gemtext files within `directory`, and its subdirectories, as
+/// pages beneath `route`.
+fn read(
+  directory: &Path,
+  route: &str,
+  pages: &mut Vec<Page>,
+) -> io::Result<()> {
+  for file in std::fs::read_dir(directory)? {
+    let path = file?.path();
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+      continue;
+    };
+
+    if name.starts_with('.') {
+      continue;
+    }
+
+    if path.is_dir() {
+      read(&path, &format!("{route}/{name}"), pages)?;
+
+      continue;
+    }
+
+    if !path
+      .extension()
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("gmi"))
+    {
+      continue;
+    }
+
+    pages.push(page(
+      &path,
+      if name == "index.gmi" {
+        format!("{route}/")
+      } else {
+        format!("{route}/{name}")
+      },
+    )?);
+  }
+
+  Ok(())
+}
+
+/// Read the page at `path`, served at `route`.
+fn page(path: &Path, route: String) -> io::Result<Page> {
+  let source = std::fs::read_to_string(path)?;
+  let (front_matter, body) = split(&source);
+  let front_matter = front_matter
+    .map(toml::from_str::<toml::Table>)
+    .transpose()
+    .map_err(|e| {
+      io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {e}", path.display()),
+      )
+    })?
+    .unwrap_or_default();
+  let text = |key: &str| {
+    match front_matter.get(key)? {
+      toml::Value::String(value) => Some(value.clone()),
+      toml::Value::Datetime(value) => Some(value.to_string()),
+      _ => None,
+    }
+  };
+  let date = text("date").or_else(|| feed::dated(path));
+  let title = text("title")
+    .or_else(|| {
+      gemtext::parse(body).into_iter().find_map(|line| {
+        match line {
+          Line::Heading {
+            text, ..
+          } => Some(text),
+          _ => None,
+        }
+      })
+    })
+    .unwrap_or_else(|| {
+      let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+
+      stem[feed::dated(path).map_or(0, |date| date.len())..]
+        .trim_start_matches(['-', '_', ' '])
+        .to_string()
+    });
+
+  Ok(Page {
+    path: route,
+    title,
+    date,
+    summary: text("summary"),
+    draft: matches!(
+      front_matter.get("draft"),
+      Some(toml::Value::Boolean(true))
+    ),
+    body: body.to_string(),
+  })
+}
+
+/// Split the front matter between leading `+++` lines from the rest of
+/// `source`.
+fn split(source: &str) -> (Option<&str>, &str) {
+  let Some(rest) = source
+    .strip_prefix("+++\n")
+    .or_else(|| source.strip_prefix("+++\r\n"))
+  else {
+    return (None, source);
+  };
+  let mut offset = 0;
+
+  for line in rest.split_inclusive('\n') {
+    if line.trim_end() == "+++" {
+      return (
+        Some(&rest[..offset]),
+        rest[offset + line.len()..].trim_start_matches(['\r', '\n']),
+      );
+    }
+
+    offset += line.len();
+  }
+
+  (None, source)
+}
@@ -0,0 +1,333 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fmt::Write, net::SocketAddr, time::Duration};
+
+#[cfg(feature = "async-std")]
+use async_std::io::{ReadExt, WriteExt};
+#[cfg(feature = "smol")]
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{
+  gemtext::{self, Line},
+  module::{adapter, Module},
+  response::Response,
+  router::{runtime, Router},
+};
+
+/// The longest selector, including its search terms, which is read.
+const MAX_SELECTOR_LENGTH: usize = 1024 + 2;
+
+/// The host and port menu lines point to.
+#[derive(Clone)]
+struct Origin {
+  host: String,
+  port: u16,
+}
+
+/// An experimental `Module` which serves a capsule's routes over Gopher.
+///
+/// Selectors are routed as if they were Gemini requests for the same path,
+/// with search terms as the query. Gemtext responses become menus, in which
+/// links within the capsule are menu items and other links are URL items;
+/// other text responses are served as text files. Input prompts become search
+/// items, and redirects and failures become menu and error lines.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::module::Gopher;
+///
+/// windmark::router::Router::new()
+///   .attach(Gopher::new("0.0.0.0:70").host("fuwn.me"));
+/// ```
+pub struct Gopher {
+  address: String,
+  host:    String,
+  router:  Option<Router>,
+  started: bool,
+}
+
+impl Gopher {
+  /// Create an adapter which listens for Gopher requests on `address`.
+  #[must_use]
+  pub fn new(address: impl Into<String>) -> Self {
+    Self {
+      address: address.into(),
+      host:    "localhost".to_string(),
+      router:  None,
+      started: false,
+    }
+  }
+
+  /// Set the host which requests are routed as, and which menus link to;
+  /// `localhost` by default.
+  #[must_use]
+  pub fn host(mut self, host: impl Into<String>) -> Self {
+    self.host = host.into();
+
+    self
+  }
+}
+
+impl Module for Gopher {
+  fn on_attach(&mut self, router: &mut Router) {
+    self.router = Some(router.clone());
+  }
+
+  fn on_start(&mut self, _: SocketAddr) {
+    if self.started {
+      return;
+    }
+
+    let Some(router) = self.router.clone() else {
+      return;
+    };
+
+    match adapter::bind(&self.address) {
+      Ok(listener) => {
+        let origin = Origin {
+          host: self.host.clone(),
+          port: listener.local_addr().map_or(70, |address| address.port()),
+        };
+
+        self.started = true;
+
+        info!("gopher listening on {}", self.address);
+        runtime::detach(adapter::serve(
+          "gopher",
+          router,
+          listener,
+          move |router, stream, peer_address, local_address| {
+            handle(router, stream, peer_address, local_address, origin.clone())
+          },
+        ));
+      }
+      Err(e) => error!("could not bind gopher to {}: {e}", self.address),
+    }
+  }
+}
+
+async fn handle(
+  mut router: Router,
+  mut stream: runtime::TcpStream,
+  peer_address: SocketAddr,
+  local_address: SocketAddr,
+  origin: Origin,
+) -> std::io::Result<()> {
+  let request = runtime::timeout(Some(Duration::from_secs(10)), async {
+    let mut request = Vec::new();
+    let mut chunk = [0u8; 1024];
+
+    while !request.contains(&b'\n') && request.len() < MAX_SELECTOR_LENGTH {
+      let size = stream.read(&mut chunk).await?;
+
+      if size == 0 {
+        break;
+      }
+
+      request.extend_from_slice(&chunk[..size]);
+    }
+
+    Ok::<_, std::io::Error>(request)
+  })
+  .await??;
+  let request = String::from_utf8_lossy(&request);
+  let request = request.lines().next().unwrap_or_default();
+  let (selector, search) = request
+    .split_once('\t')
+    .map_or((request, None), |(selector, search)| {
+      (selector, Some(search))
+    });
+  let output = match url::Url::parse(&format!(
+    "gemini://{}/{}",
+    origin.host,
+    selector.trim_start_matches('/')
+  )) {
+    Ok(mut url) => {
+      if let Some(search) = search {
+        url.set_query(Some(
+          &percent_encoding::utf8_percent_encode(
+            search,
+            percent_encoding::NON_ALPHANUMERIC,
+          )
+          .to_string(),
+        ));
+      }
+
+      let (response, header, footer) = router
+        .dispatch(Some(peer_address), local_address, url)
+        .await;
+
+      translate(&response, &header, &footer, selector, &origin)
+    }
+    Err(_) => error_menu("Invalid selector").into_bytes(),
+  };
+
+  stream.write_all(&output).await?;
+  stream.flush().await
+}
+
+/// Translate a Gemini response to `selector` into a Gopher response.
+fn translate(
+  response: &Response,
+  header: &str,
+  footer: &str,
+  selector: &str,
+  origin: &Origin,
+) -> Vec<u8> {
+  let output = match response.status {
+    10..=19 => {
+      let mut menu = String::new();
+
+      item(&mut menu, '7', &response.content, selector, origin);
+
+      terminate(menu)
+    }
+    20..=29 => {
+      let mime = response.mime.as_deref().unwrap_or("text/gemini");
+      let content = if response.raw_body {
+        response.content.clone()
+      } else {
+        format!("{header}{}\n{footer}", response.content)
+      };
+
+      if mime.starts_with("text/gemini") {
+        menu(&gemtext::parse(&content), origin)
+      } else if mime.starts_with("text/") {
+        terminate(
+          content
+            .lines()
+            .map(|line| {
+              // A line of a single period would end the file early.
+              if line.starts_with('.') {
+                format!(".{line}\r\n")
+              } else {
+                format!("{line}\r\n")
+              }
+            })
+            .collect(),
+        )
+      } else {
+        return response.content_bytes().to_vec();
+      }
+    }
+    30..=39 => {
+      let mut menu = String::new();
+
+      info(&mut menu, "This page has moved.");
+      link(&mut menu, &response.content, None, origin);
+
+      terminate(menu)
+    }
+    _ => error_menu(&response.content),
+  };
+
+  output.into_bytes()
+}
+
+/// Render gemtext lines as a Gopher menu.
+fn menu(lines: &[Line], origin: &Origin) -> String {
+  let mut menu = String::new();
+
+  for line in lines {
+    match line {
+      Line::Text(text) => info(&mut menu, text),
+      Line::Link {
+        url,
+        label,
+      } => link(&mut menu, url, label.as_deref(), origin),
+      Line::Heading {
+        level,
+        text,
+      } =>
+        info(
+          &mut menu,
+          &format!("{} {text}", "#".repeat(usize::from((*level).clamp(1, 3)))),
+        ),
+      Line::ListItem(text) => info(&mut menu, &format!("* {text}")),
+      Line::Quote(text) => info(&mut menu, &format!("> {text}")),
+      Line::Preformatted {
+        lines, ..
+      } =>
+        for line in lines {
+          info(&mut menu, line);
+        },
+    }
+  }
+
+  terminate(menu)
+}
+
+/// Append a menu item for a link, which is a menu item if it stays within the
+/// capsule and a URL item otherwise.
+fn link(menu: &mut String, url: &str, label: Option<&str>, origin: &Origin) {
+  let label = label.filter(|label| !label.is_empty()).unwrap_or(url);
+  let base = url::Url::parse(&format!("gemini://{}/", origin.host));
+  let resolved = base.and_then(|base| base.join(url));
+
+  match resolved {
+    Ok(resolved)
+      if resolved.scheme() == "gemini"
+        && resolved.host_str() == Some(origin.host.as_str()) =>
+      item(
+        menu,
+        '1',
+        label,
+        &resolved[url::Position::BeforePath..url::Position::AfterQuery],
+        origin,
+      ),
+    _ => item(menu, 'h', label, &format!("URL:{url}"), origin),
+  }
+}
+
+fn info(menu: &mut String, text: &str) {
+  // Writing to a `String` can not fail.
+  let _ = write!(menu, "i{}\t\terror.host\t1\r\n", single_line(text));
+}
+
+fn item(
+  menu: &mut String,
+  kind: char,
+  label: &str,
+  selector: &str,
+  origin: &Origin,
+) {
+  let _ = write!(
+    menu,
+    "{kind}{}\t{}\t{}\t{}\r\n",
+    single_line(label),
+    single_line(selector),
+    origin.host,
+    origin.port
+  );
+}
+
+fn error_menu(message: &str) -> String {
+  terminate(format!("3{}\t\terror.host\t1\r\n", single_line(message)))
+}
+
+fn terminate(mut response: String) -> String {
+  response.push_str(".\r\n");
+
+  response
+}
+
+/// Tabs and line breaks would corrupt a menu line.
+fn single_line(text: &str) -> String { text.replace(['\t', '\r', '\n'], " ") }
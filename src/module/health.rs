@@ -0,0 +1,216 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  fmt::Write,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+    OnceLock,
+  },
+  time::{Duration, Instant},
+};
+
+use crate::{module::Module, response::Response, router::Router};
+
+/// A source of status lines for a [`Health`] report, e.g., a module which
+/// depends on a database.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::module::{Health, HealthCheck};
+///
+/// struct Database;
+///
+/// impl HealthCheck for Database {
+///   fn name(&self) -> &str { "database" }
+///
+///   fn check(&self) -> Result<String, String> {
+///     Ok("12 connections open".to_string())
+///   }
+/// }
+///
+/// windmark::router::Router::new().attach(Health::default().check(Database));
+/// ```
+pub trait HealthCheck: Send + Sync {
+  /// The name the status is reported under.
+  fn name(&self) -> &str;
+
+  /// A line describing the current status, or why the check is failing.
+  ///
+  /// # Errors
+  ///
+  /// if the check is failing, in which case the report is marked unhealthy.
+  fn check(&self) -> Result<String, String>;
+}
+
+impl<T: HealthCheck + ?Sized> HealthCheck for Arc<T> {
+  fn name(&self) -> &str { (**self).name() }
+
+  fn check(&self) -> Result<String, String> { (**self).check() }
+}
+
+#[derive(Default)]
+struct Counters {
+  started:  OnceLock<Instant>,
+  requests: AtomicU64,
+  /// Responses by the first digit of their status, from 1 to 6.
+  statuses: [AtomicU64; 6],
+}
+
+/// A `Module` which mounts a gemtext status report of the server's uptime,
+/// the requests it has handled, and any [`HealthCheck`]s.
+///
+/// The report is mounted at `/.well-known/status` unless another route is
+/// given, and is always served with a success status; failing checks mark it
+/// as unhealthy.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::module::Health;
+///
+/// windmark::router::Router::new().attach(Health::new("/status"));
+/// ```
+pub struct Health {
+  route:    String,
+  checks:   Vec<Arc<dyn HealthCheck>>,
+  counters: Arc<Counters>,
+}
+
+impl Default for Health {
+  fn default() -> Self { Self::new("/.well-known/status") }
+}
+
+impl Health {
+  /// Create a status report which is mounted at `route`.
+  #[must_use]
+  pub fn new(route: impl Into<String>) -> Self {
+    Self {
+      route:    route.into(),
+      checks:   Vec::new(),
+      counters: Arc::default(),
+    }
+  }
+
+  /// Add a check to the report.
+  #[must_use]
+  pub fn check(mut self, check: impl HealthCheck + 'static) -> Self {
+    self.checks.push(Arc::new(check));
+
+    self
+  }
+}
+
+impl Module for Health {
+  fn on_attach(&mut self, router: &mut Router) {
+    let counters = self.counters.clone();
+    let checks = self.checks.clone();
+
+    router.mount(self.route.as_str(), move |_| {
+      Response::success(report(&counters, &checks))
+    });
+  }
+
+  fn on_start(&mut self, _: std::net::SocketAddr) {
+    self.counters.started.get_or_init(Instant::now);
+  }
+
+  fn on_post_route(&mut self, context: crate::context::HookContext) {
+    self.counters.requests.fetch_add(1, Ordering::Relaxed);
+
+    if let Some(counter) = context
+      .status
+      .and_then(|status| usize::try_from(status / 10).ok())
+      .and_then(|class| self.counters.statuses.get(class.wrapping_sub(1)))
+    {
+      counter.fetch_add(1, Ordering::Relaxed);
+    }
+  }
+}
+
+fn report(counters: &Counters, checks: &[Arc<dyn HealthCheck>]) -> String {
+  let results = checks
+    .iter()
+    .map(|check| (check.name(), check.check()))
+    .collect::<Vec<_>>();
+  let healthy = results.iter().all(|(_, result)| result.is_ok());
+  let mut report = String::from("# Status\n\n");
+
+  // Writing to a `String` can not fail.
+  let _ = writeln!(
+    report,
+    "Status: {}",
+    if healthy { "healthy" } else { "unhealthy" }
+  );
+  let _ = writeln!(
+    report,
+    "Uptime: {}",
+    counters
+      .started
+      .get()
+      .map_or_else(|| "-".to_string(), |started| uptime(started.elapsed()))
+  );
+  let _ = writeln!(
+    report,
+    "Requests: {}",
+    counters.requests.load(Ordering::Relaxed)
+  );
+
+  for (class, counter) in counters.statuses.iter().enumerate() {
+    let count = counter.load(Ordering::Relaxed);
+
+    if count > 0 {
+      let _ = writeln!(report, "* {}x responses: {count}", class + 1);
+    }
+  }
+
+  if !results.is_empty() {
+    report.push_str("\n## Checks\n\n");
+
+    for (name, result) in results {
+      let _ = match result {
+        Ok(status) => writeln!(report, "* {name}: ok, {status}"),
+        Err(reason) => writeln!(report, "* {name}: failing, {reason}"),
+      };
+    }
+  }
+
+  report
+}
+
+/// Format `elapsed` as days, hours, minutes, and seconds, e.g., `1d 2h 3m 4s`.
+fn uptime(elapsed: Duration) -> String {
+  let seconds = elapsed.as_secs();
+  let (days, hours, minutes, seconds) = (
+    seconds / 86_400,
+    seconds % 86_400 / 3600,
+    seconds % 3600 / 60,
+    seconds % 60,
+  );
+
+  if days > 0 {
+    format!("{days}d {hours}h {minutes}m {seconds}s")
+  } else if hours > 0 {
+    format!("{hours}h {minutes}m {seconds}s")
+  } else if minutes > 0 {
+    format!("{minutes}m {seconds}s")
+  } else {
+    format!("{seconds}s")
+  }
+}
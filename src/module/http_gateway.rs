@@ -0,0 +1,466 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fmt::Write, net::SocketAddr, time::Duration};
+
+#[cfg(feature = "async-std")]
+use async_std::io::{ReadExt, WriteExt};
+#[cfg(feature = "smol")]
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{
+  gemtext,
+  module::{adapter, Module},
+  response::{self, Response},
+  router::{runtime, Router},
+  utilities::escape_html as escape,
+};
+
+/// The longest HTTP request head which is read.
+const MAX_HEAD_LENGTH: usize = 8 * 1024;
+
+/// The name of the form field an input prompt is answered with.
+const INPUT_FIELD: &str = "gemini-input";
+
+/// A `Module` which mirrors a capsule on the web by serving its routes over
+/// HTTP, converting gemtext responses to HTML.
+///
+/// The HTTP listener is started along with the `Router`, and requests are
+/// routed as if they were Gemini requests for the same path, running the same
+/// hooks, headers, and footers. Input prompts are rendered as forms,
+/// redirects within the capsule become HTTP redirects while others become a
+/// page linking to their target, and Gemini statuses are mapped to their
+/// closest HTTP equivalents.
+///
+/// The gateway speaks plain HTTP; it is intended to sit behind a reverse
+/// proxy which terminates TLS.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::module::HttpGateway;
+///
+/// windmark::router::Router::new()
+///   .attach(HttpGateway::new("127.0.0.1:8080").host("fuwn.me"));
+/// ```
+pub struct HttpGateway {
+  address: String,
+  host:    Option<String>,
+  router:  Option<Router>,
+  started: bool,
+}
+
+impl HttpGateway {
+  /// Create a gateway which listens for HTTP requests on `address`.
+  #[must_use]
+  pub fn new(address: impl Into<String>) -> Self {
+    Self {
+      address: address.into(),
+      host:    None,
+      router:  None,
+      started: false,
+    }
+  }
+
+  /// Route every request as if it were for `host`, rather than the host of
+  /// its `Host` header.
+  #[must_use]
+  pub fn host(mut self, host: impl Into<String>) -> Self {
+    self.host = Some(host.into());
+
+    self
+  }
+}
+
+impl Module for HttpGateway {
+  fn on_attach(&mut self, router: &mut Router) {
+    self.router = Some(router.clone());
+  }
+
+  fn on_start(&mut self, _: SocketAddr) {
+    if self.started {
+      return;
+    }
+
+    let Some(router) = self.router.clone() else {
+      return;
+    };
+    match adapter::bind(&self.address) {
+      Ok(listener) => {
+        let host = self.host.clone();
+
+        self.started = true;
+
+        info!("http gateway listening on {}", self.address);
+        runtime::detach(adapter::serve(
+          "http gateway",
+          router,
+          listener,
+          move |router, stream, peer_address, local_address| {
+            handle(router, stream, peer_address, local_address, host.clone())
+          },
+        ));
+      }
+      Err(e) =>
+        error!("could not bind the http gateway to {}: {e}", self.address),
+    }
+  }
+}
+
+async fn handle(
+  mut router: Router,
+  mut stream: runtime::TcpStream,
+  peer_address: SocketAddr,
+  local_address: SocketAddr,
+  host: Option<String>,
+) -> std::io::Result<()> {
+  let head = runtime::timeout(Some(Duration::from_secs(10)), async {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 1024];
+
+    while !head.windows(4).any(|window| window == b"\r\n\r\n")
+      && head.len() < MAX_HEAD_LENGTH
+    {
+      let size = stream.read(&mut chunk).await?;
+
+      if size == 0 {
+        break;
+      }
+
+      head.extend_from_slice(&chunk[..size]);
+    }
+
+    Ok::<_, std::io::Error>(head)
+  })
+  .await??;
+  let head = String::from_utf8_lossy(&head);
+  let mut lines = head.lines();
+  let mut request_line = lines.next().unwrap_or_default().split(' ');
+  let (method, target) = (
+    request_line.next().unwrap_or_default(),
+    request_line.next().unwrap_or_default(),
+  );
+  let host = host.or_else(|| {
+    lines.find_map(|line| {
+      let (name, value) = line.split_once(':')?;
+
+      name.trim().eq_ignore_ascii_case("host").then(|| {
+        let value = value.trim();
+
+        // Drop the port, taking care not to split an IPv6 address.
+        match value.rsplit_once(':') {
+          Some((host, port))
+            if !host.ends_with(':')
+              && port.bytes().all(|b| b.is_ascii_digit()) =>
+            host.to_string(),
+          _ => value.to_string(),
+        }
+      })
+    })
+  });
+  let output = if method != "GET" && method != "HEAD" {
+    http_response(405, "text/plain", b"Method Not Allowed", &[])
+  } else if let Some(url) = host
+    .filter(|_| target.starts_with('/'))
+    .and_then(|host| gemini_url(&host, target))
+  {
+    let (response, header, footer) = router
+      .dispatch(Some(peer_address), local_address, url.clone())
+      .await;
+
+    translate(&response, &url, &header, &footer, method == "HEAD")
+  } else {
+    http_response(400, "text/plain", b"Bad Request", &[])
+  };
+
+  stream.write_all(&output).await?;
+  stream.flush().await
+}
+
+/// The Gemini URL an HTTP request for `target` on `host` maps to, translating
+/// a submitted input form into a Gemini query.
+fn gemini_url(host: &str, target: &str) -> Option<url::Url> {
+  let mut url = url::Url::parse(&format!("gemini://{host}{target}")).ok()?;
+  let input = url.query().and_then(|query| {
+    query
+      .strip_prefix(INPUT_FIELD)
+      .and_then(|query| query.strip_prefix('='))
+      .map(|input| crate::utilities::percent_decode(&input.replace('+', " ")))
+  });
+
+  if let Some(input) = input {
+    url.set_query(Some(
+      &percent_encoding::utf8_percent_encode(
+        &input,
+        percent_encoding::NON_ALPHANUMERIC,
+      )
+      .to_string(),
+    ));
+  }
+
+  Some(url)
+}
+
+/// Translate a Gemini response to a request for `url` into a complete HTTP
+/// response.
+fn translate(
+  response: &Response,
+  url: &url::Url,
+  header: &str,
+  footer: &str,
+  head_only: bool,
+) -> Vec<u8> {
+  let meta = escape(&response.content);
+  let (status, content_type, body, extra_headers) = match response.status {
+    10..=19 =>
+      (
+        200,
+        "text/html; charset=utf-8".to_string(),
+        page(
+          &meta,
+          None,
+          &format!(
+            "<form method=\"get\"><p><label>{meta} <input \
+             name=\"{INPUT_FIELD}\" type=\"{}\" autofocus></label> \
+             <button>Submit</button></p></form>\n",
+            if response.status == 11 {
+              "password"
+            } else {
+              "text"
+            },
+          ),
+        )
+        .into_bytes(),
+        Vec::new(),
+      ),
+    // A streamed body has no end which could be waited for.
+    20..=29 if response.is_streamed() =>
+      (
+        501,
+        "text/html; charset=utf-8".to_string(),
+        page(
+          "Not Implemented",
+          None,
+          "<h1>501</h1>\n<p>This page is streamed, which the HTTP gateway \
+           does not support.</p>\n",
+        )
+        .into_bytes(),
+        Vec::new(),
+      ),
+    20..=29 => {
+      let (content_type, body) = success(response, header, footer);
+
+      (200, content_type, body, Vec::new())
+    }
+    30..=39 => redirect(response, url),
+    _ => {
+      let status = match response.status {
+        44 => 429,
+        43 => 502,
+        40..=49 => 503,
+        51 => 404,
+        52 => 410,
+        53 => 421,
+        59 => 400,
+        60..=69 => 403,
+        _ => 500,
+      };
+
+      (
+        status,
+        "text/html; charset=utf-8".to_string(),
+        page(&meta, None, &format!("<h1>{status}</h1>\n<p>{meta}</p>\n"))
+          .into_bytes(),
+        Vec::new(),
+      )
+    }
+  };
+  let mut output = http_response(status, &content_type, &body, &extra_headers);
+
+  if head_only {
+    output.truncate(output.len() - body.len());
+  }
+
+  output
+}
+
+/// The HTTP response to a Gemini redirect: a redirect to the gateway's path
+/// for a target on the same capsule, or a page linking to any other target,
+/// which a browser could not follow.
+fn redirect(
+  response: &Response,
+  url: &url::Url,
+) -> (u16, String, Vec<u8>, Vec<(&'static str, String)>) {
+  let target = response::sanitise_meta(&response.content);
+
+  match url.join(&target) {
+    Ok(target)
+      if target.scheme() == "gemini"
+        && target.host_str() == url.host_str()
+        && target.port() == url.port() =>
+    {
+      let mut location = target.path().to_string();
+
+      if let Some(query) = target.query() {
+        location.push('?');
+        location.push_str(query);
+      }
+
+      (
+        if response.status == 31 { 301 } else { 302 },
+        "text/plain".to_string(),
+        Vec::new(),
+        vec![("Location", location)],
+      )
+    }
+    _ =>
+      (
+        200,
+        "text/html; charset=utf-8".to_string(),
+        page(
+          "Redirect",
+          None,
+          &format!(
+            "<p>This page has moved.</p>
+{}",
+            gemtext::to_html(&gemtext::parse(&format!("=> {target}\n")))
+          ),
+        )
+        .into_bytes(),
+        Vec::new(),
+      ),
+  }
+}
+
+/// The content type and body of the HTTP response to a successful Gemini
+/// response, with gemtext rendered as HTML.
+fn success(
+  response: &Response,
+  header: &str,
+  footer: &str,
+) -> (String, Vec<u8>) {
+  let mime = response.mime.as_deref().unwrap_or("text/gemini");
+  let character_set = response.character_set.as_deref().unwrap_or("utf-8");
+
+  if !mime.starts_with("text/") {
+    return (mime.to_string(), response.content_bytes().to_vec());
+  }
+
+  let content = if response.raw_body {
+    response.content.clone()
+  } else {
+    format!("{header}{}\n{footer}", response.content)
+  };
+
+  if !mime.starts_with("text/gemini") {
+    return (
+      format!("{mime}; charset={character_set}"),
+      content.into_bytes(),
+    );
+  }
+
+  let lines = gemtext::parse(&content);
+  let title = lines.iter().find_map(|line| {
+    match line {
+      gemtext::Line::Heading {
+        text, ..
+      } => Some(escape(text)),
+      _ => None,
+    }
+  });
+
+  (
+    "text/html; charset=utf-8".to_string(),
+    page(
+      title.as_deref().unwrap_or_default(),
+      response
+        .languages
+        .as_ref()
+        .and_then(|languages| languages.first())
+        .map(String::as_str),
+      &gemtext::to_html(&lines),
+    )
+    .into_bytes(),
+  )
+}
+
+/// Wrap an HTML fragment in a complete document.
+fn page(title: &str, language: Option<&str>, body: &str) -> String {
+  format!(
+    "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta \
+     charset=\"utf-8\">\n<meta name=\"viewport\" \
+     content=\"width=device-width, \
+     initial-scale=1\">\n<title>{title}</title>\n</head>\n<body>\n{body}</\
+     body>\n</html>\n",
+    escape(language.unwrap_or("en"))
+  )
+}
+
+/// Make `value`, which may come from a route's response, safe to place in an
+/// HTTP header, sanitised as a Gemini meta is and without any other control
+/// characters.
+fn header_value(value: &str) -> String {
+  response::sanitise_meta(value)
+    .chars()
+    .filter(|character| !character.is_control())
+    .collect()
+}
+
+fn http_response(
+  status: u16,
+  content_type: &str,
+  body: &[u8],
+  extra_headers: &[(&str, String)],
+) -> Vec<u8> {
+  let reason = match status {
+    200 => "OK",
+    301 => "Moved Permanently",
+    302 => "Found",
+    400 => "Bad Request",
+    403 => "Forbidden",
+    404 => "Not Found",
+    405 => "Method Not Allowed",
+    410 => "Gone",
+    421 => "Misdirected Request",
+    429 => "Too Many Requests",
+    501 => "Not Implemented",
+    502 => "Bad Gateway",
+    503 => "Service Unavailable",
+    _ => "Internal Server Error",
+  };
+  let mut output = format!(
+    "HTTP/1.1 {status} {reason}\r\nContent-Type: {}\r\nContent-Length: \
+     {}\r\nConnection: close\r\n",
+    header_value(content_type),
+    body.len()
+  );
+
+  for (name, value) in extra_headers {
+    // Writing to a `String` can not fail.
+    let _ = write!(output, "{name}: {}\r\n", header_value(value));
+  }
+
+  output.push_str("\r\n");
+
+  let mut output = output.into_bytes();
+
+  output.extend_from_slice(body);
+
+  output
+}
@@ -0,0 +1,204 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  net::SocketAddr,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  },
+  time::SystemTime,
+};
+
+use async_trait::async_trait;
+use url::Url;
+
+use crate::{
+  context::HookContext,
+  known_clients::KnownClients,
+  module::Module,
+  router::runtime,
+};
+
+/// A request seen by a [`Mirror`], without its response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MirroredRequest {
+  pub url:          Url,
+  pub peer_address: Option<SocketAddr>,
+  /// The address of the listener the request arrived on.
+  pub listener:     SocketAddr,
+  pub request_id:   String,
+  /// The hexadecimal SHA-256 fingerprint of the peer's certificate.
+  pub fingerprint:  Option<String>,
+  pub received_at:  SystemTime,
+}
+
+/// A destination for the requests seen by a [`Mirror`].
+#[async_trait]
+pub trait MirrorSink: Send + Sync {
+  async fn mirror(&self, request: MirroredRequest);
+}
+
+#[async_trait]
+impl<T> MirrorSink for T
+where T: Fn(MirroredRequest) + Send + Sync
+{
+  async fn mirror(&self, request: MirroredRequest) { (*self)(request) }
+}
+
+/// A [`MirrorSink`] which replays each request against another capsule,
+/// e.g., a staging deployment, discarding its response.
+///
+/// The scheme, host, and port of each request are replaced with those of the
+/// upstream. Client certificates can not be forwarded, so requests are made
+/// anonymously unless the client has an identity of its own.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::module::{Mirror, Upstream};
+///
+/// windmark::router::Router::new().attach(Mirror::new(Upstream::new(
+///   url::Url::parse("gemini://staging.example.com:1966/").unwrap(),
+/// )));
+/// ```
+#[cfg(feature = "client")]
+pub struct Upstream {
+  base:   Url,
+  client: crate::client::Client,
+}
+
+#[cfg(feature = "client")]
+impl Upstream {
+  #[must_use]
+  pub fn new(base: Url) -> Self {
+    Self::with_client(base, crate::client::Client::new())
+  }
+
+  /// Replay requests using `client`, e.g., to trust the upstream's
+  /// certificate ahead of time.
+  #[must_use]
+  pub const fn with_client(base: Url, client: crate::client::Client) -> Self {
+    Self {
+      base,
+      client,
+    }
+  }
+}
+
+#[cfg(feature = "client")]
+#[async_trait]
+impl MirrorSink for Upstream {
+  async fn mirror(&self, request: MirroredRequest) {
+    let mut url = self.base.clone();
+
+    url.set_path(request.url.path());
+    url.set_query(request.url.query());
+
+    if let Err(e) = self.client.get(url.as_str()).await {
+      debug!(
+        "could not mirror request {} to {url}: {e}",
+        request.request_id
+      );
+    }
+  }
+}
+
+/// A `Module` which asynchronously copies each incoming request to a
+/// [`MirrorSink`], so that traffic can be inspected or replayed elsewhere
+/// without affecting its response.
+///
+/// At most 64 requests are mirrored at once unless another limit is given;
+/// requests arriving beyond the limit are not mirrored.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::module::{Mirror, MirroredRequest};
+///
+/// windmark::router::Router::new().attach(
+///   Mirror::new(|request: MirroredRequest| {
+///     println!("{} {}", request.request_id, request.url);
+///   })
+///   .max_in_flight(16),
+/// );
+/// ```
+pub struct Mirror {
+  sink:          Arc<dyn MirrorSink>,
+  max_in_flight: usize,
+  in_flight:     Arc<AtomicUsize>,
+}
+
+impl Mirror {
+  #[must_use]
+  pub fn new(sink: impl MirrorSink + 'static) -> Self {
+    Self {
+      sink:          Arc::new(sink),
+      max_in_flight: 64,
+      in_flight:     Arc::default(),
+    }
+  }
+
+  /// Set the number of requests which may be mirrored at once.
+  #[must_use]
+  pub const fn max_in_flight(mut self, limit: usize) -> Self {
+    self.max_in_flight = limit;
+
+    self
+  }
+}
+
+/// A mirrored request which is yet to complete, counted until it is dropped
+/// so that a panicking sink does not leak its place.
+struct InFlight(Arc<AtomicUsize>);
+
+impl Drop for InFlight {
+  fn drop(&mut self) { self.0.fetch_sub(1, Ordering::AcqRel); }
+}
+
+impl Module for Mirror {
+  fn on_pre_route(&mut self, context: HookContext) {
+    if self.in_flight.fetch_add(1, Ordering::AcqRel) >= self.max_in_flight {
+      self.in_flight.fetch_sub(1, Ordering::AcqRel);
+      debug!(
+        "not mirroring request {}: too many in flight",
+        context.request_id
+      );
+
+      return;
+    }
+
+    let request = MirroredRequest {
+      fingerprint:  context
+        .certificate
+        .as_deref()
+        .and_then(KnownClients::fingerprint),
+      url:          context.url,
+      peer_address: context.peer_address,
+      listener:     context.listener,
+      request_id:   context.request_id,
+      received_at:  SystemTime::now(),
+    };
+    let sink = self.sink.clone();
+    let in_flight = InFlight(self.in_flight.clone());
+
+    runtime::detach(async move {
+      sink.mirror(request).await;
+      drop(in_flight);
+    });
+  }
+}
@@ -0,0 +1,136 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+  context::RouteContext,
+  module::Module,
+  response::Response,
+  router::{CrawlPolicy, Crawler, Router},
+};
+
+/// A `Module` which mounts a `robots.txt`, as described by the Gemini
+/// `robots.txt` companion specification, built from the crawl policies of
+/// the mounted routes.
+///
+/// Routes with parameters or wildcards are disallowed by the prefix before
+/// their first parameter. Paths which are not mounted as routes, such as the
+/// contents of a served directory, can be disallowed with
+/// [`Robots::disallow`].
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{
+///   module::Robots,
+///   response::Response,
+///   router::{CrawlPolicy, Crawler},
+/// };
+///
+/// windmark::router::Router::new()
+///   .attach(Robots::default().disallow("/files/", Crawler::Archiver))
+///   .mount_with_crawl_policy("/search", CrawlPolicy::disallow_all(), |_| {
+///     Response::success("Search...")
+///   });
+/// ```
+pub struct Robots {
+  route:      String,
+  disallowed: Vec<(Crawler, String)>,
+}
+
+impl Default for Robots {
+  fn default() -> Self { Self::new("/robots.txt") }
+}
+
+impl Robots {
+  /// Create a `robots.txt` which is mounted at `route`.
+  #[must_use]
+  pub fn new(route: impl Into<String>) -> Self {
+    Self {
+      route:      route.into(),
+      disallowed: Vec::new(),
+    }
+  }
+
+  /// Disallow `crawler` from requesting paths beginning with `path`.
+  #[must_use]
+  pub fn disallow(mut self, path: impl Into<String>, crawler: Crawler) -> Self {
+    self.disallowed.push((crawler, path.into()));
+
+    self
+  }
+}
+
+impl Module for Robots {
+  fn on_attach(&mut self, router: &mut Router) {
+    let disallowed = self.disallowed.clone();
+
+    router.mount(self.route.as_str(), move |context: RouteContext| {
+      let mut disallowed = disallowed.clone();
+
+      for route in context.routes() {
+        if let CrawlPolicy::Disallow(crawlers) = route.crawl_policy {
+          let path = route
+            .pattern
+            .find([':', '*'])
+            .map_or(route.pattern.as_str(), |end| &route.pattern[..end])
+            .to_string();
+
+          for crawler in crawlers {
+            disallowed.push((crawler, path.clone()));
+          }
+        }
+      }
+
+      Response::new(20, robots(&disallowed))
+        .mime("text/plain")
+        .character_set("utf-8")
+        .raw()
+    });
+  }
+}
+
+/// Render one record per user agent, in the order they were first
+/// disallowed.
+fn robots(disallowed: &[(Crawler, String)]) -> String {
+  let mut records: Vec<(&str, Vec<&str>)> = Vec::new();
+
+  for (crawler, path) in disallowed {
+    let user_agent = crawler.user_agent();
+
+    match records.iter_mut().find(|(agent, _)| *agent == user_agent) {
+      Some((_, paths)) if paths.contains(&path.as_str()) => {}
+      Some((_, paths)) => paths.push(path),
+      None => records.push((user_agent, vec![path])),
+    }
+  }
+
+  records
+    .into_iter()
+    .map(|(user_agent, paths)| {
+      let mut record = format!("User-agent: {user_agent}\n");
+
+      for path in paths {
+        record.push_str("Disallow: ");
+        record.push_str(path);
+        record.push('\n');
+      }
+
+      record
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
@@ -0,0 +1,445 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+use std::{
+  collections::{HashMap, HashSet},
+  path::{Path, PathBuf},
+  sync::{Arc, RwLock},
+};
+
+use crate::{
+  context::RouteContext,
+  gemtext::{self, Line},
+  module::Module,
+  response::Response,
+  router::{RouteMetadata, Router},
+  utilities::{percent_decode, Pagination},
+};
+
+/// The number of characters an excerpt is cut to.
+const EXCERPT_LENGTH: usize = 160;
+
+/// A document which can be searched.
+#[derive(Clone, Debug)]
+struct Document {
+  path:        String,
+  title:       String,
+  lines:       Vec<String>,
+  terms:       HashMap<String, usize>,
+  title_terms: HashSet<String>,
+}
+
+impl Document {
+  fn new(path: String, title: String, gemtext: &str) -> Self {
+    let lines = gemtext::parse(gemtext)
+      .into_iter()
+      .filter_map(|line| {
+        match line {
+          Line::Text(text)
+          | Line::Heading {
+            text, ..
+          }
+          | Line::ListItem(text)
+          | Line::Quote(text) => Some(text),
+          Line::Link {
+            label, ..
+          } => label,
+          Line::Preformatted {
+            ..
+          } => None,
+        }
+      })
+      .filter(|line| !line.trim().is_empty())
+      .collect::<Vec<_>>();
+    let mut terms = HashMap::new();
+
+    for term in lines.iter().flat_map(|line| tokenize(line)) {
+      *terms.entry(term).or_insert(0) += 1;
+    }
+
+    Self {
+      path,
+      title_terms: tokenize(&title).collect(),
+      title,
+      lines,
+      terms,
+    }
+  }
+}
+
+/// A search result.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+  /// The path of the matching document.
+  pub path:    String,
+  pub title:   String,
+  /// The first line of the document which contains a term of the query.
+  pub excerpt: Option<String>,
+  /// How well the document matches the query, higher being better.
+  pub score:   f64,
+}
+
+/// A `Module` which mounts a full-text search of a directory of gemtext
+/// files, or of registered documents, at `/search`.
+///
+/// Requests without a query are asked for one, and results are ranked by how
+/// often, and how rarely across every document, the terms of the query
+/// appear, with matches in a title counting for more. Results are listed ten
+/// to a page, by default, with the page given by a `page` query parameter.
+///
+/// Directories are read when the module is attached, and can be read again
+/// with [`Search::reindex`], e.g., from a [`Watch`](crate::module::Watch).
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::module::Search;
+///
+/// windmark::router::Router::new().attach(
+///   Search::default().directory("/gemlog", "./gemlog").document(
+///     "/about",
+///     "About",
+///     "A capsule about crabs.",
+///   ),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct Search {
+  route:       String,
+  title:       String,
+  limit:       usize,
+  per_page:    usize,
+  directories: Vec<(String, PathBuf)>,
+  registered:  Vec<Document>,
+  #[cfg(feature = "content")]
+  content:     Vec<crate::module::Content>,
+  documents:   Arc<RwLock<Arc<Vec<Document>>>>,
+}
+
+impl Default for Search {
+  fn default() -> Self { Self::new("/search") }
+}
+
+impl Search {
+  /// Create a search which is mounted at `route`.
+  #[must_use]
+  pub fn new(route: impl Into<String>) -> Self {
+    Self {
+      route: route.into(),
+      title: "Search".to_string(),
+      limit: 100,
+      per_page: 10,
+      directories: Vec::new(),
+      registered: Vec::new(),
+      #[cfg(feature = "content")]
+      content: Vec::new(),
+      documents: Arc::default(),
+    }
+  }
+
+  /// Set the heading of the results, which is also used to ask for a query.
+  #[must_use]
+  pub fn title(mut self, title: impl Into<String>) -> Self {
+    self.title = title.into();
+
+    self
+  }
+
+  /// Set the maximum number of results found.
+  #[must_use]
+  pub const fn limit(mut self, limit: usize) -> Self {
+    self.limit = limit;
+
+    self
+  }
+
+  /// Set the number of results listed on each page of results.
+  ///
+  /// # Panics
+  ///
+  /// if `per_page` is zero.
+  #[must_use]
+  pub const fn per_page(mut self, per_page: usize) -> Self {
+    assert!(per_page > 0, "a page must hold at least one result");
+
+    self.per_page = per_page;
+
+    self
+  }
+
+  /// Search the gemtext files within `directory`, and its subdirectories,
+  /// which are served at `route`, e.g., by [`Router::mount_directory`].
+  #[must_use]
+  pub fn directory(
+    mut self,
+    route: impl Into<String>,
+    directory: impl Into<PathBuf>,
+  ) -> Self {
+    self.directories.push((
+      route.into().trim_end_matches('/').to_string(),
+      directory.into(),
+    ));
+
+    self
+  }
+
+  /// Search the pages served by `content`, as they are when searched.
+  #[cfg(feature = "content")]
+  #[must_use]
+  pub fn content(mut self, content: &crate::module::Content) -> Self {
+    self.content.push(content.clone());
+
+    self
+  }
+
+  /// Search a gemtext document served at `path`.
+  #[must_use]
+  pub fn document(
+    mut self,
+    path: impl Into<String>,
+    title: impl Into<String>,
+    gemtext: impl AsRef<str>,
+  ) -> Self {
+    self.registered.push(Document::new(
+      path.into(),
+      title.into(),
+      gemtext.as_ref(),
+    ));
+
+    self
+  }
+
+  /// Read the directories again, replacing the documents read from them
+  /// before.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the index's lock is poisoned.
+  pub fn reindex(&self) {
+    let mut documents = self.registered.clone();
+
+    for (route, directory) in &self.directories {
+      if let Err(e) = read(directory, route, &mut documents) {
+        warn!("could not index {}: {}", directory.display(), e);
+      }
+    }
+
+    *self.documents.write().unwrap() = Arc::new(documents);
+  }
+
+  /// The best matches for `query`, best first.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the index's lock is poisoned.
+  #[must_use]
+  pub fn search(&self, query: &str) -> Vec<SearchResult> {
+    let query = tokenize(query).collect::<HashSet<_>>();
+
+    if query.is_empty() {
+      return Vec::new();
+    }
+
+    let indexed = self.documents.read().unwrap().clone();
+    #[cfg(feature = "content")]
+    let pages = self
+      .content
+      .iter()
+      .flat_map(crate::module::Content::pages)
+      .map(|page| Document::new(page.path, page.title, &page.body))
+      .collect::<Vec<_>>();
+    #[cfg(not(feature = "content"))]
+    let pages = Vec::new();
+    let documents = indexed.iter().chain(&pages).collect::<Vec<_>>();
+    #[allow(clippy::cast_precision_loss)]
+    let total = documents.len() as f64;
+    let mut results = Vec::new();
+
+    for document in &documents {
+      let mut score = 0.0;
+
+      for term in &query {
+        let occurrences = document.terms.get(term).copied().unwrap_or(0)
+          + usize::from(document.title_terms.contains(term)) * 3;
+
+        if occurrences == 0 {
+          continue;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let frequency = documents
+          .iter()
+          .filter(|other| {
+            other.terms.contains_key(term) || other.title_terms.contains(term)
+          })
+          .count() as f64;
+        #[allow(clippy::cast_precision_loss)]
+        let weight = occurrences as f64;
+
+        score += weight * (total / frequency).ln_1p();
+      }
+
+      if score > 0.0 {
+        results.push(SearchResult {
+          path: document.path.clone(),
+          title: document.title.clone(),
+          excerpt: excerpt(document, &query),
+          score,
+        });
+      }
+    }
+
+    results.sort_by(|left, right| {
+      right
+        .score
+        .total_cmp(&left.score)
+        .then_with(|| left.path.cmp(&right.path))
+    });
+    results.truncate(self.limit);
+
+    results
+  }
+
+  fn respond(&self, context: &RouteContext) -> Response {
+    // Links to other pages of results add a `page` parameter to the query.
+    let query = context
+      .url
+      .query()
+      .unwrap_or_default()
+      .split('&')
+      .filter(|pair| !pair.starts_with("page="))
+      .map(percent_decode)
+      .collect::<Vec<_>>()
+      .join(" ");
+
+    if query.trim().is_empty() {
+      return Response::input(self.title.as_str());
+    }
+
+    let results = self.search(&query);
+    let pagination =
+      Pagination::from_url(&context.url, results.len(), self.per_page);
+    let mut document = gemtext::Document::new();
+
+    document.heading(1, self.title.as_str()).blank();
+
+    if results.is_empty() {
+      document.text(format!("No results were found for \"{}\".", query.trim()));
+    } else {
+      document.text(format!(
+        "{} result{} for \"{}\":",
+        results.len(),
+        if results.len() == 1 { "" } else { "s" },
+        query.trim()
+      ));
+
+      for result in pagination.items(&results) {
+        document
+          .blank()
+          .link(result.path.as_str(), result.title.as_str());
+
+        if let Some(excerpt) = &result.excerpt {
+          document.quote(excerpt.as_str());
+        }
+      }
+
+      pagination.links(&mut document, &context.url);
+    }
+
+    document.blank().link(self.route.as_str(), "Search again");
+
+    document.into()
+  }
+}
+
+impl Module for Search {
+  fn on_attach(&mut self, router: &mut Router) {
+    self.reindex();
+
+    let search = self.clone();
+
+    router.mount_with_metadata(
+      self.route.as_str(),
+      RouteMetadata::new().title(self.title.as_str()),
+      move |context: RouteContext| search.respond(&context),
+    );
+  }
+}
+
+/// The lowercase words of `text`.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+  text
+    .split(|character: char| !character.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(str::to_lowercase)
+}
+
+/// The first line of `document` which contains a term of `query`, cut to
+/// [`EXCERPT_LENGTH`] characters.
+fn excerpt(document: &Document, query: &HashSet<String>) -> Option<String> {
+  let line = document
+    .lines
+    .iter()
+    .find(|line| tokenize(line).any(|term| query.contains(&term)))?;
+
+  Some(match line.char_indices().nth(EXCERPT_LENGTH) {
+    Some((end, _)) => format!("{}…", line[..end].trim_end()),
+    None => line.clone(),
+  })
+}
+
+/// Read the gemtext files within `directory`, and its subdirectories, as
+/// documents beneath `route`.
+fn read(
+  directory: &Path,
+  route: &str,
+  documents: &mut Vec<Document>,
+) -> std::io::Result<()> {
+  for file in std::fs::read_dir(directory)? {
+    let path = file?.path();
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+      continue;
+    };
+
+    if name.starts_with('.') {
+      continue;
+    }
+
+    if path.is_dir() {
+      read(&path, &format!("{route}/{name}"), documents)?;
+    } else if path
+      .extension()
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("gmi"))
+    {
+      let gemtext = std::fs::read_to_string(&path)?;
+      let title = gemtext::parse(&gemtext)
+        .into_iter()
+        .find_map(|line| {
+          match line {
+            Line::Heading {
+              text, ..
+            } => Some(text),
+            _ => None,
+          }
+        })
+        .unwrap_or_else(|| name.to_string());
+
+      documents.push(Document::new(format!("{route}/{name}"), title, &gemtext));
+    }
+  }
+
+  Ok(())
+}
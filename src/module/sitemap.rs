@@ -0,0 +1,127 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+  context::RouteContext,
+  module::Module,
+  response::Response,
+  router::Router,
+};
+
+/// A `Module` which mounts a gemtext listing of every mounted route.
+///
+/// Routes with parameters or wildcards are listed without a link, as they do
+/// not name a single page, and hidden routes are left out. Titles and
+/// descriptions supplied with [`Router::mount_with_metadata`] or
+/// [`Router::mount_with_description`] are used as link labels.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{module::Sitemap, response::Response};
+///
+/// windmark::router::Router::new()
+///   .attach(Sitemap::default())
+///   .mount_with_description("/", "Home", |_| Response::success("Hello!"));
+/// ```
+pub struct Sitemap {
+  route:   String,
+  title:   String,
+  #[cfg(feature = "content")]
+  content: Vec<crate::module::Content>,
+}
+
+impl Default for Sitemap {
+  fn default() -> Self { Self::new("/sitemap.gmi") }
+}
+
+impl Sitemap {
+  /// Create a sitemap which is mounted at `route`.
+  #[must_use]
+  pub fn new(route: impl Into<String>) -> Self {
+    Self {
+      route: route.into(),
+      title: "Sitemap".to_string(),
+      #[cfg(feature = "content")]
+      content: Vec::new(),
+    }
+  }
+
+  /// Set the heading of the sitemap.
+  #[must_use]
+  pub fn title(mut self, title: impl Into<String>) -> Self {
+    self.title = title.into();
+
+    self
+  }
+
+  /// List the pages served by `content` after the mounted routes.
+  #[cfg(feature = "content")]
+  #[must_use]
+  pub fn content(mut self, content: &crate::module::Content) -> Self {
+    self.content.push(content.clone());
+
+    self
+  }
+}
+
+impl Module for Sitemap {
+  fn on_attach(&mut self, router: &mut Router) {
+    let title = self.title.clone();
+    let own_route = self.route.clone();
+    #[cfg(feature = "content")]
+    let content = self.content.clone();
+
+    router.mount(self.route.as_str(), move |context: RouteContext| {
+      let lines = context
+        .routes()
+        .into_iter()
+        .filter(|route| !route.hidden && route.pattern != own_route)
+        .map(|route| {
+          let label = match (&route.title, &route.description) {
+            (Some(title), Some(description)) =>
+              format!("{title}: {description}"),
+            (Some(label), None) | (None, Some(label)) => label.clone(),
+            (None, None) => route.pattern.clone(),
+          };
+
+          if route.pattern.contains([':', '*']) {
+            format!("* {label} ({})", route.pattern)
+          } else {
+            format!("=> {} {label}", route.pattern)
+          }
+        })
+        .collect::<Vec<_>>();
+
+      #[cfg(feature = "content")]
+      let lines = {
+        let mut lines = lines;
+
+        lines.extend(content.iter().flat_map(|content| {
+          content
+            .pages()
+            .into_iter()
+            .map(|page| format!("=> {} {}", page.path, page.title))
+        }));
+
+        lines
+      };
+
+      Response::success(format!("# {title}\n\n{}", lines.join("\n")))
+    });
+  }
+}
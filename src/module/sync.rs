@@ -17,13 +17,35 @@
 
 use crate::context::HookContext;
 
+/// A stateful module.
+///
+/// A panic within a hook is caught and logged along with the location the
+/// module was attached from, without affecting other modules or the request
+/// being handled.
 pub trait Module {
   /// Called right after the module is attached.
   fn on_attach(&mut self, _: &mut crate::router::Router) {}
 
+  /// Called once when the `Router` starts accepting connections, with the
+  /// address of its first listener. The address of every listener is passed
+  /// to the callback set with
+  /// [`Router::set_on_listen`](crate::router::Router::set_on_listen).
+  ///
+  /// A module attached to a running `Router` is called right after
+  /// `on_attach`.
+  fn on_start(&mut self, _: std::net::SocketAddr) {}
+
+  /// Called after a shutdown requested with `Router::shutdown`, once every
+  /// open connection has been handled, or when the module is detached from a
+  /// running `Router`.
+  fn on_shutdown(&mut self) {}
+
   /// Called before a route is mounted.
   fn on_pre_route(&mut self, _: HookContext) {}
 
   /// Called after a route is mounted.
+  ///
+  /// The context includes the elapsed time, status, and size of the
+  /// response.
   fn on_post_route(&mut self, _: HookContext) {}
 }
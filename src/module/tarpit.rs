@@ -0,0 +1,320 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  collections::HashMap,
+  net::IpAddr,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use crate::{
+  module::Module,
+  router::{
+    guard::{Admission, Guard},
+    Router,
+  },
+};
+
+/// The number of tracked peers above which expired offences are pruned.
+const PRUNE_AFTER: usize = 1024;
+
+type BanCallback = dyn Fn(IpAddr, Duration) + Send + Sync;
+
+struct Offender {
+  strikes:      usize,
+  first:        Instant,
+  banned_until: Option<Instant>,
+}
+
+#[derive(Clone)]
+struct Settings {
+  statuses:        Vec<i32>,
+  window:          Duration,
+  delay_after:     usize,
+  delay:           Duration,
+  slow_down_after: usize,
+  ban_after:       usize,
+  ban:             Duration,
+  on_ban:          Option<Arc<BanCallback>>,
+}
+
+/// A `Module` which escalates against peers making repeated bad requests.
+///
+/// Each response with one of the tracked statuses (by default `59`, `61`,
+/// and `62`), as is each request made while being told to slow down, is a
+/// strike against the IP address of the peer. Strikes are
+/// forgotten once the window they were made within has passed. As a peer's
+/// strikes add up, its requests are first delayed, then answered with `44`,
+/// and finally its connections are refused outright for the duration of a
+/// ban.
+///
+/// Bans can be exported, e.g., to a firewall, with [`Tarpit::on_ban`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use windmark::module::Tarpit;
+///
+/// windmark::router::Router::new().attach(
+///   Tarpit::default()
+///     .ban_after(20, Duration::from_secs(60 * 60))
+///     .on_ban(|ip, duration| {
+///       println!("banned {ip} for {} seconds", duration.as_secs());
+///     }),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct Tarpit {
+  settings:  Settings,
+  offenders: Arc<Mutex<HashMap<IpAddr, Offender>>>,
+}
+
+impl Default for Tarpit {
+  fn default() -> Self {
+    Self {
+      settings:  Settings {
+        statuses:        vec![59, 61, 62],
+        window:          Duration::from_secs(60),
+        delay_after:     3,
+        delay:           Duration::from_secs(1),
+        slow_down_after: 6,
+        ban_after:       10,
+        ban:             Duration::from_secs(600),
+        on_ban:          None,
+      },
+      offenders: Arc::default(),
+    }
+  }
+}
+
+impl Tarpit {
+  /// The statuses which count as a strike against a peer.
+  #[must_use]
+  pub fn statuses(mut self, statuses: impl IntoIterator<Item = i32>) -> Self {
+    self.settings.statuses = statuses.into_iter().collect();
+
+    self
+  }
+
+  /// How long strikes are remembered for, counting from a peer's first.
+  #[must_use]
+  pub const fn window(mut self, window: Duration) -> Self {
+    self.settings.window = window;
+
+    self
+  }
+
+  /// Delay the requests of a peer with at least `strikes` strikes by
+  /// `delay`.
+  #[must_use]
+  pub const fn delay_after(mut self, strikes: usize, delay: Duration) -> Self {
+    self.settings.delay_after = strikes;
+    self.settings.delay = delay;
+
+    self
+  }
+
+  /// Answer the requests of a peer with at least `strikes` strikes with `44`
+  /// until its window has passed.
+  #[must_use]
+  pub const fn slow_down_after(mut self, strikes: usize) -> Self {
+    self.settings.slow_down_after = strikes;
+
+    self
+  }
+
+  /// Refuse the connections of a peer for `duration` once it reaches
+  /// `strikes` strikes.
+  #[must_use]
+  pub const fn ban_after(mut self, strikes: usize, duration: Duration) -> Self {
+    self.settings.ban_after = strikes;
+    self.settings.ban = duration;
+
+    self
+  }
+
+  /// Call `callback` with the IP address and duration of each new ban.
+  ///
+  /// The callback is called while a request is being handled, so a slow
+  /// export should be moved onto another thread or task.
+  #[must_use]
+  pub fn on_ban(
+    mut self,
+    callback: impl Fn(IpAddr, Duration) + Send + Sync + 'static,
+  ) -> Self {
+    self.settings.on_ban = Some(Arc::new(callback));
+
+    self
+  }
+
+  /// The banned IP addresses along with the time remaining on their bans.
+  #[must_use]
+  pub fn bans(&self) -> Vec<(IpAddr, Duration)> {
+    let now = Instant::now();
+
+    self.offenders.lock().map_or_else(
+      |_| Vec::new(),
+      |offenders| {
+        offenders
+          .iter()
+          .filter_map(|(ip, offender)| {
+            offender
+              .banned_until
+              .filter(|until| *until > now)
+              .map(|until| (*ip, until - now))
+          })
+          .collect()
+      },
+    )
+  }
+
+  /// How a request from `offender` is treated.
+  fn admission(&self, offender: &Offender, now: Instant) -> Admission {
+    let remaining =
+      (offender.first + self.settings.window).saturating_duration_since(now);
+
+    if let Some(until) = offender.banned_until.filter(|until| *until > now) {
+      return Admission::SlowDown(until - now);
+    }
+
+    if remaining.is_zero() {
+      Admission::Allow
+    } else if offender.strikes >= self.settings.slow_down_after {
+      Admission::SlowDown(remaining)
+    } else if offender.strikes >= self.settings.delay_after {
+      Admission::Delay(self.settings.delay)
+    } else {
+      Admission::Allow
+    }
+  }
+
+  /// Count a strike against `ip`, banning it once it has too many.
+  fn strike(&self, ip: IpAddr) {
+    let now = Instant::now();
+    let Ok(mut offenders) = self.offenders.lock() else {
+      return;
+    };
+
+    if offenders.len() >= PRUNE_AFTER {
+      let window = self.settings.window;
+
+      offenders.retain(|_, offender| {
+        offender.banned_until.is_some_and(|until| until > now)
+          || offender.first + window > now
+      });
+    }
+
+    let offender = offenders.entry(ip).or_insert(Offender {
+      strikes:      0,
+      first:        now,
+      banned_until: None,
+    });
+
+    if offender.banned_until.is_some_and(|until| until > now) {
+      return;
+    }
+
+    if offender.first + self.settings.window <= now {
+      offender.strikes = 0;
+      offender.first = now;
+    }
+
+    offender.strikes += 1;
+
+    if offender.strikes < self.settings.ban_after {
+      return;
+    }
+
+    offender.strikes = 0;
+    offender.banned_until = Some(now + self.settings.ban);
+
+    drop(offenders);
+    warn!(
+      "banning {ip} for {} seconds after repeated bad requests",
+      self.settings.ban.as_secs()
+    );
+
+    if let Some(on_ban) = &self.settings.on_ban {
+      on_ban(ip, self.settings.ban);
+    }
+  }
+
+  /// Lift the ban on, and forget the strikes of, `ip`, returning whether it
+  /// was banned.
+  #[allow(clippy::must_use_candidate)]
+  pub fn unban(&self, ip: IpAddr) -> bool {
+    self
+      .offenders
+      .lock()
+      .ok()
+      .and_then(|mut offenders| offenders.remove(&ip))
+      .and_then(|offender| offender.banned_until)
+      .is_some_and(|until| until > Instant::now())
+  }
+}
+
+impl Module for Tarpit {
+  fn on_attach(&mut self, router: &mut Router) {
+    router.add_guard(Arc::new(self.clone()));
+  }
+}
+
+impl Guard for Tarpit {
+  fn accepts(&self, ip: IpAddr) -> bool {
+    let now = Instant::now();
+
+    let banned = self.offenders.lock().is_ok_and(|offenders| {
+      offenders
+        .get(&ip)
+        .and_then(|offender| offender.banned_until)
+        .is_some_and(|until| until > now)
+    });
+
+    !banned
+  }
+
+  fn admit(&self, ip: IpAddr) -> Admission {
+    let now = Instant::now();
+    let admission = self
+      .offenders
+      .lock()
+      .ok()
+      .and_then(|offenders| {
+        offenders
+          .get(&ip)
+          .map(|offender| self.admission(offender, now))
+      })
+      .unwrap_or(Admission::Allow);
+
+    // A peer which keeps making requests while being told to slow down is
+    // misbehaving just the same.
+    if matches!(admission, Admission::SlowDown(_)) {
+      self.strike(ip);
+    }
+
+    admission
+  }
+
+  fn record(&self, ip: IpAddr, status: i32) {
+    if self.settings.statuses.contains(&status) {
+      self.strike(ip);
+    }
+  }
+}
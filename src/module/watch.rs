@@ -0,0 +1,180 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+use std::{
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::{Duration, SystemTime},
+};
+
+use crate::{module::Module, router::runtime};
+
+type Callback = Arc<dyn Fn() + Send + Sync>;
+
+/// A `Module` which calls back when the files within a set of paths change,
+/// e.g., to clear a template cache or to reload a directory of content after
+/// a post is edited, without restarting the server.
+///
+/// Paths are polled, by default every two seconds, once the `Router` starts
+/// accepting connections. A file which is added, removed, or modified counts
+/// as a change.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use windmark::module::Watch;
+///
+/// windmark::router::Router::new().attach(
+///   Watch::new("./public")
+///     .interval(Duration::from_secs(5))
+///     .on_change(|| println!("./public changed")),
+/// );
+/// ```
+pub struct Watch {
+  paths:     Vec<PathBuf>,
+  interval:  Duration,
+  callbacks: Vec<Callback>,
+  stop:      Option<Arc<AtomicBool>>,
+}
+
+impl Watch {
+  /// Watch the file or directory at `path`.
+  #[must_use]
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    Self {
+      paths:     vec![path.into()],
+      interval:  Duration::from_secs(2),
+      callbacks: Vec::new(),
+      stop:      None,
+    }
+  }
+
+  /// Also watch the file or directory at `path`.
+  #[must_use]
+  pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+    self.paths.push(path.into());
+
+    self
+  }
+
+  /// Set how often the paths are checked for changes.
+  #[must_use]
+  pub const fn interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+
+    self
+  }
+
+  /// Call `callback` whenever a change is found.
+  #[must_use]
+  pub fn on_change(
+    mut self,
+    callback: impl Fn() + Send + Sync + 'static,
+  ) -> Self {
+    self.callbacks.push(Arc::new(callback));
+
+    self
+  }
+}
+
+impl Module for Watch {
+  fn on_start(&mut self, _: std::net::SocketAddr) {
+    if self.stop.is_some() {
+      return;
+    }
+
+    let callbacks = self.callbacks.clone();
+
+    self.stop = Some(poll(self.paths.clone(), self.interval, move || {
+      for callback in &callbacks {
+        callback();
+      }
+    }));
+  }
+
+  fn on_shutdown(&mut self) {
+    if let Some(stop) = self.stop.take() {
+      stop.store(true, Ordering::Relaxed);
+    }
+  }
+}
+
+/// Check `paths` for changes every `interval`, calling `on_change` for each
+/// change found, until the returned flag is set.
+pub fn poll(
+  paths: Vec<PathBuf>,
+  interval: Duration,
+  on_change: impl Fn() + Send + 'static,
+) -> Arc<AtomicBool> {
+  let stop = Arc::new(AtomicBool::new(false));
+  let stopped = stop.clone();
+
+  runtime::detach(async move {
+    let mut previous = snapshot(&paths);
+
+    loop {
+      runtime::sleep(interval).await;
+
+      if stopped.load(Ordering::Relaxed) {
+        break;
+      }
+
+      let current = snapshot(&paths);
+
+      if current != previous {
+        debug!("change found within {paths:?}");
+        previous = current;
+        on_change();
+      }
+    }
+  });
+
+  stop
+}
+
+/// The modification time and length of every file within `paths`.
+fn snapshot(paths: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+  let mut files = Vec::new();
+
+  for path in paths {
+    visit(path, &mut files);
+  }
+
+  files.sort();
+
+  files
+}
+
+fn visit(path: &Path, files: &mut Vec<(PathBuf, Option<SystemTime>, u64)>) {
+  let Ok(metadata) = std::fs::metadata(path) else {
+    return;
+  };
+
+  if metadata.is_dir() {
+    if let Ok(entries) = std::fs::read_dir(path) {
+      for entry in entries.flatten() {
+        visit(&entry.path(), files);
+      }
+    }
+  } else {
+    files.push((path.to_path_buf(), metadata.modified().ok(), metadata.len()));
+  }
+}
@@ -17,88 +17,376 @@
 
 //! Content and response handlers
 
+/// Invoke `$callback` with every `Response` constructor which only takes its
+/// content, along with its status.
+///
+/// Both the constructors and their macros are generated from this list, so
+/// the two can not fall out of sync; `tests/response_macros.rs` checks that
+/// every one of them, along with `success`, has both macros.
+macro_rules! content_responses {
+  ($callback:ident) => {
+    $callback! {
+      input = 10,
+      sensitive_input = 11,
+      temporary_redirect = 30,
+      permanent_redirect = 31,
+      temporary_failure = 40,
+      server_unavailable = 41,
+      cgi_error = 42,
+      proxy_error = 43,
+      slow_down = 44,
+      permanent_failure = 50,
+      not_found = 51,
+      gone = 52,
+      proxy_refused = 53,
+      bad_request = 59,
+      client_certificate_required = 60,
+      certificate_not_authorised = 61,
+      certificate_not_valid = 62,
+    }
+  };
+}
+
+mod failure;
 #[cfg(feature = "response-macros")]
 mod macros;
+mod stream;
+
+pub use failure::{Failure, IntoResponse, Status};
+pub(crate) use stream::{BodyReceiver, Production};
+pub use stream::{BodySender, Disconnected};
+
+/// The maximum length of a response's meta, in bytes.
+pub const MAX_META_LENGTH: usize = 1024;
 
 macro_rules! response {
-  ($name:ident, $status:expr) => {
-    pub fn $name<S>(content: S) -> Self
-    where S: Into<String> + AsRef<str> {
-      Self::new($status, content.into())
-    }
+  ($($name:ident = $status:expr),* $(,)?) => {
+    $(
+      pub fn $name<S>(content: S) -> Self
+      where S: Into<String> + AsRef<str> {
+        Self::new($status, content.into())
+      }
+    )*
   };
 }
 
 /// The content and response type a handler should reply with.
 #[derive(Clone)]
 pub struct Response {
-  pub status:        i32,
-  pub mime:          Option<String>,
-  pub content:       String,
-  pub character_set: Option<String>,
-  pub languages:     Option<Vec<String>>,
+  pub status:          i32,
+  pub mime:            Option<String>,
+  pub content:         String,
+  pub character_set:   Option<String>,
+  pub languages:       Option<Vec<String>>,
+  /// Send `content` exactly as it is, without the `Router`'s headers and
+  /// footers. The meta of a raw response with a non-text MIME type carries
+  /// no character set or language parameters.
+  pub raw_body:        bool,
+  /// The body of a binary response, sent in place of `content`.
+  pub(crate) binary:   Option<Vec<u8>>,
+  /// The producer of a streamed body, sent after `content`.
+  pub(crate) body:     Option<stream::Body>,
+  /// The early failure this response was made from, if any.
+  pub(crate) failure:  Option<Failure>,
+  /// The error this response was made from, if any.
+  pub(crate) error: Option<std::sync::Arc<dyn std::error::Error + Send + Sync>>,
+  /// The template which `content` is rendered from by the `Router`.
+  #[cfg(feature = "templates")]
+  pub(crate) template: Option<(String, crate::template::Context)>,
 }
 
 impl Response {
-  response!(input, 10);
+  content_responses!(response);
 
-  response!(sensitive_input, 11);
+  #[allow(clippy::needless_pass_by_value)]
+  pub fn success(content: impl ToString) -> Self {
+    Self::new(20, content.to_string())
+      .mime("text/gemini")
+      .languages(["en"])
+      .character_set("utf-8")
+  }
 
-  response!(temporary_redirect, 30);
+  /// A successful Gemini response sent without the `Router`'s headers and
+  /// footers.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let response = windmark::response::Response::success_raw("# Hello!");
+  ///
+  /// assert!(response.raw_body);
+  /// ```
+  #[must_use]
+  pub fn success_raw(content: impl ToString) -> Self {
+    Self::success(content).raw()
+  }
 
-  response!(permanent_redirect, 31);
+  /// A successful Gemini response whose body is sent chunk by chunk as
+  /// `producer` produces it, keeping the connection open until it finishes.
+  ///
+  /// The `Router`'s header is sent before the first chunk and its footer after
+  /// the last. Sending waits while the client falls behind, and fails once it
+  /// disconnects, at which point `producer` is dropped.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new().mount("/count", |_| {
+  ///   Response::stream(|body| {
+  ///     async move {
+  ///       for number in 1.. {
+  ///         body.send(format!("{number}\n")).await?;
+  ///         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+  ///       }
+  ///
+  ///       Ok(())
+  ///     }
+  ///   })
+  /// });
+  /// ```
+  #[must_use]
+  pub fn stream<F, Fut>(producer: F) -> Self
+  where
+    F: FnOnce(BodySender) -> Fut + Send + 'static,
+    Fut:
+      std::future::Future<Output = Result<(), Disconnected>> + Send + 'static,
+  {
+    let mut response = Self::success("");
 
-  response!(temporary_failure, 40);
+    response.body = Some(stream::Body::new(producer));
 
-  response!(server_unavailable, 41);
+    response
+  }
 
-  response!(cgi_error, 42);
+  /// The body as it is sent, which for a binary response, such as one made
+  /// with [`Response::binary_success`], is carried as bytes rather than in
+  /// `content`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// let response = Response::binary_success([0xff, 0xd8], "image/jpeg");
+  ///
+  /// assert_eq!(response.content_bytes(), [0xff, 0xd8]);
+  /// ```
+  #[must_use]
+  pub fn content_bytes(&self) -> &[u8] {
+    self.binary.as_deref().unwrap_or(self.content.as_bytes())
+  }
 
-  response!(proxy_error, 43);
+  /// Whether the body is streamed, as with [`Response::stream`].
+  #[must_use]
+  pub const fn is_streamed(&self) -> bool { self.body.is_some() }
 
-  response!(slow_down, 44);
+  #[must_use]
+  pub fn binary_success(
+    content: impl AsRef<[u8]>,
+    mime: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    let mut response = Self::new(20, "").mime(mime).raw();
 
-  response!(permanent_failure, 50);
+    response.binary = Some(content.as_ref().to_vec());
 
-  response!(not_found, 51);
+    response
+  }
 
-  response!(gone, 52);
+  #[cfg(feature = "auto-deduce-mime")]
+  #[must_use]
+  pub fn binary_success_auto(content: &[u8]) -> Self {
+    Self::binary_success(content, tree_magic::from_u8(content))
+  }
 
-  response!(proxy_refused, 53);
+  /// A successful response whose MIME type is chosen from the extension of
+  /// `path` alone, using the default [`MimeTypes`](crate::mime::MimeTypes).
+  ///
+  /// Unlike `binary_success_auto`, the content is never inspected. Gemini
+  /// documents are answered like [`Response::success`], other text with its
+  /// MIME type and a UTF-8 character set, and anything else as binary, with
+  /// unknown extensions becoming `application/octet-stream`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let response =
+  ///   windmark::response::Response::success_file_ext("notes.txt", "Hello!");
+  ///
+  /// assert_eq!(response.mime.as_deref(), Some("text/plain"));
+  /// ```
+  #[must_use]
+  pub fn success_file_ext(
+    path: impl AsRef<std::path::Path>,
+    content: impl AsRef<[u8]>,
+  ) -> Self {
+    Self::success_mime(
+      content.as_ref(),
+      &crate::mime::defaults()
+        .for_path(path)
+        .unwrap_or_else(|| "application/octet-stream".to_string()),
+    )
+  }
 
-  response!(bad_request, 59);
+  /// Read the file at `path` into a successful response, or a not found
+  /// response if there is no such file.
+  ///
+  /// The MIME type is chosen from the extension of `path` using the default
+  /// [`MimeTypes`](crate::mime::MimeTypes), falling back to content
+  /// inspection with `auto-deduce-mime`, or to `application/octet-stream`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new()
+  ///   .mount("/", |_| Response::file("public/index.gmi"));
+  /// ```
+  pub async fn file(path: impl AsRef<std::path::Path> + Send) -> Self {
+    let path = path.as_ref();
+
+    match crate::router::runtime::read(path).await {
+      Ok(content) =>
+        Self::success_bytes(&content, crate::mime::defaults().for_path(path)),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound || path.is_dir() =>
+        Self::not_found("The requested resource could not be found."),
+      Err(e) => {
+        warn!("could not read {}: {}", path.display(), e);
+
+        Self::temporary_failure("The requested resource could not be read.")
+      }
+    }
+  }
 
-  response!(client_certificate_required, 60);
+  /// A successful gemtext response rendered from the template `name`, within
+  /// the [`Templates`](crate::template::Templates) set on the `Router`, with
+  /// `context`.
+  ///
+  /// The template is rendered once the route handler has responded. If it can
+  /// not be rendered, the error handler responds in its place with
+  /// [`ErrorKind::Handler`](crate::context::ErrorKind::Handler).
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{
+  ///   context::RouteContext,
+  ///   response::Response,
+  ///   template::Context,
+  /// };
+  ///
+  /// windmark::router::Router::new().mount(
+  ///   "/hello/:name",
+  ///   |context: RouteContext| {
+  ///     Response::template(
+  ///       "hello.gmi",
+  ///       Context::new().insert(
+  ///         "name",
+  ///         context.parameters.get("name").cloned().unwrap_or_default(),
+  ///       ),
+  ///     )
+  ///   },
+  /// );
+  /// ```
+  #[cfg(feature = "templates")]
+  #[must_use]
+  pub fn template(
+    name: impl Into<String>,
+    context: crate::template::Context,
+  ) -> Self {
+    let mut response = Self::success("");
 
-  response!(certificate_not_authorised, 61);
+    response.template = Some((name.into(), context));
 
-  response!(certificate_not_valid, 62);
+    response
+  }
 
-  #[allow(clippy::needless_pass_by_value)]
-  pub fn success(content: impl ToString) -> Self {
-    Self::new(20, content.to_string())
-      .with_mime("text/gemini")
-      .with_languages(["en"])
-      .with_character_set("utf-8")
-      .clone()
+  /// A response made from a route handler's `error`, which the error handler
+  /// responds in place of with
+  /// [`ErrorKind::Handler`](crate::context::ErrorKind::Handler).
+  ///
+  /// Route handlers which return a `Result` with a boxed error are replied to
+  /// with this response when they fail, so that errors can be propagated with
+  /// `?`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new()
+  ///   .mount("/motd", |_| {
+  ///     async {
+  ///       match std::fs::read_to_string("motd.gmi") {
+  ///         Ok(motd) => Response::success(motd),
+  ///         Err(e) => Response::error(e),
+  ///       }
+  ///     }
+  ///   })
+  ///   .mount("/version", |_| {
+  ///     async {
+  ///       let version = std::fs::read_to_string("VERSION")?;
+  ///
+  ///       Ok::<_, Box<dyn std::error::Error + Send + Sync>>(Response::success(
+  ///         version,
+  ///       ))
+  ///     }
+  ///   });
+  /// ```
+  pub fn error(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+    let mut response =
+      Self::temporary_failure(Status::TemporaryFailure.message());
+
+    response.error = Some(std::sync::Arc::new(error));
+
+    response
   }
 
+  /// A successful gemtext response converted from `markdown`, as described by
+  /// [`gemtext::from_markdown`](crate::gemtext::from_markdown).
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new().mount("/", |_| {
+  ///   Response::from_markdown("# Hello\n\nThis page was written in *Markdown*.")
+  /// });
+  /// ```
+  #[cfg(feature = "markdown")]
   #[must_use]
-  pub fn binary_success(
-    content: impl AsRef<[u8]>,
-    mime: impl Into<String> + AsRef<str>,
-  ) -> Self {
-    Self::new(21, String::from_utf8_lossy(content.as_ref()))
-      .with_mime(mime)
-      .clone()
+  pub fn from_markdown(markdown: impl AsRef<str>) -> Self {
+    Self::success(crate::gemtext::from_markdown(markdown.as_ref()))
   }
 
-  #[cfg(feature = "auto-deduce-mime")]
-  #[must_use]
-  pub fn binary_success_auto(content: &[u8]) -> Self {
-    Self::new(22, String::from_utf8_lossy(content))
-      .with_mime(tree_magic::from_u8(content))
-      .clone()
+  /// A successful response for `content` of the MIME type `mime`, if known.
+  pub(crate) fn success_bytes(content: &[u8], mime: Option<String>) -> Self {
+    mime.map_or_else(
+      || {
+        #[cfg(feature = "auto-deduce-mime")]
+        return Self::binary_success_auto(content);
+
+        #[cfg(not(feature = "auto-deduce-mime"))]
+        Self::binary_success(content, "application/octet-stream")
+      },
+      |mime| Self::success_mime(content, &mime),
+    )
+  }
+
+  /// A successful response for `content` of the MIME type `mime`.
+  pub(crate) fn success_mime(content: &[u8], mime: &str) -> Self {
+    if mime == "text/gemini" {
+      Self::success(String::from_utf8_lossy(content))
+    } else if mime.starts_with("text/") {
+      Self::new(20, String::from_utf8_lossy(content))
+        .mime(mime)
+        .character_set("utf-8")
+    } else {
+      Self::binary_success(content, mime)
+    }
   }
 
   #[must_use]
@@ -109,6 +397,13 @@ impl Response {
       content: content.into(),
       character_set: None,
       languages: None,
+      raw_body: false,
+      binary: None,
+      body: None,
+      failure: None,
+      error: None,
+      #[cfg(feature = "templates")]
+      template: None,
     }
   }
 
@@ -142,6 +437,77 @@ impl Response {
 
     self
   }
+
+  /// Mark the body to be sent without the `Router`'s headers and footers.
+  pub fn with_raw_body(&mut self, raw_body: bool) -> &mut Self {
+    self.raw_body = raw_body;
+
+    self
+  }
+
+  /// Send the body without the `Router`'s headers and footers, consuming and
+  /// returning the `Response`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::response::Response::success("=> / Home").raw(); 
+  /// ```
+  #[must_use]
+  pub const fn raw(mut self) -> Self {
+    self.raw_body = true;
+
+    self
+  }
+
+  /// Set the MIME type, consuming and returning the `Response`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let response =
+  ///   windmark::response::Response::success("Hello!").mime("text/plain");
+  ///
+  /// assert_eq!(response.mime.as_deref(), Some("text/plain"));
+  /// ```
+  #[must_use]
+  pub fn mime(mut self, mime: impl Into<String> + AsRef<str>) -> Self {
+    self.with_mime(mime);
+
+    self
+  }
+
+  /// Set the character set, consuming and returning the `Response`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::response::Response::success("Hello!").character_set("utf-8");
+  /// ```
+  #[must_use]
+  pub fn character_set(
+    mut self,
+    character_set: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    self.with_character_set(character_set);
+
+    self
+  }
+
+  /// Set the languages, consuming and returning the `Response`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::response::Response::success("Hallo!").languages(["de"]); 
+  /// ```
+  #[must_use]
+  pub fn languages<S>(mut self, languages: impl AsRef<[S]>) -> Self
+  where S: Into<String> + AsRef<str> {
+    self.with_languages(languages);
+
+    self
+  }
 }
 
 impl std::future::IntoFuture for Response {
@@ -150,3 +516,31 @@ impl std::future::IntoFuture for Response {
 
   fn into_future(self) -> Self::IntoFuture { std::future::ready(self) }
 }
+
+/// Make `meta` safe to place in a response header by replacing line breaks,
+/// which would end the header early, with spaces and truncating it to
+/// [`MAX_META_LENGTH`] bytes.
+pub(crate) fn sanitise_meta(meta: &str) -> std::borrow::Cow<'_, str> {
+  if meta.len() <= MAX_META_LENGTH && !meta.contains(['\r', '\n']) {
+    return std::borrow::Cow::Borrowed(meta);
+  }
+
+  let mut sanitised = meta.replace(['\r', '\n'], " ");
+
+  if sanitised.len() > MAX_META_LENGTH {
+    warn!(
+      "response meta of {} bytes truncated to {MAX_META_LENGTH} bytes",
+      sanitised.len()
+    );
+
+    let mut end = MAX_META_LENGTH;
+
+    while !sanitised.is_char_boundary(end) {
+      end -= 1;
+    }
+
+    sanitised.truncate(end);
+  }
+
+  std::borrow::Cow::Owned(sanitised)
+}
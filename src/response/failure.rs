@@ -0,0 +1,216 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Early failures a route handler can return in place of a `Response`.
+
+use super::Response;
+
+/// A failure status of the Gemini protocol, for failing early with
+/// `Err(Status::NotFound)` without writing out a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Status {
+  TemporaryFailure,
+  ServerUnavailable,
+  CgiError,
+  ProxyError,
+  SlowDown,
+  PermanentFailure,
+  NotFound,
+  Gone,
+  ProxyRefused,
+  BadRequest,
+  ClientCertificateRequired,
+  CertificateNotAuthorised,
+  CertificateNotValid,
+}
+
+impl Status {
+  /// The status code sent to the client.
+  #[must_use]
+  pub const fn code(self) -> i32 {
+    match self {
+      Self::TemporaryFailure => 40,
+      Self::ServerUnavailable => 41,
+      Self::CgiError => 42,
+      Self::ProxyError => 43,
+      Self::SlowDown => 44,
+      Self::PermanentFailure => 50,
+      Self::NotFound => 51,
+      Self::Gone => 52,
+      Self::ProxyRefused => 53,
+      Self::BadRequest => 59,
+      Self::ClientCertificateRequired => 60,
+      Self::CertificateNotAuthorised => 61,
+      Self::CertificateNotValid => 62,
+    }
+  }
+
+  /// The meta sent to the client when a failure carries no message of its
+  /// own.
+  #[must_use]
+  pub const fn message(self) -> &'static str {
+    match self {
+      Self::TemporaryFailure => "Temporary failure",
+      Self::ServerUnavailable => "Server unavailable",
+      Self::CgiError => "CGI error",
+      Self::ProxyError => "Proxy error",
+      Self::SlowDown => "Slow down",
+      Self::PermanentFailure => "Permanent failure",
+      Self::NotFound => "Not found",
+      Self::Gone => "Gone",
+      Self::ProxyRefused => "Proxy request refused",
+      Self::BadRequest => "Bad request",
+      Self::ClientCertificateRequired => "Client certificate required",
+      Self::CertificateNotAuthorised => "Certificate not authorised",
+      Self::CertificateNotValid => "Certificate not valid",
+    }
+  }
+}
+
+/// A failure returned early by a route handler.
+///
+/// The `Router` replies with the failure's status and message, unless a
+/// failure handler has been set for its status with
+/// [`Router::set_failure_handler`](crate::router::Router::set_failure_handler).
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::response::{Failure, Response, Status};
+///
+/// windmark::router::Router::new()
+///   .mount("/secret", |_| {
+///     async { Err::<Response, _>(Status::NotFound) }
+///   })
+///   .mount("/gone", |_| {
+///     async { Err::<Response, _>(Failure::new(52, "This page has moved on.")) }
+///   });
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+  status:  i32,
+  message: String,
+}
+
+impl Failure {
+  #[must_use]
+  pub fn new(status: i32, message: impl Into<String>) -> Self {
+    Self {
+      status,
+      message: message.into(),
+    }
+  }
+
+  #[must_use]
+  pub const fn status(&self) -> i32 { self.status }
+
+  #[must_use]
+  pub fn message(&self) -> &str { &self.message }
+}
+
+impl From<Status> for Failure {
+  fn from(status: Status) -> Self { Self::new(status.code(), status.message()) }
+}
+
+impl std::fmt::Display for Failure {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} {}", self.status, self.message)
+  }
+}
+
+impl std::error::Error for Failure {}
+
+impl From<Failure> for Response {
+  fn from(failure: Failure) -> Self {
+    let mut response = Self::new(failure.status, failure.message.clone());
+
+    response.failure = Some(failure);
+
+    response
+  }
+}
+
+/// A value a route handler can reply with: a `Response`, or a `Result` whose
+/// error is an early [`Failure`] or a boxed error, which is handed to the
+/// error handler as described by [`Response::error`].
+pub trait IntoResponse {
+  fn into_response(self) -> Response;
+}
+
+impl IntoResponse for Response {
+  fn into_response(self) -> Response { self }
+}
+
+impl<E: Into<Failure>> IntoResponse for Result<Response, E> {
+  fn into_response(self) -> Response {
+    self.unwrap_or_else(|failure| failure.into().into())
+  }
+}
+
+impl IntoResponse
+  for Result<Response, Box<dyn std::error::Error + Send + Sync>>
+{
+  fn into_response(self) -> Response {
+    self.unwrap_or_else(|error| {
+      let mut response =
+        Response::temporary_failure(Status::TemporaryFailure.message());
+
+      response.error = Some(error.into());
+
+      response
+    })
+  }
+}
+
+/// Return early from a route handler with a [`Failure`], given either a
+/// [`Status`] or a status code and message.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::response::{Response, Status};
+///
+/// windmark::router::Router::new()
+///   .mount("/admin", |context| {
+///     async move {
+///       if context.certificate.is_none() {
+///         windmark::bail!(60, "Sign in with a certificate first.");
+///       }
+///
+///       Ok(Response::success("Welcome back!"))
+///     }
+///   })
+///   .mount("/drafts", |_| {
+///     async {
+///       windmark::bail!(Status::NotFound);
+///
+///       #[allow(unreachable_code)]
+///       Ok(Response::success("Nothing to see here."))
+///     }
+///   });
+/// ```
+#[macro_export]
+macro_rules! bail {
+  ($status:expr, $message:expr $(,)?) => {
+    return ::core::result::Result::Err($crate::response::Failure::new(
+      $status, $message,
+    ))
+  };
+  ($status:expr $(,)?) => {
+    return ::core::result::Result::Err($crate::response::Failure::from($status))
+  };
+}
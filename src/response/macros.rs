@@ -15,6 +15,21 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+//! Macros creating route handlers which reply with a `Response`.
+//!
+//! A synchronous and an asynchronous macro exists for every constructor of a
+//! `Response` which only takes its content, such as
+//! `Response::certificate_not_authorised`:
+//!
+//! ```rust
+//! windmark::router::Router::new()
+//!   .mount("/", windmark::certificate_not_authorised!("No entry."))
+//!   .mount(
+//!     "/async",
+//!     windmark::certificate_not_authorised_async!("No entry."),
+//!   );
+//! ```
+
 macro_rules! sync_response {
   ($($name:ident),*) => {
     $(
@@ -58,25 +73,15 @@ macro_rules! response {
   };
 }
 
-response!(
-  input,
-  sensitive_input,
-  success,
-  temporary_redirect,
-  permanent_redirect,
-  temporary_failure,
-  server_unavailable,
-  cgi_error,
-  proxy_error,
-  slow_down,
-  permanent_failure,
-  not_found,
-  gone,
-  proxy_refused,
-  bad_request,
-  client_certificate_required,
-  certificate_not_valid,
-);
+macro_rules! content_response_macros {
+  ($($name:ident = $status:expr),* $(,)?) => {
+    response!($($name),*);
+  };
+}
+
+content_responses!(content_response_macros);
+
+response!(success);
 
 #[cfg(feature = "auto-deduce-mime")]
 response!(binary_success_auto);
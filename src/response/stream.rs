@@ -0,0 +1,206 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  collections::VecDeque,
+  fmt,
+  future::{poll_fn, Future},
+  pin::Pin,
+  sync::{Arc, Mutex},
+  task::{Poll, Waker},
+};
+
+/// How many chunks may be waiting to be written before [`BodySender::send`]
+/// waits for the client to catch up.
+const CAPACITY: usize = 16;
+
+/// A running producer of a streamed body.
+pub type Production = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+type Producer = Box<dyn FnOnce(BodySender) -> Production + Send>;
+
+/// The producer of a streamed body, shared by every clone of its `Response`
+/// and taken by the first to be sent.
+#[derive(Clone)]
+pub struct Body {
+  producer: Arc<Mutex<Option<Producer>>>,
+}
+
+impl Body {
+  pub fn new<F, Fut>(producer: F) -> Self
+  where
+    F: FnOnce(BodySender) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), Disconnected>> + Send + 'static,
+  {
+    Self {
+      producer: Arc::new(Mutex::new(Some(Box::new(move |sender| {
+        Box::pin(async move {
+          let _ = producer(sender).await;
+        })
+      })))),
+    }
+  }
+
+  /// Take the producer, along with the receiving end of the chunks it sends.
+  pub fn take(&self) -> Option<(Production, BodyReceiver)> {
+    let producer = self.producer.lock().ok()?.take()?;
+    let shared = Arc::new(Mutex::new(Shared::default()));
+
+    Some((
+      producer(BodySender {
+        shared: shared.clone(),
+      }),
+      BodyReceiver {
+        shared,
+      },
+    ))
+  }
+}
+
+#[derive(Default)]
+struct Shared {
+  chunks:         VecDeque<String>,
+  /// Whether the receiver is gone, i.e., the client disconnected.
+  closed:         bool,
+  /// Whether the sender is gone, i.e., the body is complete.
+  complete:       bool,
+  receiver_waker: Option<Waker>,
+  sender_waker:   Option<Waker>,
+}
+
+/// The client disconnected from a streamed response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Disconnected;
+
+impl fmt::Display for Disconnected {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("the client disconnected")
+  }
+}
+
+impl std::error::Error for Disconnected {}
+
+/// Sends the chunks of a streamed response, as created by
+/// [`Response::stream`](super::Response::stream).
+pub struct BodySender {
+  shared: Arc<Mutex<Shared>>,
+}
+
+impl BodySender {
+  /// Send a chunk to the client, waiting while too many earlier chunks have
+  /// yet to be written.
+  ///
+  /// # Errors
+  ///
+  /// if the client disconnected.
+  pub async fn send(
+    &self,
+    chunk: impl Into<String>,
+  ) -> Result<(), Disconnected> {
+    let mut chunk = Some(chunk.into());
+
+    poll_fn(|context| {
+      let Ok(mut shared) = self.shared.lock() else {
+        return Poll::Ready(Err(Disconnected));
+      };
+
+      if shared.closed {
+        return Poll::Ready(Err(Disconnected));
+      }
+
+      if shared.chunks.len() >= CAPACITY {
+        shared.sender_waker = Some(context.waker().clone());
+
+        return Poll::Pending;
+      }
+
+      if let Some(chunk) = chunk.take() {
+        shared.chunks.push_back(chunk);
+      }
+
+      if let Some(waker) = shared.receiver_waker.take() {
+        waker.wake();
+      }
+
+      Poll::Ready(Ok(()))
+    })
+    .await
+  }
+
+  /// Whether the client has disconnected.
+  #[must_use]
+  pub fn is_closed(&self) -> bool {
+    self.shared.lock().map_or(true, |shared| shared.closed)
+  }
+}
+
+impl Drop for BodySender {
+  fn drop(&mut self) {
+    if let Ok(mut shared) = self.shared.lock() {
+      shared.complete = true;
+
+      if let Some(waker) = shared.receiver_waker.take() {
+        waker.wake();
+      }
+    }
+  }
+}
+
+/// Receives the chunks of a streamed response as the `Router` writes them.
+pub struct BodyReceiver {
+  shared: Arc<Mutex<Shared>>,
+}
+
+impl BodyReceiver {
+  /// The next chunk, or `None` once the body is complete.
+  pub async fn recv(&self) -> Option<String> {
+    poll_fn(|context| {
+      let Ok(mut shared) = self.shared.lock() else {
+        return Poll::Ready(None);
+      };
+
+      if let Some(chunk) = shared.chunks.pop_front() {
+        if let Some(waker) = shared.sender_waker.take() {
+          waker.wake();
+        }
+
+        return Poll::Ready(Some(chunk));
+      }
+
+      if shared.complete {
+        return Poll::Ready(None);
+      }
+
+      shared.receiver_waker = Some(context.waker().clone());
+
+      Poll::Pending
+    })
+    .await
+  }
+}
+
+impl Drop for BodyReceiver {
+  fn drop(&mut self) {
+    if let Ok(mut shared) = self.shared.lock() {
+      shared.closed = true;
+
+      if let Some(waker) = shared.sender_waker.take() {
+        waker.wake();
+      }
+    }
+  }
+}
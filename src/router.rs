@@ -15,21 +15,57 @@
 // Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
-#![allow(clippy::significant_drop_tightening)]
+#![allow(clippy::significant_drop_tightening, clippy::struct_excessive_bools)]
+
+mod accept;
+mod authorization;
+pub(crate) mod buffers;
+mod builder;
+#[cfg(feature = "config")]
+mod config;
+mod connections;
+mod files;
+pub(crate) mod guard;
+mod handle;
+mod handshake;
+mod index;
+mod modules;
+mod profile;
+mod redirect;
+mod robots;
+mod routes;
+pub(crate) mod runtime;
+#[cfg(feature = "tower")]
+mod service;
+mod shutdown;
+mod socket;
+mod stats;
+mod tasks;
+mod telemetry;
+mod tls;
+mod unwind;
+mod warm;
 
 use std::{
+  collections::HashMap,
   error::Error,
-  future::IntoFuture,
-  sync::{Arc, Mutex},
+  future::{Future, IntoFuture},
+  net::SocketAddr,
+  sync::{Arc, Mutex, RwLock},
   time,
 };
 
 #[cfg(feature = "async-std")]
 use async_std::{
-  io::{ReadExt, WriteExt},
+  io::{ReadExt, Write as _, WriteExt},
   sync::Mutex as AsyncMutex,
 };
-use openssl::ssl::{self, SslAcceptor, SslMethod};
+use openssl::ssl::{self, SslAcceptor, SslMethod, SslVersion};
+#[cfg(feature = "smol")]
+use smol::{
+  io::{AsyncReadExt, AsyncWrite as _, AsyncWriteExt},
+  lock::Mutex as AsyncMutex,
+};
 #[cfg(feature = "tokio")]
 use tokio::{
   io::{AsyncReadExt, AsyncWriteExt},
@@ -37,17 +73,55 @@ use tokio::{
 };
 use url::Url;
 
+#[cfg(feature = "config")]
+pub use self::config::{Config, ConfigError, Limits};
+use self::unwind::Registered;
+pub use self::{
+  accept::{AcceptError, AcceptPolicy},
+  authorization::CertificatePolicy,
+  builder::RouterBuilder,
+  handle::ServerHandle,
+  handshake::{HandshakeError, HandshakeFailure},
+  profile::Profile,
+  redirect::{FixPathPolicy, RedirectKind},
+  robots::{CrawlPolicy, Crawler},
+  routes::{RouteInfo, RouteMetadata},
+  socket::SocketOptions,
+  stats::{RouteStats, Stats},
+  tls::{CertificateExpiry, CloseMode, TlsPolicy},
+};
 use crate::{
-  context::{ErrorContext, HookContext, RouteContext},
+  context::{
+    ErrorContext,
+    ErrorKind,
+    HookContext,
+    NotFoundContext,
+    Rejection,
+    RouteContext,
+    TlsInfo,
+  },
   handler::{
+    AcceptErrorHook,
+    CertificateExpiryHook,
+    ConnectionHook,
     ErrorResponse,
+    GemtextHook,
+    HandshakeErrorHook,
+    ListenHook,
+    Middleware,
+    Next,
+    NotFoundResponse,
     Partial,
+    PartialInfo,
+    PartialScope,
     PostRouteHook,
     PreRouteHook,
-    RouteResponse,
+    RejectionHook,
   },
+  mime::MimeTypes,
   module::{AsyncModule, Module},
-  response::Response,
+  response::{self, IntoResponse, Response},
+  secret::SecretKeys,
 };
 
 macro_rules! block {
@@ -58,54 +132,150 @@ macro_rules! block {
     });
     #[cfg(feature = "async-std")]
     ::async_std::task::block_on(async { $body });
+    #[cfg(feature = "smol")]
+    ::smol::block_on(async { $body });
   };
 }
 
-macro_rules! or_error {
-  ($stream:ident, $operation:expr, $error_format:literal) => {
-    match $operation {
-      Ok(u) => u,
-      Err(e) => {
-        $stream
-          .write_all(format!($error_format, e).as_bytes())
-          .await?;
+pub(crate) use self::routes::RouteTable;
+use self::routes::{Handler, Route};
 
-        // $stream.shutdown().await?;
+#[cfg(feature = "tokio")]
+type Stream = tokio_openssl::SslStream<runtime::TcpStream>;
+#[cfg(any(feature = "async-std", feature = "smol"))]
+type Stream = async_std_openssl::SslStream<runtime::TcpStream>;
 
-        return Ok(());
-      }
-    }
-  };
+/// A request read from a client, before it is routed.
+struct Incoming {
+  peer_address: Option<SocketAddr>,
+  listener:     SocketAddr,
+  url:          Url,
+  /// Why the request is malformed, if it is.
+  bad_request:  Option<Rejection>,
+  request_id:   String,
+  certificate:  Option<openssl::x509::X509>,
+  tls:          TlsInfo,
+  /// The exact bytes of the request line.
+  raw_request:  crate::context::RawRequest,
+  received_at:  time::SystemTime,
+  started_at:   time::Instant,
+  /// Set once the client disconnects.
+  closed:       crate::context::Closed,
 }
 
-#[cfg(feature = "tokio")]
-type Stream = tokio_openssl::SslStream<tokio::net::TcpStream>;
-#[cfg(feature = "async-std")]
-type Stream = async_std_openssl::SslStream<async_std::net::TcpStream>;
+/// What an accepted connection holds for as long as it is open: its slots in
+/// the `Router`'s connection limits, and its place among the connections a
+/// shutdown waits for.
+pub(crate) type Permits = (
+  Option<runtime::Permit>,
+  Option<connections::PeerPermit>,
+  shutdown::Connection,
+);
+
+/// The limits on connections which can change while the `Router` is running.
+#[derive(Clone, Copy)]
+struct ConnectionLimits {
+  handshake_timeout:    Option<time::Duration>,
+  read_timeout:         Option<time::Duration>,
+  write_timeout:        Option<time::Duration>,
+  handler_timeout:      Option<time::Duration>,
+  min_write_rate:       Option<std::num::NonZeroU64>,
+  max_peer_connections: Option<usize>,
+}
+
+/// Middleware added for a path prefix.
+type PrefixMiddleware = (String, Arc<dyn Middleware>);
+type ScopedPartial = (PartialScope, Registered<dyn Partial>);
 
 /// A router which takes care of all tasks a Windmark server should handle:
 /// response generation, panics, logging, and more.
 #[derive(Clone)]
 pub struct Router {
-  routes: matchit::Router<Arc<AsyncMutex<Box<dyn RouteResponse>>>>,
-  error_handler:         Arc<AsyncMutex<Box<dyn ErrorResponse>>>,
-  private_key_file_name: String,
-  private_key_content:   Option<String>,
-  certificate_file_name: String,
-  certificate_content:   Option<String>,
-  headers:               Arc<Mutex<Vec<Box<dyn Partial>>>>,
-  footers:               Arc<Mutex<Vec<Box<dyn Partial>>>>,
-  ssl_acceptor:          Arc<SslAcceptor>,
+  routes: Arc<RwLock<Arc<RouteTable>>>,
+  error_handler: Arc<AsyncMutex<Box<dyn ErrorResponse>>>,
+  failure_handlers: HashMap<i32, Arc<AsyncMutex<Box<dyn ErrorResponse>>>>,
+  not_found_handler: Option<Arc<AsyncMutex<Box<dyn NotFoundResponse>>>>,
+  private_key: tls::Material,
+  certificate: tls::Material,
+  tls_policy: TlsPolicy,
+  close_mode: CloseMode,
+  min_tls_version: Option<SslVersion>,
+  max_tls_version: Option<SslVersion>,
+  cipher_list: Option<String>,
+  ciphersuites: Option<String>,
+  keylog_file: Option<std::path::PathBuf>,
+  session_id_context: Option<Vec<u8>>,
+  session_tickets: bool,
+  headers: Arc<Mutex<Vec<ScopedPartial>>>,
+  footers: Arc<Mutex<Vec<ScopedPartial>>>,
+  gemtext_processors: Arc<Mutex<Vec<Registered<dyn GemtextHook>>>>,
+  ssl_acceptor: Arc<RwLock<Arc<SslAcceptor>>>,
+  custom_ssl_acceptor: bool,
+  finalized: bool,
+  unmounted: Arc<Mutex<Vec<String>>>,
+  certificate_expiry_warning: Option<u32>,
+  certificate_expiry_interval: Option<time::Duration>,
+  certificate_expiry_callback:
+    Option<Arc<Mutex<Box<dyn CertificateExpiryHook>>>>,
+  listen_callback: Option<Arc<Mutex<Box<dyn ListenHook>>>>,
+  accept_policy: AcceptPolicy,
+  accept_error_callback: Option<Arc<Mutex<Box<dyn AcceptErrorHook>>>>,
+  connection_callback: Option<Arc<Mutex<Box<dyn ConnectionHook>>>>,
+  handshake_error_callback: Option<Arc<Mutex<Box<dyn HandshakeErrorHook>>>>,
+  rejection_callback: Option<Arc<Mutex<Box<dyn RejectionHook>>>>,
+  #[cfg(feature = "logger")]
+  default_logger: bool,
+  #[cfg(feature = "logger")]
+  log_filter: String,
   #[cfg(feature = "logger")]
-  default_logger:        bool,
-  pre_route_callback:    Arc<Mutex<Box<dyn PreRouteHook>>>,
-  post_route_callback:   Arc<Mutex<Box<dyn PostRouteHook>>>,
-  character_set:         String,
-  languages:             Vec<String>,
-  port:                  i32,
-  async_modules:         Arc<AsyncMutex<Vec<Box<dyn AsyncModule + Send>>>>,
-  modules:               Arc<Mutex<Vec<Box<dyn Module + Send>>>>,
-  fix_path:              bool,
+  log_requests: bool,
+  pre_route_callback: Arc<Mutex<Registered<dyn PreRouteHook>>>,
+  post_route_callback: Arc<Mutex<Registered<dyn PostRouteHook>>>,
+  character_set: String,
+  languages: Vec<String>,
+  port: i32,
+  async_modules: Arc<RwLock<Arc<modules::AsyncModules>>>,
+  modules: Arc<RwLock<Arc<modules::Modules>>>,
+  fix_path: bool,
+  fix_path_policy: FixPathPolicy,
+  strict_urls: bool,
+  minimal_meta: bool,
+  /// Shared by every clone, so that a reloaded configuration changes them
+  /// for the running `Router`.
+  limits: Arc<RwLock<ConnectionLimits>>,
+  max_connections: Option<usize>,
+  /// The slots of `max_connections`, created once the `Router` starts
+  /// serving.
+  connection_slots: Option<Arc<runtime::Semaphore>>,
+  peer_connections: connections::PeerConnections,
+  /// The `Router` as it is while serving, which admits the connections of
+  /// adapters holding a clone taken before it was fully configured.
+  serving: Arc<RwLock<Option<Self>>>,
+  /// The addresses to listen on, along with their names.
+  listeners: Vec<(String, Option<String>)>,
+  hostnames: Vec<String>,
+  socket_options: SocketOptions,
+  secret_keys: SecretKeys,
+  shutdown: shutdown::Shutdown,
+  listening: Arc<Mutex<Vec<SocketAddr>>>,
+  /// The names of the bound listeners which were added with one.
+  listener_names: Arc<RwLock<HashMap<SocketAddr, String>>>,
+  tasks: Arc<Mutex<tasks::Tasks>>,
+  warmed: warm::Warmed,
+  middleware: Arc<Mutex<Vec<PrefixMiddleware>>>,
+  restrictions: Arc<RwLock<Vec<(String, CertificatePolicy)>>>,
+  guards: Arc<RwLock<Vec<Arc<dyn guard::Guard>>>>,
+  #[cfg(feature = "templates")]
+  templates: Option<crate::template::Templates>,
+  mime_types: MimeTypes,
+  buffers: Arc<buffers::BufferPool>,
+  stats: Stats,
+  #[cfg(feature = "config")]
+  config_path: Option<std::path::PathBuf>,
+  #[cfg(feature = "config")]
+  applied_config: Arc<Mutex<Option<Config>>>,
+  #[cfg(all(unix, feature = "config"))]
+  reload_config_on_sighup: bool,
 }
 
 impl Router {
@@ -123,8 +293,18 @@ impl Router {
   #[must_use]
   pub fn new() -> Self { Self::default() }
 
+  /// Create a consuming [`RouterBuilder`].
+  ///
+  /// # Panics
+  ///
+  /// if a default `SslAcceptor` could not be built.
+  pub fn builder() -> RouterBuilder { RouterBuilder::new() }
+
   /// Set the filename of the private key file.
   ///
+  /// RSA and EC keys are accepted, in either their traditional or PKCS#8
+  /// encoding.
+  ///
   /// # Examples
   ///
   /// ```rust
@@ -134,7 +314,7 @@ impl Router {
     &mut self,
     private_key_file_name: impl Into<String> + AsRef<str>,
   ) -> &mut Self {
-    self.private_key_file_name = private_key_file_name.into();
+    self.private_key = tls::Material::File(private_key_file_name.into());
 
     self
   }
@@ -150,12 +330,16 @@ impl Router {
     &mut self,
     private_key_content: impl Into<String> + AsRef<str>,
   ) -> &mut Self {
-    self.private_key_content = Some(private_key_content.into());
+    self.private_key =
+      tls::Material::Pem(private_key_content.into().into_bytes());
 
     self
   }
 
-  /// Set the filename of the certificate chain file.
+  /// Set the filename of the certificate file.
+  ///
+  /// Only the first certificate in the file is used; see
+  /// [`Router::set_certificate_chain_file`] to serve intermediate certificates.
   ///
   /// # Examples
   ///
@@ -166,7 +350,47 @@ impl Router {
     &mut self,
     certificate_name: impl Into<String> + AsRef<str>,
   ) -> &mut Self {
-    self.certificate_file_name = certificate_name.into();
+    self.certificate = tls::Material::File(certificate_name.into());
+
+    self
+  }
+
+  /// Set the filename of a certificate chain file: the certificate followed
+  /// by any intermediate certificates, such as Let's Encrypt's
+  /// `fullchain.pem`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_certificate_chain_file("fullchain.pem")
+  ///   .set_private_key_file("privkey.pem");
+  /// ```
+  pub fn set_certificate_chain_file(
+    &mut self,
+    certificate_chain_name: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    self.certificate = tls::Material::ChainFile(certificate_chain_name.into());
+
+    self
+  }
+
+  /// Set the filename of a bundle file containing both the private key and
+  /// the certificate chain.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_bundle_file("windmark_bundle.pem");
+  /// ```
+  pub fn set_bundle_file(
+    &mut self,
+    bundle_name: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    let bundle_name = bundle_name.into();
+
+    self.private_key = tls::Material::File(bundle_name.clone());
+    self.certificate = tls::Material::ChainFile(bundle_name);
 
     self
   }
@@ -182,14 +406,83 @@ impl Router {
     &mut self,
     certificate_content: impl Into<String> + AsRef<str>,
   ) -> &mut Self {
-    self.certificate_content = Some(certificate_content.into());
+    self.certificate =
+      tls::Material::Pem(certificate_content.into().into_bytes());
+
+    self
+  }
+
+  /// Set the PEM-encoded content of the private key.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_private_key_pem(b"..."); 
+  /// ```
+  pub fn set_private_key_pem(
+    &mut self,
+    private_key: impl AsRef<[u8]>,
+  ) -> &mut Self {
+    self.private_key = tls::Material::Pem(private_key.as_ref().to_vec());
+
+    self
+  }
+
+  /// Set the DER-encoded content of the private key.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_private_key_der([0u8; 0]); 
+  /// ```
+  pub fn set_private_key_der(
+    &mut self,
+    private_key: impl AsRef<[u8]>,
+  ) -> &mut Self {
+    self.private_key = tls::Material::Der(private_key.as_ref().to_vec());
+
+    self
+  }
+
+  /// Set the PEM-encoded content of the certificate, optionally followed by
+  /// any intermediate certificates.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_certificate_pem(b"..."); 
+  /// ```
+  pub fn set_certificate_pem(
+    &mut self,
+    certificate: impl AsRef<[u8]>,
+  ) -> &mut Self {
+    self.certificate = tls::Material::Pem(certificate.as_ref().to_vec());
+
+    self
+  }
+
+  /// Set the DER-encoded content of the certificate.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_certificate_der([0u8; 0]); 
+  /// ```
+  pub fn set_certificate_der(
+    &mut self,
+    certificate: impl AsRef<[u8]>,
+  ) -> &mut Self {
+    self.certificate = tls::Material::Der(certificate.as_ref().to_vec());
 
     self
   }
 
   /// Map routes to URL paths
   ///
-  /// Supports both synchronous and asynchronous handlers
+  /// Supports both synchronous and asynchronous handlers. A route which
+  /// conflicts with one already mounted is reported by [`Router::finalize`],
+  /// or, once the `Router` is running, logged; [`Router::try_mount`] reports
+  /// it straight away.
   ///
   /// # Examples
   ///
@@ -205,807 +498,5168 @@ impl Router {
   ///
   /// # Panics
   ///
-  /// May panic if the route cannot be mounted.
+  /// May panic if the route table's lock is poisoned.
   pub fn mount<R>(
     &mut self,
     route: impl Into<String> + AsRef<str>,
-    mut handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
   ) -> &mut Self
   where
-    R: IntoFuture<Output = Response> + Send + 'static,
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
     <R as IntoFuture>::IntoFuture: Send,
   {
-    self
-      .routes
-      .insert(
-        route.into(),
-        Arc::new(AsyncMutex::new(Box::new(move |context: RouteContext| {
-          handler(context).into_future()
-        }))),
-      )
-      .unwrap();
-
-    self
+    self.mount_route(RouteInfo::new(route.into()), vec![], handler)
   }
 
-  /// Create an error handler which will be displayed on any error.
+  /// Map a route to a URL path like [`Router::mount`], reporting a conflict
+  /// with a route already mounted straight away, e.g., for a route mounted
+  /// while the `Router` is running.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// windmark::router::Router::new().set_error_handler(|_| {
-  ///   windmark::response::Response::success("You have encountered an error!")
-  /// });
+  /// use windmark::response::Response;
+  ///
+  /// let mut router = windmark::router::Router::new();
+  ///
+  /// router.mount("/posts/:id", |_| Response::success("A post"));
+  ///
+  /// assert!(router
+  ///   .try_mount("/posts/:name", |_| Response::success("Another post"))
+  ///   .is_err());
   /// ```
-  pub fn set_error_handler<R>(
+  ///
+  /// # Errors
+  ///
+  /// if the route conflicts with one already mounted, in which case it is not
+  /// mounted.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn try_mount<R>(
     &mut self,
-    mut handler: impl FnMut(ErrorContext) -> R + Send + Sync + 'static,
-  ) -> &mut Self
+    route: impl Into<String> + AsRef<str>,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> Result<&mut Self, crate::Error>
   where
-    R: IntoFuture<Output = Response> + Send + 'static,
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
     <R as IntoFuture>::IntoFuture: Send,
   {
-    self.error_handler = Arc::new(AsyncMutex::new(Box::new(move |context| {
-      handler(context).into_future()
-    })));
+    Arc::make_mut(&mut self.routes.write().unwrap())
+      .insert(Route {
+        info:       RouteInfo::new(route.into()),
+        handler:    Handler::exclusive(handler),
+        middleware: vec![],
+      })
+      .map_err(|e| crate::Error::Routes(vec![e]))?;
 
-    self
+    Ok(self)
   }
 
-  /// Add a header for the `Router` which should be displayed on every route.
+  /// Map a route to a URL path, running its handler on a thread set aside
+  /// for blocking work, such as CPU-heavy rendering or blocking I/O, so that
+  /// it does not stall the executor.
+  ///
+  /// Unlike [`Router::mount`], requests are not handed to the handler one at
+  /// a time, and a call which outlives its request, e.g., because it timed
+  /// out, still holds up a graceful shutdown until it returns.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new().mount_blocking("/report", |_| {
+  ///   Response::success(std::fs::read_to_string("report.gmi").unwrap_or_default())
+  /// });
+  /// ```
   ///
   /// # Panics
   ///
-  /// May panic if the header cannot be added.
+  /// May panic if the route table's lock is poisoned.
+  pub fn mount_blocking<R>(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    handler: impl Fn(RouteContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoResponse + Send + 'static,
+  {
+    self.insert_route(Route {
+      info:       RouteInfo::new(route.into()),
+      handler:    Handler::Blocking(
+        Arc::new(move |context| handler(context).into_response()),
+        self.shutdown.clone(),
+      ),
+      middleware: vec![],
+    })
+  }
+
+  /// Map a route to a URL path, along with a description of it which route
+  /// introspection, such as the [`Sitemap`](crate::module::Sitemap) module,
+  /// can present.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// windmark::router::Router::new().add_header(
-  ///   |context: windmark::context::RouteContext| {
-  ///     format!("This is displayed at the top of {}!", context.url.path())
-  ///   },
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new().mount_with_description(
+  ///   "/about",
+  ///   "Who I am and what I do",
+  ///   |_| Response::success("About that..."),
   /// );
   /// ```
-  pub fn add_header(&mut self, handler: impl Partial + 'static) -> &mut Self {
-    (*self.headers.lock().unwrap()).push(Box::new(handler));
-
-    self
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn mount_with_description<R>(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    description: impl Into<String> + AsRef<str>,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.mount_route(
+      RouteInfo {
+        description: Some(description.into()),
+        ..RouteInfo::new(route.into())
+      },
+      vec![],
+      handler,
+    )
   }
 
-  /// Add a footer for the `Router` which should be displayed on every route.
+  /// Map a route to a URL path, along with a title, description, and
+  /// whether it is hidden, which route introspection, such as the
+  /// [`Sitemap`](crate::module::Sitemap) module, can present.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{response::Response, router::RouteMetadata};
+  ///
+  /// windmark::router::Router::new()
+  ///   .mount_with_metadata(
+  ///     "/guestbook",
+  ///     RouteMetadata::new()
+  ///       .title("Guestbook")
+  ///       .description("Leave a message for future visitors"),
+  ///     |_| Response::success("Sign the guestbook!"),
+  ///   )
+  ///   .mount_with_metadata("/admin", RouteMetadata::new().hidden(), |_| {
+  ///     Response::success("Welcome back!")
+  ///   });
+  /// ```
   ///
   /// # Panics
   ///
-  /// May panic if the header cannot be added.
+  /// May panic if the route table's lock is poisoned.
+  pub fn mount_with_metadata<R>(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    metadata: RouteMetadata,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.mount_route(
+      RouteInfo {
+        title: metadata.title,
+        description: metadata.description,
+        hidden: metadata.hidden,
+        ..RouteInfo::new(route.into())
+      },
+      vec![],
+      handler,
+    )
+  }
+
+  /// Map a route to a URL path, running `middleware` around its handler.
+  ///
+  /// Middleware added for a prefix of the route with
+  /// [`Router::add_middleware`] runs before, and around, `middleware`.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// windmark::router::Router::new().add_footer(
-  ///   |context: windmark::context::RouteContext| {
-  ///     format!("This is displayed at the bottom of {}!", context.url.path())
+  /// use windmark::{context::RouteContext, handler::Next, response::Response};
+  ///
+  /// windmark::router::Router::new().mount_with_middleware(
+  ///   "/admin/*path",
+  ///   |context: RouteContext, next: Next| {
+  ///     async move {
+  ///       if context.certificate.is_some() {
+  ///         next.run(context).await
+  ///       } else {
+  ///         Response::client_certificate_required("Who are you?")
+  ///       }
+  ///     }
   ///   },
+  ///   |_| Response::success("Welcome back!"),
   /// );
   /// ```
-  pub fn add_footer(&mut self, handler: impl Partial + 'static) -> &mut Self {
-    (*self.footers.lock().unwrap()).push(Box::new(handler));
-
-    self
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn mount_with_middleware<R>(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    middleware: impl Middleware + 'static,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.mount_route(
+      RouteInfo::new(route.into()),
+      vec![Arc::new(middleware)],
+      handler,
+    )
   }
 
-  /// Run the `Router` and wait for requests
+  /// Map a route to a URL path, overriding the router's handler timeout for
+  /// it.
+  ///
+  /// If the handler does not respond within `timeout`, the error handler
+  /// responds in its place with [`ErrorKind::Timeout`].
   ///
   /// # Examples
   ///
   /// ```rust
-  /// windmark::router::Router::new().run(); 
+  /// use std::time::Duration;
+  ///
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new()
+  ///   .set_handler_timeout(Some(Duration::from_secs(5)))
+  ///   .mount_with_timeout("/report", Duration::from_secs(60), |_| {
+  ///     Response::success("Here is your report.")
+  ///   });
   /// ```
   ///
   /// # Panics
   ///
-  /// if the client could not be accepted.
+  /// May panic if the route table's lock is poisoned.
+  pub fn mount_with_timeout<R>(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    timeout: time::Duration,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.mount_route(
+      RouteInfo {
+        timeout: Some(timeout),
+        ..RouteInfo::new(route.into())
+      },
+      vec![],
+      handler,
+    )
+  }
+
+  /// Map a route to a URL path, along with which crawlers may request it, as
+  /// published by the [`Robots`](crate::module::Robots) module.
   ///
-  /// # Errors
+  /// # Examples
   ///
-  /// if the `TcpListener` could not be bound.
-  pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
-    self.create_acceptor()?;
-
-    #[cfg(feature = "logger")]
-    if self.default_logger {
-      pretty_env_logger::init();
-    }
+  /// ```rust
+  /// use windmark::{
+  ///   response::Response,
+  ///   router::{CrawlPolicy, Crawler},
+  /// };
+  ///
+  /// windmark::router::Router::new().mount_with_crawl_policy(
+  ///   "/guestbook",
+  ///   CrawlPolicy::Disallow(vec![Crawler::Archiver, Crawler::Indexer]),
+  ///   |_| Response::success("Sign the guestbook!"),
+  /// );
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn mount_with_crawl_policy<R>(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    crawl_policy: CrawlPolicy,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.mount_route(
+      RouteInfo {
+        crawl_policy,
+        ..RouteInfo::new(route.into())
+      },
+      vec![],
+      handler,
+    )
+  }
 
-    #[cfg(feature = "tokio")]
-    let listener =
-      tokio::net::TcpListener::bind(format!("0.0.0.0:{}", self.port)).await?;
-    #[cfg(feature = "async-std")]
-    let listener =
-      async_std::net::TcpListener::bind(format!("0.0.0.0:{}", self.port))
-        .await?;
+  fn mount_route<R>(
+    &mut self,
+    info: RouteInfo,
+    middleware: Vec<Arc<dyn Middleware>>,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.insert_route(Route {
+      info,
+      handler: Handler::exclusive(handler),
+      middleware,
+    })
+  }
 
-    #[cfg(feature = "logger")]
-    info!("windmark is listening for connections");
+  fn insert_route(&mut self, route: Route) -> &mut Self {
+    let inserted =
+      Arc::make_mut(&mut self.routes.write().unwrap()).insert(route);
 
-    loop {
-      match listener.accept().await {
-        Ok((stream, _)) => {
-          let mut self_clone = self.clone();
-          let acceptor = self_clone.ssl_acceptor.clone();
-          #[cfg(feature = "tokio")]
-          let spawner = tokio::spawn;
-          #[cfg(feature = "async-std")]
-          let spawner = async_std::task::spawn;
-
-          spawner(async move {
-            let ssl = match ssl::Ssl::new(acceptor.context()) {
-              Ok(ssl) => ssl,
-              Err(e) => {
-                error!("ssl context error: {:?}", e);
-
-                return;
-              }
-            };
-
-            #[cfg(feature = "tokio")]
-            let quick_stream = tokio_openssl::SslStream::new(ssl, stream);
-            #[cfg(feature = "async-std")]
-            let quick_stream = async_std_openssl::SslStream::new(ssl, stream);
-
-            match quick_stream {
-              Ok(mut stream) => {
-                if let Err(e) = std::pin::Pin::new(&mut stream).accept().await {
-                  println!("stream accept error: {e:?}");
-                }
-
-                if let Err(e) = self_clone.handle(&mut stream).await {
-                  error!("handle error: {}", e);
-                }
-              }
-              Err(e) => error!("ssl stream error: {:?}", e),
-            }
-          });
-        }
-        Err(e) => error!("tcp stream error: {:?}", e),
+    if let Err(e) = inserted {
+      // A running `Router` will not be finalized again, so the error can only
+      // be logged.
+      if self.listening_addresses().is_empty() {
+        self.unmounted.lock().unwrap().push(e);
+      } else {
+        error!("{e}");
       }
     }
 
-    // Ok(())
+    self
   }
 
-  #[allow(
-    clippy::too_many_lines,
-    clippy::needless_pass_by_ref_mut,
-    clippy::significant_drop_in_scrutinee
-  )]
-  async fn handle(
-    &mut self,
-    stream: &mut Stream,
-  ) -> Result<(), Box<dyn Error>> {
-    let mut buffer = [0u8; 1024];
-    let mut url = Url::parse("gemini://fuwn.me/")?;
-    let mut footer = String::new();
-    let mut header = String::new();
-
-    while let Ok(size) = stream.read(&mut buffer).await {
-      let request = or_error!(
-        stream,
-        String::from_utf8(buffer[0..size].to_vec()),
-        "59 The server (Windmark) received a bad request: {}"
-      );
-
-      url = or_error!(
-        stream,
-        Url::parse(&request.replace("\r\n", "")),
-        "59 The server (Windmark) received a bad request: {}"
-      );
+  /// Map routes to a [`tower_service::Service`], allowing handlers to be
+  /// composed with the tower middleware ecosystem.
+  ///
+  /// The service is cloned for each request. If it fails to become ready or
+  /// to produce a `Response`, the error is logged and the client receives a
+  /// temporary failure.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{context::RouteContext, response::Response};
+  ///
+  /// #[derive(Clone)]
+  /// struct Hello;
+  ///
+  /// impl tower_service::Service<RouteContext> for Hello {
+  ///   type Error = std::convert::Infallible;
+  ///   type Future = std::future::Ready<Result<Response, Self::Error>>;
+  ///   type Response = Response;
+  ///
+  ///   fn poll_ready(
+  ///     &mut self,
+  ///     _: &mut std::task::Context<'_>,
+  ///   ) -> std::task::Poll<Result<(), Self::Error>> {
+  ///     std::task::Poll::Ready(Ok(()))
+  ///   }
+  ///
+  ///   fn call(&mut self, _: RouteContext) -> Self::Future {
+  ///     std::future::ready(Ok(Response::success("Hello!")))
+  ///   }
+  /// }
+  ///
+  /// windmark::router::Router::new().mount_service("/", Hello);
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  #[cfg(feature = "tower")]
+  pub fn mount_service<S>(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    service: S,
+  ) -> &mut Self
+  where
+    S: tower_service::Service<RouteContext, Response = Response>
+      + Clone
+      + Send
+      + Sync
+      + 'static,
+    S::Future: Send,
+    S::Error: std::fmt::Display + Send,
+  {
+    self.mount(route, move |context| {
+      service::call(service.clone(), context)
+    })
+  }
+
+  /// Run `middleware` around the handlers of every route requested beneath
+  /// `prefix`, such as `"/admin"`.
+  ///
+  /// Middleware runs in the order it was added, before any middleware the
+  /// route was mounted with.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{context::RouteContext, handler::Next};
+  ///
+  /// windmark::router::Router::new().add_middleware(
+  ///   "/admin",
+  ///   |context: RouteContext, next: Next| {
+  ///     async move {
+  ///       let mut response = next.run(context).await;
+  ///
+  ///       response.content.push_str("\n=> / Leave");
+  ///
+  ///       response
+  ///     }
+  ///   },
+  /// );
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the middleware cannot be added.
+  pub fn add_middleware(
+    &mut self,
+    prefix: impl Into<String> + AsRef<str>,
+    middleware: impl Middleware + 'static,
+  ) -> &mut Self {
+    (*self.middleware.lock().unwrap())
+      .push((prefix.into(), Arc::new(middleware)));
+
+    self
+  }
+
+  /// The middleware added for prefixes of `path`.
+  fn middleware_for(&self, path: &str) -> Vec<Arc<dyn Middleware>> {
+    self.middleware.lock().map_or_else(
+      |_| Vec::new(),
+      |middleware| {
+        middleware
+          .iter()
+          .filter(|(prefix, _)| is_beneath(path, prefix))
+          .map(|(_, middleware)| middleware.clone())
+          .collect()
+      },
+    )
+  }
+
+  /// Only allow clients presenting a certificate authorised by `policy` to
+  /// request paths beneath `prefix`, such as `"/admin"`, replacing any policy
+  /// set for the same prefix before.
+  ///
+  /// Clients without a certificate are asked for one with a `60` response,
+  /// and clients whose certificate is not authorised are answered with `61`,
+  /// before any middleware or route handler runs. A path beneath several
+  /// restricted prefixes must be authorised by each of their policies.
+  ///
+  /// Every clone of a `Router` shares its restrictions, so they may be
+  /// changed while the server is running.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::router::{CertificatePolicy, Router};
+  ///
+  /// Router::new().restrict(
+  ///   "/admin",
+  ///   CertificatePolicy::new().fingerprint(
+  ///     "b7205a1f9636e9b2a3680163026268654238ee0a503c488257a260a2321f8c49",
+  ///   ),
+  /// );
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the restrictions' lock is poisoned.
+  pub fn restrict(
+    &mut self,
+    prefix: impl Into<String> + AsRef<str>,
+    policy: CertificatePolicy,
+  ) -> &mut Self {
+    let mut restrictions = self.restrictions.write().unwrap();
+
+    restrictions.retain(|(restricted, _)| restricted != prefix.as_ref());
+    restrictions.push((prefix.into(), policy));
+    drop(restrictions);
+
+    self
+  }
+
+  /// Lift the restriction on `prefix`, returning whether there was one.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the restrictions' lock is poisoned.
+  pub fn unrestrict(&mut self, prefix: impl AsRef<str>) -> bool {
+    let mut restrictions = self.restrictions.write().unwrap();
+    let count = restrictions.len();
+
+    restrictions.retain(|(restricted, _)| restricted != prefix.as_ref());
+
+    restrictions.len() != count
+  }
+
+  /// The response refusing a client the restrictions on `path` do not
+  /// authorise, if any.
+  fn authorize(
+    &self,
+    path: &str,
+    certificate: Option<&openssl::x509::X509>,
+  ) -> Option<Response> {
+    // Without its restrictions, no request can be known to be authorised.
+    let Ok(restrictions) = self.restrictions.read() else {
+      error!("refusing a request for {path}: the restrictions are poisoned");
+
+      return Some(Response::temporary_failure(
+        "This page can not be accessed at the moment.",
+      ));
+    };
+    let mut policies = restrictions
+      .iter()
+      .filter(|(prefix, _)| is_beneath(path, prefix))
+      .map(|(_, policy)| policy)
+      .peekable();
+
+    policies.peek()?;
+
+    let Some(certificate) = certificate else {
+      return Some(Response::client_certificate_required(
+        "A client certificate is required to access this page.",
+      ));
+    };
+
+    policies.any(|policy| !policy.allows(certificate)).then(|| {
+      Response::certificate_not_authorised(
+        "Your certificate is not authorised to access this page.",
+      )
+    })
+  }
+
+  /// Serve the files within `directory` at `route`.
+  ///
+  /// Requests for a directory are answered with its `index.gmi`, and paths
+  /// which would escape `directory`, including through symbolic links, are
+  /// treated as missing. MIME types are
+  /// chosen by extension from the `Router`'s [`MimeTypes`], falling back to
+  /// content inspection with `auto-deduce-mime`, or to
+  /// `application/octet-stream`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().mount_directory("/files", "./public");
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn mount_directory(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    directory: impl Into<std::path::PathBuf>,
+  ) -> &mut Self {
+    let route = route.into();
+    let directory = directory.into();
+    let index = directory.clone();
+    let mime_types = self.mime_types.clone();
+    let index_mime_types = self.mime_types.clone();
+
+    self.mount(
+      format!("{}/*path", route.trim_end_matches('/')),
+      move |context: RouteContext| {
+        files::serve(
+          directory.clone(),
+          context.parameters.get("path").cloned().unwrap_or_default(),
+          mime_types.clone(),
+        )
+      },
+    );
+    self.mount(route, move |_| {
+      files::serve(index.clone(), String::new(), index_mime_types.clone())
+    });
+
+    self
+  }
+
+  /// Serve `emoji` as the capsule's icon at `/favicon.txt`, as described by
+  /// the Gemini favicon convention, replacing any icon set before.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_favicon("🦀"); 
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn set_favicon(&mut self, emoji: impl AsRef<str>) -> &mut Self {
+    self.mount_text("/favicon.txt", emoji.as_ref().trim())
+  }
+
+  /// Serve `content` as plain text at `/.well-known/{name}`, e.g., a
+  /// `security.txt`, replacing any content added under `name` before.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .add_well_known("security.txt", "Contact: mailto:security@example.com");
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn add_well_known(
+    &mut self,
+    name: impl AsRef<str>,
+    content: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    self.mount_text(
+      &format!("/.well-known/{}", name.as_ref().trim_start_matches('/')),
+      content,
+    )
+  }
+
+  /// Mount, or replace, a route serving `content` as plain text.
+  fn mount_text(
+    &mut self,
+    route: &str,
+    content: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    let content = content.into();
+    let handler = move |_| {
+      Response::new(20, content.clone())
+        .mime("text/plain")
+        .character_set("utf-8")
+        .raw()
+    };
+
+    if !self.replace(route, handler.clone()) {
+      self.mount(route, handler);
+    }
+
+    self
+  }
+
+  /// Map files with `extension` to `mime` when serving directories, replacing
+  /// any existing mapping.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().add_mime_type("ts", "video/mp2t"); 
+  /// ```
+  pub fn add_mime_type(
+    &mut self,
+    extension: impl AsRef<str>,
+    mime: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    self.mime_types.insert(extension, mime);
+
+    self
+  }
+
+  /// The registry of MIME types used when serving directories, shared by
+  /// every clone of the `Router`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let router = windmark::router::Router::new();
+  ///
+  /// assert_eq!(
+  ///   router.mime_types().get("gmi").as_deref(),
+  ///   Some("text/gemini")
+  /// );
+  /// ```
+  #[must_use]
+  pub const fn mime_types(&self) -> &MimeTypes { &self.mime_types }
+
+  /// Redirect requests for `route` to `target`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::router::{RedirectKind, Router};
+  ///
+  /// Router::new().redirect("/old", "/new", RedirectKind::Permanent);
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn redirect(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+    target: impl Into<String> + AsRef<str>,
+    kind: RedirectKind,
+  ) -> &mut Self {
+    let target = target.into();
+
+    self.mount(route, move |_| kind.response(target.as_str()))
+  }
+
+  /// Redirect requests for each route to its target, all with the same kind
+  /// of redirect.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::router::{RedirectKind, Router};
+  ///
+  /// Router::new().redirect_all(
+  ///   [("/blog", "/gemlog"), ("/blog/feed.xml", "/gemlog/atom.xml")],
+  ///   RedirectKind::Permanent,
+  /// );
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn redirect_all<R, T>(
+    &mut self,
+    redirects: impl IntoIterator<Item = (R, T)>,
+    kind: RedirectKind,
+  ) -> &mut Self
+  where
+    R: Into<String> + AsRef<str>,
+    T: Into<String> + AsRef<str>,
+  {
+    for (route, target) in redirects {
+      self.redirect(route, target, kind);
+    }
+
+    self
+  }
+
+  /// List the mounted routes, in the order they were mounted.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// let mut router = windmark::router::Router::new();
+  ///
+  /// router.mount("/", |_| Response::success("Hello!"));
+  ///
+  /// assert_eq!(router.routes()[0].pattern, "/");
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  #[must_use]
+  pub fn routes(&self) -> Vec<RouteInfo> {
+    self.routes.read().unwrap().routes()
+  }
+
+  /// Mount a gemtext index of every route which is not hidden at `route`,
+  /// labelled by their titles and followed by their descriptions.
+  ///
+  /// The index lists routes as they are when it is requested, so routes
+  /// mounted afterwards are included. The index itself is hidden.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{response::Response, router::RouteMetadata};
+  ///
+  /// windmark::router::Router::new()
+  ///   .enable_auto_index("/")
+  ///   .mount_with_metadata(
+  ///     "/time",
+  ///     RouteMetadata::new()
+  ///       .title("Time")
+  ///       .description("The current time on the server"),
+  ///     |_| Response::success("It's always tea time."),
+  ///   );
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn enable_auto_index(
+    &mut self,
+    route: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    self.mount_with_metadata(
+      route,
+      RouteMetadata::new().hidden(),
+      |context: RouteContext| {
+        Response::success(index::render(&context.routes()))
+      },
+    )
+  }
+
+  /// A handle to the per-route request statistics: hits, error rate, and
+  /// response time percentiles.
+  ///
+  /// The handle can be moved into a route or module, and reflects requests
+  /// handled after it was obtained.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let router = windmark::router::Router::new();
+  ///
+  /// for route in router.stats().routes() {
+  ///   println!(
+  ///     "{}: {:.1}% errors",
+  ///     route.pattern,
+  ///     route.error_rate() * 100.0
+  ///   );
+  /// }
+  /// ```
+  #[must_use]
+  pub fn stats(&self) -> Stats { self.stats.clone() }
+
+  /// Remove the route mounted at `pattern`, returning whether one was
+  /// mounted.
+  ///
+  /// Every clone of a `Router` shares its routes, so a clone captured by a
+  /// route or module may unmount routes while the server is running; the
+  /// change applies to subsequent requests.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// let mut router = windmark::router::Router::new();
+  ///
+  /// router.mount("/", |_| Response::success("Hello!"));
+  ///
+  /// assert!(router.unmount("/"));
+  /// assert!(router.routes().is_empty());
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn unmount(&mut self, pattern: impl AsRef<str>) -> bool {
+    Arc::make_mut(&mut self.routes.write().unwrap()).remove(pattern.as_ref())
+  }
+
+  /// Make `change` to a copy of the routes, to be installed whole with
+  /// [`Router::install_routes`], so that each request sees the routes from
+  /// before or after the change.
+  ///
+  /// # Errors
+  ///
+  /// if a route mounted by `change` conflicts, with every conflict.
+  #[cfg(feature = "config")]
+  fn stage_routes(
+    &self,
+    change: impl FnOnce(&mut Self),
+  ) -> Result<Arc<RouteTable>, Vec<String>> {
+    let mut staged = self.clone();
+
+    staged.routes = Arc::new(RwLock::new(self.routes.read().unwrap().clone()));
+    // The copy is not listening, so conflicts are collected rather than
+    // logged.
+    staged.listening = Arc::default();
+    staged.unmounted = Arc::default();
+    change(&mut staged);
+
+    let unmounted = std::mem::take(&mut *staged.unmounted.lock().unwrap());
+
+    if !unmounted.is_empty() {
+      return Err(unmounted);
+    }
+
+    let routes = staged.routes.read().unwrap().clone();
+
+    Ok(routes)
+  }
+
+  /// Install routes staged with [`Router::stage_routes`]. Routes mounted
+  /// through another clone since they were staged are replaced.
+  #[cfg(feature = "config")]
+  fn install_routes(&self, routes: Arc<RouteTable>) {
+    *self.routes.write().unwrap() = routes;
+  }
+
+  /// Swap the handler of the route mounted at `pattern`, returning whether
+  /// one was mounted.
+  ///
+  /// The route keeps its pattern and metadata. Like
+  /// [`Router::unmount`], the change applies to subsequent requests made
+  /// through any clone of the `Router`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// let mut router = windmark::router::Router::new();
+  ///
+  /// router.mount("/", |_| Response::success("Hello!"));
+  ///
+  /// assert!(router.replace("/", |_| Response::success("Goodbye!")));
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  pub fn replace<R>(
+    &mut self,
+    pattern: impl AsRef<str>,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> bool
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    Arc::make_mut(&mut self.routes.write().unwrap())
+      .replace(pattern.as_ref(), Handler::exclusive(handler))
+  }
+
+  /// Create an error handler which will be displayed on any error.
+  ///
+  /// The error handler is invoked when a route handler panics, and for
+  /// unmatched routes if no not-found handler has been set with
+  /// [`Router::set_not_found_handler`].
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_error_handler(|_| {
+  ///   windmark::response::Response::success("You have encountered an error!")
+  /// });
+  /// ```
+  pub fn set_error_handler<R>(
+    &mut self,
+    mut handler: impl FnMut(ErrorContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture<Output = Response> + Send + 'static,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.error_handler = Arc::new(AsyncMutex::new(Box::new(move |context| {
+      handler(context).into_future()
+    })));
+
+    self
+  }
+
+  /// Create a failure handler which customises the response to every early
+  /// [`Failure`](response::Failure) of the given status returned by a route
+  /// handler, such as with [`bail!`](crate::bail).
+  ///
+  /// The handler receives the failure as [`ErrorKind::Failure`]. Failures of
+  /// a status without a failure handler are sent as they are, and never reach
+  /// the error handler.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{context::ErrorKind, response::Response};
+  ///
+  /// windmark::router::Router::new().set_failure_handler(51, |context| {
+  ///   let ErrorKind::Failure(failure) = context.kind else {
+  ///     unreachable!()
+  ///   };
+  ///
+  ///   Response::not_found(format!(
+  ///     "{} ({} does not exist)",
+  ///     failure.message(),
+  ///     context.url.path()
+  ///   ))
+  /// });
+  /// ```
+  pub fn set_failure_handler<R>(
+    &mut self,
+    status: i32,
+    mut handler: impl FnMut(ErrorContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture<Output = Response> + Send + 'static,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.failure_handlers.insert(
+      status,
+      Arc::new(AsyncMutex::new(Box::new(move |context| {
+        handler(context).into_future()
+      }))),
+    );
+
+    self
+  }
+
+  /// Create a not-found handler which will be displayed when no route matches
+  /// the requested path.
+  ///
+  /// If no not-found handler is set, unmatched routes fall back to the error
+  /// handler.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_not_found_handler(|context| {
+  ///   windmark::response::Response::not_found(format!(
+  ///     "{} does not exist!",
+  ///     context.url.path()
+  ///   ))
+  /// });
+  /// ```
+  pub fn set_not_found_handler<R>(
+    &mut self,
+    mut handler: impl FnMut(NotFoundContext) -> R + Send + Sync + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture<Output = Response> + Send + 'static,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.not_found_handler =
+      Some(Arc::new(AsyncMutex::new(Box::new(move |context| {
+        handler(context).into_future()
+      }))));
+
+    self
+  }
+
+  /// Add a header for the `Router` which should be displayed on every route.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the header cannot be added.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().add_header(
+  ///   |context: windmark::context::RouteContext| {
+  ///     format!("This is displayed at the top of {}!", context.url.path())
+  ///   },
+  /// );
+  /// ```
+  #[track_caller]
+  pub fn add_header(&mut self, handler: impl Partial + 'static) -> &mut Self {
+    self.add_header_for(PartialScope::Everywhere, handler)
+  }
+
+  /// Add a header for the `Router` which should be displayed on the routes
+  /// within `scope`: a path prefix, such as `"/blog"`, or a predicate.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the header cannot be added.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::context::RouteContext;
+  ///
+  /// windmark::router::Router::new()
+  ///   .add_header_for("/blog", |_: RouteContext| "=> /blog Back".to_string())
+  ///   .add_header_for(
+  ///     |context: &RouteContext| context.url.path().ends_with(".gmi"),
+  ///     |_: RouteContext| "# My Capsule".to_string(),
+  ///   );
+  /// ```
+  #[track_caller]
+  pub fn add_header_for(
+    &mut self,
+    scope: impl Into<PartialScope>,
+    handler: impl Partial + 'static,
+  ) -> &mut Self {
+    (*self.headers.lock().unwrap())
+      .push((scope.into(), Registered::new(Box::new(handler))));
+
+    self
+  }
+
+  /// Add a footer for the `Router` which should be displayed on every route.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the header cannot be added.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().add_footer(
+  ///   |context: windmark::context::RouteContext| {
+  ///     format!("This is displayed at the bottom of {}!", context.url.path())
+  ///   },
+  /// );
+  /// ```
+  #[track_caller]
+  pub fn add_footer(&mut self, handler: impl Partial + 'static) -> &mut Self {
+    self.add_footer_for(PartialScope::Everywhere, handler)
+  }
+
+  /// Add a footer for the `Router` which should be displayed on the routes
+  /// within `scope`: a path prefix, such as `"/blog"`, or a predicate.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the footer cannot be added.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::context::RouteContext;
+  ///
+  /// windmark::router::Router::new().add_footer_for("/blog", |_: RouteContext| {
+  ///   "=> /blog/atom.xml Subscribe".to_string()
+  /// });
+  /// ```
+  #[track_caller]
+  pub fn add_footer_for(
+    &mut self,
+    scope: impl Into<PartialScope>,
+    handler: impl Partial + 'static,
+  ) -> &mut Self {
+    (*self.footers.lock().unwrap())
+      .push((scope.into(), Registered::new(Box::new(handler))));
+
+    self
+  }
+
+  /// Remove every header, including those added by modules.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the headers cannot be cleared.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .add_header(|_| "# My Capsule".to_string())
+  ///   .clear_headers();
+  /// ```
+  pub fn clear_headers(&mut self) -> &mut Self {
+    self.headers.lock().unwrap().clear();
+
+    self
+  }
+
+  /// Remove every footer, including those added by modules.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the footers cannot be cleared.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .add_footer(|_| "=> / Home".to_string())
+  ///   .clear_footers();
+  /// ```
+  pub fn clear_footers(&mut self) -> &mut Self {
+    self.footers.lock().unwrap().clear();
+
+    self
+  }
+
+  /// List the headers, in the order they are displayed.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the headers cannot be read.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let mut router = windmark::router::Router::new();
+  ///
+  /// router.add_header_for("/blog", |_| "=> /blog Back".to_string());
+  ///
+  /// assert_eq!(router.headers().len(), 1);
+  ///
+  /// for header in router.headers() {
+  ///   println!("{:?} added at {}", header.scope, header.location);
+  /// }
+  /// ```
+  #[must_use]
+  pub fn headers(&self) -> Vec<PartialInfo> {
+    describe_partials(&self.headers.lock().unwrap())
+  }
+
+  /// List the footers, in the order they are displayed.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the footers cannot be read.
+  #[must_use]
+  pub fn footers(&self) -> Vec<PartialInfo> {
+    describe_partials(&self.footers.lock().unwrap())
+  }
+
+  /// Keep only the headers for which `keep` returns `true`, e.g., to remove
+  /// the headers added by a particular module.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the headers cannot be modified.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .add_header(|_| "# My Capsule".to_string())
+  ///   .retain_headers(|header| !header.location.file().contains("vendor"));
+  /// ```
+  pub fn retain_headers(
+    &mut self,
+    keep: impl FnMut(&PartialInfo) -> bool,
+  ) -> &mut Self {
+    retain_partials(&mut self.headers.lock().unwrap(), keep);
+
+    self
+  }
+
+  /// Keep only the footers for which `keep` returns `true`.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the footers cannot be modified.
+  pub fn retain_footers(
+    &mut self,
+    keep: impl FnMut(&PartialInfo) -> bool,
+  ) -> &mut Self {
+    retain_partials(&mut self.footers.lock().unwrap(), keep);
+
+    self
+  }
+
+  /// Set the templates which responses produced with `Response::template`
+  /// are rendered from.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::template::Templates;
+  ///
+  /// windmark::router::Router::new().set_templates(Templates::new("templates"));
+  /// ```
+  #[cfg(feature = "templates")]
+  pub fn set_templates(
+    &mut self,
+    templates: crate::template::Templates,
+  ) -> &mut Self {
+    self.templates = Some(templates);
+
+    self
+  }
+
+  /// Add a processor which transforms the lines of every successful
+  /// `text/gemini` response, after the post-route callback has run.
+  ///
+  /// Processors run in the order they were added and see the whole page,
+  /// including its headers and footers. Raw, streamed, and non-gemtext
+  /// responses are left untouched.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the processor cannot be added.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::gemtext::Line;
+  ///
+  /// windmark::router::Router::new().add_gemtext_processor(
+  ///   |_: windmark::context::HookContext, lines: &mut Vec<Line>| {
+  ///     for line in lines {
+  ///       if let Line::Link {
+  ///         url, ..
+  ///       } = line
+  ///       {
+  ///         *url = url.replace("http://", "https://");
+  ///       }
+  ///     }
+  ///   },
+  /// );
+  /// ```
+  #[track_caller]
+  pub fn add_gemtext_processor(
+    &mut self,
+    processor: impl GemtextHook + 'static,
+  ) -> &mut Self {
+    (*self.gemtext_processors.lock().unwrap())
+      .push(Registered::new(Box::new(processor)));
+
+    self
+  }
+
+  /// Run the `Router` and wait for requests
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().run(); 
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// if the client could not be accepted.
+  ///
+  /// # Errors
+  ///
+  /// if the `Router` could not be finalized, the port is invalid, or the
+  /// `TcpListener` could not be bound.
+  pub async fn run(&mut self) -> Result<(), crate::Error> {
+    if !self.finalized {
+      self.finalize()?;
+    }
+
+    let listeners = self.bind()?;
+
+    self.serve(listeners).await
+  }
+
+  /// Start the `Router` in the background, returning once its listeners are
+  /// bound.
+  ///
+  /// The returned [`ServerHandle`] exposes the bound addresses, so a `Router`
+  /// set to port 0 can be reached on the port the operating system assigned
+  /// to it, e.g., by integration tests running in parallel.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// async fn test() -> Result<(), windmark::Error> {
+  ///   let server = windmark::router::Router::new()
+  ///     .set_private_key_file("windmark_private.pem")
+  ///     .set_certificate_file("windmark_public.pem")
+  ///     .set_port(0)
+  ///     .start()?;
+  ///
+  ///   println!("listening on port {}", server.local_addr().port());
+  ///
+  ///   server.stop().await
+  /// }
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// if the `Router` could not be finalized, the port is invalid, or the
+  /// `TcpListener` could not be bound.
+  ///
+  /// # Panics
+  ///
+  /// if called outside of a `tokio` runtime, with the `tokio` runtime.
+  pub fn start(&mut self) -> Result<ServerHandle, crate::Error> {
+    if !self.finalized {
+      self.finalize()?;
+    }
+
+    let listeners = self.bind()?;
+    let addresses = listeners
+      .iter()
+      .map(runtime::TcpListener::local_addr)
+      .collect::<std::io::Result<Vec<_>>>()?;
+    let mut router = self.clone();
+    let task = runtime::spawn(async move { router.serve(listeners).await });
+
+    Ok(ServerHandle::new(addresses, self.shutdown.clone(), task))
+  }
+
+  /// Bind the configured listeners, or the configured port on every interface
+  /// if there are none.
+  fn bind(&self) -> Result<Vec<runtime::TcpListener>, crate::Error> {
+    let addresses = if self.listeners.is_empty() {
+      let port = u16::try_from(self.port)
+        .map_err(|_| crate::Error::InvalidPort(self.port))?;
+
+      vec![(format!("0.0.0.0:{port}"), None)]
+    } else {
+      self.listeners.clone()
+    };
+    let mut listeners = Vec::with_capacity(addresses.len());
+
+    for (address, name) in addresses {
+      let listener = self.socket_options.bind(address)?;
+
+      if let Some(name) = name {
+        if let Ok(mut names) = self.listener_names.write() {
+          names.insert(listener.local_addr()?, name);
+        }
+      }
+
+      listeners.push(runtime::listener_from_std(listener)?);
+    }
+
+    Ok(listeners)
+  }
+
+  /// Run the `Router` on an already bound `TcpListener` and wait for requests
+  ///
+  /// This allows binding privileged ports, socket activation, or custom
+  /// socket options to be handled before the listener is handed to Windmark.
+  /// Listeners added with [`Router::add_listener`] and the configured port are
+  /// ignored.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+  ///
+  /// windmark::router::Router::new().run_with_listener(listener);
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// if the `Router` could not be finalized, or the `TcpListener` could not be
+  /// used by the runtime.
+  pub async fn run_with_listener(
+    &mut self,
+    listener: std::net::TcpListener,
+  ) -> Result<(), crate::Error> {
+    listener.set_nonblocking(true)?;
+
+    self
+      .serve(vec![runtime::listener_from_std(listener)?])
+      .await
+  }
+
+  /// Prepare the `Router` to serve requests, reporting configuration errors
+  /// before any listener is started.
+  ///
+  /// The certificate and private key are checked to have been set and to be
+  /// readable, and the TLS acceptor is built from them, unless one was set
+  /// with [`Router::set_ssl_acceptor`]. Hostnames which could never match a
+  /// request, and routes which could not be mounted, e.g., because they
+  /// conflict with a route mounted before them, are reported. [`Router::run`]
+  /// finalizes the `Router` if it has not been already.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// let mut router = windmark::router::Router::new();
+  ///
+  /// router
+  ///   .mount("/posts/:id", |_| Response::success("A post"))
+  ///   .mount("/posts/:name", |_| Response::success("Another post"));
+  ///
+  /// assert!(router.finalize().is_err());
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// if a route could not be mounted, a hostname is invalid, or the
+  /// certificate or private key is missing, unreadable, or otherwise unusable.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the list of unmounted routes' lock is poisoned.
+  pub fn finalize(&mut self) -> Result<&mut Self, crate::Error> {
+    let unmounted = std::mem::take(&mut *self.unmounted.lock().unwrap());
+
+    if !unmounted.is_empty() {
+      return Err(crate::Error::Routes(unmounted));
+    }
+
+    if let Some(hostname) = self
+      .hostnames
+      .iter()
+      .find(|hostname| !is_valid_hostname(hostname))
+    {
+      return Err(crate::Error::InvalidHostname(hostname.clone()));
+    }
+
+    if !self.custom_ssl_acceptor {
+      self.certificate.check("certificate")?;
+      self.private_key.check("private key")?;
+      self.create_acceptor()?;
+    }
+
+    self.finalized = true;
+
+    Ok(self)
+  }
+
+  /// Run `job` every `interval` while the `Router` is running, e.g., to
+  /// regenerate a feed or to prune a cache.
+  ///
+  /// Tasks start when the `Router` starts accepting connections, or right
+  /// away if it already has, and first run after one `interval`. Once a
+  /// shutdown is requested, tasks are not run again, and a run in progress is
+  /// completed before the running [`Router::run`] returns. A panic within
+  /// `job` is logged, and the task is run again after the next `interval`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use std::time::Duration;
+  ///
+  /// windmark::router::Router::new()
+  ///   .spawn_task(Duration::from_secs(60 * 60), || {
+  ///     async { println!("An hour has passed.") }
+  ///   });
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the task list's lock is poisoned.
+  #[track_caller]
+  pub fn spawn_task<R>(
+    &mut self,
+    interval: time::Duration,
+    mut job: impl FnMut() -> R + Send + 'static,
+  ) -> &mut Self
+  where
+    R: IntoFuture<Output = ()> + Send + 'static,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    let task = tasks::Task::new(
+      interval,
+      Box::new(move || Box::pin(job().into_future())),
+      std::panic::Location::caller(),
+    );
+
+    self.tasks.lock().unwrap().add(task);
+
+    self
+  }
+
+  /// Render the route at `path` when the `Router` starts accepting
+  /// connections, and again every `interval`, so that requests for `path`
+  /// are answered without waiting on its handler, e.g., for an expensive page
+  /// after a deploy.
+  ///
+  /// Only successful responses are kept, and they are only used for requests
+  /// without a query or a client certificate, as the handler is called
+  /// without either. Middleware, headers, and footers still run on each
+  /// request.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use std::time::Duration;
+  ///
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new()
+  ///   .mount("/stats", |_| Response::success("Expensive statistics..."))
+  ///   .warm("/stats", Duration::from_secs(5 * 60));
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the task list's lock is poisoned.
+  #[track_caller]
+  pub fn warm(
+    &mut self,
+    path: impl Into<String>,
+    interval: time::Duration,
+  ) -> &mut Self {
+    let path = path.into();
+    let router = self.clone();
+    let task = tasks::Task::new(
+      interval,
+      Box::new(move || {
+        let router = router.clone();
+        let path = path.clone();
+
+        Box::pin(async move { router.render_warmed(&path).await })
+      }),
+      std::panic::Location::caller(),
+    )
+    .immediately();
+
+    self.tasks.lock().unwrap().add(task);
+
+    self
+  }
+
+  /// Hand a response made from an error to the error handler, or one made
+  /// from an early failure to the failure handler set for its status, if
+  /// there is one.
+  async fn customise_failure(
+    &self,
+    response: Response,
+    context: impl FnOnce(ErrorKind) -> ErrorContext + Send,
+  ) -> Response {
+    if let Some(error) = &response.error {
+      let context = context(ErrorKind::Handler(error.clone()));
+
+      return (*self.error_handler).lock().await.call(context).await;
+    }
+
+    let Some(failure) = &response.failure else {
+      return response;
+    };
+    let Some(handler) = self.failure_handlers.get(&failure.status()) else {
+      return response;
+    };
+    let context = context(ErrorKind::Failure(failure.clone()));
+
+    handler.lock().await.call(context).await
+  }
+
+  /// Call the handler of the route at `path` without a request, keeping its
+  /// response if it succeeds.
+  async fn render_warmed(&self, path: &str) {
+    let routes = self.routes.read().unwrap().clone();
+    let Ok(route) = routes.at(path) else {
+      warn!("could not warm {path}, as no route matches it");
+      self.warmed.set(path, None);
+
+      return;
+    };
+    let Ok(url) = Url::parse(&format!(
+      "gemini://{}{path}",
+      self.hostnames.first().map_or("localhost", String::as_str)
+    )) else {
+      return;
+    };
+    let handler = route.value.handler.clone();
+    let timeout = route
+      .value
+      .info
+      .timeout
+      .or_else(|| self.limits().handler_timeout);
+    let context = RouteContext::new(
+      Err(std::io::Error::from(std::io::ErrorKind::NotConnected)),
+      url,
+      generate_request_id(),
+      crate::context::Parameters::lazy(routes.clone(), path),
+      None,
+      TlsInfo::default(),
+    )
+    .with_routes(routes.clone())
+    .with_handler(route.value.handler_id());
+    let response = runtime::timeout(
+      timeout,
+      unwind::CatchUnwind::new(Box::pin(
+        async move { handler.call(context).await },
+      )),
+    )
+    .await;
+
+    self.warmed.set(
+      path,
+      match response {
+        Ok(Ok(response))
+          if response.status == 20 && response.body.is_none() =>
+          Some(response),
+        Ok(Ok(response)) => {
+          warn!(
+            "could not warm {path}, as it responded with {}",
+            response.status
+          );
+
+          None
+        }
+        Ok(Err(payload)) => {
+          error!(
+            "route handler for {path} panicked while warming: {}",
+            unwind::panic_message(&*payload)
+          );
+
+          None
+        }
+        Err(_) => {
+          warn!("could not warm {path}, as its handler timed out");
+
+          None
+        }
+      },
+    );
+  }
+
+  /// Stop the `Router`, and every clone of it, from accepting connections.
+  ///
+  /// Connections which are already open are handled to completion, after
+  /// which modules are notified through their `on_shutdown` hooks and the
+  /// running [`Router::run`] returns. A `Router` which has been shut down
+  /// can not be run again.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// let mut router = windmark::router::Router::new();
+  /// let handle = router.clone();
+  ///
+  /// router.mount("/stop", move |_| {
+  ///   handle.shutdown();
+  ///
+  ///   Response::success("Stopping...")
+  /// });
+  /// ```
+  pub fn shutdown(&self) { self.shutdown.trigger(); }
+
+  async fn serve(
+    &mut self,
+    listeners: Vec<runtime::TcpListener>,
+  ) -> Result<(), crate::Error> {
+    if !self.finalized {
+      self.finalize()?;
+    }
+
+    #[cfg(feature = "logger")]
+    if self.default_logger {
+      if let Err(e) = pretty_env_logger::formatted_builder()
+        .parse_filters(&self.log_filter)
+        .try_init()
+      {
+        eprintln!("could not initialise the default logger: {e}");
+      }
+    }
+
+    if let Some(expiry) = CertificateExpiry::new(&self.acceptor()) {
+      info!(
+        "windmark is serving a certificate which expires {} ({} days)",
+        expiry.not_after, expiry.days_remaining
+      );
+    }
+
+    self.check_certificate_expiry();
+
+    #[cfg(all(unix, feature = "config"))]
+    if self.reload_config_on_sighup {
+      self.reload_config_on_hangup();
+    }
+
+    if let Some(interval) = self.certificate_expiry_interval {
+      let router = self.clone();
+
+      runtime::detach(async move {
+        while runtime::or(
+          async {
+            runtime::sleep(interval).await;
+
+            true
+          },
+          async {
+            router.shutdown.triggered().await;
+
+            false
+          },
+        )
+        .await
+        {
+          router.check_certificate_expiry();
+        }
+      });
+    }
+
+    if let Ok(mut tasks) = self.tasks.lock() {
+      tasks.start(&self.shutdown);
+    }
+
+    self.connection_slots = self
+      .max_connections
+      .map(|limit| Arc::new(runtime::Semaphore::new(limit)));
+
+    if let Ok(mut serving) = self.serving.write() {
+      *serving = Some(self.clone());
+    }
+
+    let mut bound = Vec::with_capacity(listeners.len());
+
+    for listener in listeners {
+      let address = listener.local_addr()?;
+
+      #[cfg(feature = "logger")]
+      info!("windmark is listening for connections on {address}");
+
+      if let Ok(mut listening) = self.listening.lock() {
+        listening.push(address);
+      }
+
+      if let Some(callback) = &self.listen_callback {
+        if let Ok(mut callback) = callback.lock() {
+          callback.call(address);
+        }
+      }
+
+      bound.push((listener, address));
+    }
+
+    if let Some(&(_, address)) = bound.first() {
+      self.start_modules(address).await;
+    }
+
+    let accepting = bound
+      .into_iter()
+      .map(|(listener, address)| {
+        runtime::spawn(self.clone().accept_with(
+          listener,
+          address,
+          move |router, stream, peer_address, permits| {
+            router.connect(stream, peer_address, address, permits)
+          },
+        ))
+      })
+      .collect::<Vec<_>>();
+
+    let mut failure = None;
+
+    for accept in accepting {
+      if let Some(Err(e)) = runtime::join(accept).await {
+        failure.get_or_insert(e);
+      }
+    }
+
+    self.stop().await;
+
+    failure.map_or(Ok(()), |e| Err(crate::Error::Io(e)))
+  }
+
+  /// Wait for open connections and background tasks to finish, then stop
+  /// the modules.
+  async fn stop(&self) {
+    self.shutdown.drained().await;
+
+    let tasks = self
+      .tasks
+      .lock()
+      .map(|mut tasks| tasks.stop())
+      .unwrap_or_default();
+
+    for task in tasks {
+      runtime::join(task).await;
+    }
+
+    if let Ok(mut listening) = self.listening.lock() {
+      listening.clear();
+    }
+
+    if let Ok(mut names) = self.listener_names.write() {
+      names.clear();
+    }
+
+    if let Ok(mut serving) = self.serving.write() {
+      serving.take();
+    }
+
+    self.stop_modules().await;
+  }
+
+  async fn stop_modules(&self) {
+    for attached in self.async_module_snapshot().iter() {
+      unwind::async_module_hook(
+        "on_shutdown",
+        attached.location,
+        None,
+        attached.module.lock().await.on_shutdown(),
+      )
+      .await;
+    }
+
+    for attached in self.module_snapshot().iter() {
+      if let Ok(mut module) = attached.module.lock() {
+        unwind::module_hook("on_shutdown", attached.location, None, || {
+          module.on_shutdown();
+        });
+      }
+    }
+  }
+
+  async fn start_modules(&self, address: SocketAddr) {
+    for attached in self.async_module_snapshot().iter() {
+      unwind::async_module_hook(
+        "on_start",
+        attached.location,
+        None,
+        attached.module.lock().await.on_start(address),
+      )
+      .await;
+    }
+
+    for attached in self.module_snapshot().iter() {
+      if let Ok(mut module) = attached.module.lock() {
+        unwind::module_hook("on_start", attached.location, None, || {
+          module.on_start(address);
+        });
+      }
+    }
+  }
+
+  fn check_certificate_expiry(&self) {
+    let Some(warning) = self.certificate_expiry_warning else {
+      return;
+    };
+    let Some(expiry) = CertificateExpiry::new(&self.acceptor()) else {
+      return;
+    };
+
+    if expiry.days_remaining > i32::try_from(warning).unwrap_or(i32::MAX) {
+      return;
+    }
+
+    if let Some(callback) = &self.certificate_expiry_callback {
+      if let Ok(mut callback) = callback.lock() {
+        callback.call(expiry);
+      }
+    } else if expiry.days_remaining < 0 {
+      error!("the served certificate expired {}", expiry.not_after);
+    } else {
+      warn!(
+        "the served certificate expires {} ({} days)",
+        expiry.not_after, expiry.days_remaining
+      );
+    }
+  }
+
+  /// Accept connections on `listener`, bound to `address`, until the
+  /// `Router` is shut down, handing each one which the guards, connection
+  /// callback, and connection limits admit to `on_connection` along with the
+  /// permits it must hold for as long as it is open.
+  ///
+  /// Failures to accept are handled as the accept policy directs.
+  pub(crate) async fn accept_with<F, Fut>(
+    self,
+    listener: runtime::TcpListener,
+    address: SocketAddr,
+    on_connection: F,
+  ) -> std::io::Result<()>
+  where
+    F: Fn(Self, runtime::TcpStream, SocketAddr, Permits) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    let router = self
+      .serving
+      .read()
+      .ok()
+      .and_then(|serving| serving.clone())
+      .unwrap_or(self);
+
+    router
+      .admit_connections(listener, address, on_connection)
+      .await
+  }
+
+  async fn admit_connections<F, Fut>(
+    self,
+    listener: runtime::TcpListener,
+    address: SocketAddr,
+    on_connection: F,
+  ) -> std::io::Result<()>
+  where
+    F: Fn(Self, runtime::TcpStream, SocketAddr, Permits) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    let connections = self.connection_slots.clone();
+    let mut consecutive_failures = 0;
+
+    loop {
+      let Some((permit, accepted)) = runtime::or(
+        async {
+          let permit = match &connections {
+            Some(connections) => runtime::acquire(connections).await,
+            None => None,
+          };
+
+          Some((permit, listener.accept().await))
+        },
+        async {
+          self.shutdown.triggered().await;
+
+          None
+        },
+      )
+      .await
+      else {
+        break;
+      };
+
+      match accepted {
+        Ok((stream, peer_address)) => {
+          consecutive_failures = 0;
+
+          let Some(peer_permit) = self.admit(peer_address, address) else {
+            continue;
+          };
+
+          if let Err(e) = self.socket_options.configure(&stream) {
+            warn!("could not apply socket options: {e}");
+          }
+
+          runtime::detach(on_connection(
+            self.clone(),
+            stream,
+            peer_address,
+            (permit, peer_permit, self.shutdown.connection()),
+          ));
+        }
+        Err(e) =>
+          self
+            .accept_failed(address, e, &mut consecutive_failures)
+            .await?,
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Whether a connection from `peer_address` on the listener at `listener`
+  /// passes the guards, the connection callback, and the limit of
+  /// connections per peer, along with the permit it must hold if there is a
+  /// limit.
+  #[allow(clippy::option_option)]
+  fn admit(
+    &self,
+    peer_address: SocketAddr,
+    listener: SocketAddr,
+  ) -> Option<Option<connections::PeerPermit>> {
+    if !self.guards_accept(peer_address.ip()) {
+      debug!("refusing connection from {}: banned", peer_address.ip());
+
+      return None;
+    }
+
+    if !self.connection_accepted(peer_address, listener) {
+      return None;
+    }
+
+    let Some(limit) = self.limits().max_peer_connections else {
+      return Some(None);
+    };
+    let permit = self.peer_connections.try_acquire(peer_address.ip(), limit);
+
+    if permit.is_none() {
+      debug!(
+        "refusing connection from {}: too many open connections",
+        peer_address.ip()
+      );
+    }
+
+    permit.map(Some)
+  }
+
+  /// Serve Gemini over an accepted connection, beginning with its TLS
+  /// handshake.
+  fn connect(
+    mut self,
+    stream: runtime::TcpStream,
+    peer_address: SocketAddr,
+    listener: SocketAddr,
+    permits: Permits,
+  ) -> impl Future<Output = ()> + Send {
+    let acceptor = self.acceptor();
+    let span = telemetry::Span::connection(peer_address, listener);
+
+    span.instrument(async move {
+      let _permits = permits;
+      let ssl = match ssl::Ssl::new(acceptor.context()) {
+        Ok(ssl) => ssl,
+        Err(e) => {
+          error!("ssl context error: {e:?}");
+
+          return;
+        }
+      };
+
+      #[cfg(feature = "tokio")]
+      let quick_stream = tokio_openssl::SslStream::new(ssl, stream);
+      #[cfg(any(feature = "async-std", feature = "smol"))]
+      let quick_stream = async_std_openssl::SslStream::new(ssl, stream);
+
+      match quick_stream {
+        Ok(mut stream) => {
+          if !self.handshake(&mut stream, peer_address, listener).await {
+            return;
+          }
+
+          if let Err(e) = self.handle(&mut stream, listener).await {
+            error!("handle error: {e}");
+          }
+        }
+        Err(e) => error!("ssl stream error: {e:?}"),
+      }
+    })
+  }
+
+  /// Perform the TLS handshake of a connection from `peer_address` on the
+  /// listener at `listener`, reporting it if it fails.
+  async fn handshake(
+    &self,
+    stream: &mut Stream,
+    peer_address: SocketAddr,
+    listener: SocketAddr,
+  ) -> bool {
+    let timeout = self.limits().handshake_timeout;
+    let reason = match runtime::timeout(
+      timeout,
+      std::pin::Pin::new(stream).accept(),
+    )
+    .await
+    {
+      Ok(Ok(())) => return true,
+      Ok(Err(e)) => HandshakeFailure::Ssl(e),
+      Err(_) => HandshakeFailure::Timeout(timeout.unwrap_or_default()),
+    };
+
+    warn!("tls handshake with {peer_address} failed: {reason}");
+
+    if let Some(callback) = &self.handshake_error_callback {
+      if let Ok(mut callback) = callback.lock() {
+        callback.call(&HandshakeError {
+          peer_address,
+          listener,
+          reason,
+        });
+      }
+    }
+
+    false
+  }
+
+  /// Whether the connection callback, if any, lets a connection from
+  /// `peer_address` on the listener at `listener` go on to its handshake.
+  fn connection_accepted(
+    &self,
+    peer_address: SocketAddr,
+    listener: SocketAddr,
+  ) -> bool {
+    let Some(callback) = &self.connection_callback else {
+      return true;
+    };
+    let context = crate::context::ConnectionContext {
+      peer_address,
+      listener,
+      listener_name: self
+        .listener_names
+        .read()
+        .ok()
+        .and_then(|names| names.get(&listener).cloned()),
+    };
+
+    let accepted = callback
+      .lock()
+      .map_or(true, |mut callback| callback.call(&context));
+
+    if !accepted {
+      debug!(
+        "refusing connection from {}: rejected by the connection callback",
+        peer_address.ip()
+      );
+    }
+
+    accepted
+  }
+
+  /// React to a failure to accept a connection on the listener at `address`
+  /// as the accept policy directs, returning the error if the listener should
+  /// give up.
+  async fn accept_failed(
+    &self,
+    address: SocketAddr,
+    error: std::io::Error,
+    consecutive_failures: &mut usize,
+  ) -> std::io::Result<()> {
+    if accept::is_transient(&error) {
+      debug!("could not accept a connection on {address}: {error}");
+
+      return Ok(());
+    }
+
+    *consecutive_failures += 1;
+
+    error!(
+      "could not accept a connection on {address} ({consecutive_failures} in \
+       a row): {error}"
+    );
+
+    let failure = AcceptError {
+      listener: address,
+      consecutive: *consecutive_failures,
+      error,
+    };
+
+    if failure.consecutive >= self.accept_policy.report_after {
+      if let Some(callback) = &self.accept_error_callback {
+        if let Ok(mut callback) = callback.lock() {
+          callback.call(&failure);
+        }
+      }
+    }
+
+    if self.accept_policy.is_fatal(failure.consecutive) {
+      error!("giving up on accepting connections on {address}");
+      self.shutdown.trigger();
+
+      return Err(failure.error);
+    }
+
+    runtime::or(
+      runtime::sleep(self.accept_policy.backoff(failure.consecutive)),
+      self.shutdown.triggered(),
+    )
+    .await;
+
+    Ok(())
+  }
+
+  async fn handle(
+    &mut self,
+    stream: &mut Stream,
+    listener: SocketAddr,
+  ) -> Result<(), Box<dyn Error>> {
+    let request_id = generate_request_id();
+    let span = telemetry::Span::request(&request_id);
+
+    span
+      .instrument(self.respond(stream, listener, request_id, &span))
+      .await
+  }
+
+  async fn respond(
+    &mut self,
+    stream: &mut Stream,
+    listener: SocketAddr,
+    request_id: String,
+    span: &telemetry::Span,
+  ) -> Result<(), Box<dyn Error>> {
+    let started_at = time::Instant::now();
+    let received_at = time::SystemTime::now();
+    let (url, bad_request, raw_request) =
+      runtime::timeout(self.limits().read_timeout, async {
+        let mut request = self.buffers.take();
+        let mut chunk = [0u8; 1024];
+
+        while let Ok(size) = stream.read(&mut chunk).await {
+          if size == 0 {
+            break;
+          }
+
+          request.extend_from_slice(&chunk[..size]);
+
+          if request.contains(&b'\n') || request.len() > MAX_REQUEST_LENGTH {
+            break;
+          }
+        }
+
+        parse_request(&request).map(|(url, bad_request)| {
+          (url, bad_request, crate::context::RawRequest::new(request))
+        })
+      })
+      .await??;
+
+    span.record_url(&url);
+
+    let peer_address = stream.get_ref().peer_addr().ok();
+
+    if let Some(peer_address) = peer_address {
+      match self.guards_admit(peer_address.ip()) {
+        guard::Admission::Allow => {}
+        guard::Admission::Delay(delay) => runtime::sleep(delay).await,
+        guard::Admission::SlowDown(wait) => {
+          return self
+            .write_response(
+              stream,
+              Response::slow_down(wait.as_secs().max(1).to_string()),
+              "",
+              "",
+            )
+            .await;
+        }
+      }
+    }
+
+    let closed = crate::context::Closed::default();
+    #[cfg(feature = "logger")]
+    let (path, logged_id) = (url.path().to_string(), request_id.clone());
+    let routing = self.route(
+      Incoming {
+        peer_address,
+        listener,
+        url,
+        bad_request,
+        request_id,
+        certificate: stream.ssl().peer_certificate(),
+        tls: TlsInfo::new(stream.ssl()),
+        raw_request,
+        received_at,
+        started_at,
+        closed: closed.clone(),
+      },
+      span,
+    );
+    let (content, header, footer) = runtime::or(routing, async {
+      disconnected(stream).await;
+      closed.close();
+
+      std::future::pending().await
+    })
+    .await;
+
+    if let Some(peer_address) = peer_address {
+      self.guards_record(peer_address.ip(), content.status);
+    }
+
+    #[cfg(feature = "logger")]
+    let summary = self.log_requests.then(|| {
+      let length = match (content.status, content.raw_body) {
+        (20, true) => content.content_bytes().len(),
+        (20, false) =>
+          header.len() + content.content_bytes().len() + 1 + footer.len(),
+        _ => 0,
+      };
+
+      (content.status, self.meta(&content), length)
+    });
+    let written = self.write_response(stream, content, &header, &footer).await;
+
+    #[cfg(feature = "logger")]
+    if let Some((status, meta, length)) = summary {
+      info!(
+        "[{logged_id}] {} {path} {status} {meta} ({length} bytes, {:?})",
+        peer_address.map_or_else(|| "-".to_string(), |peer| peer.to_string()),
+        started_at.elapsed()
+      );
+    }
+
+    written
+  }
+
+  /// Log a request rejected before routing and report it to the rejection
+  /// callback.
+  fn reject(&self, context: HookContext, rejection: &Rejection) {
+    debug!(
+      "rejecting request {} ({}): {rejection}",
+      context.request_id,
+      rejection.code()
+    );
+
+    if let Some(callback) = &self.rejection_callback {
+      if let Ok(mut callback) = callback.lock() {
+        callback.call(context, rejection);
+      }
+    }
+  }
+
+  /// Add a guard consulted for every connection and request.
+  pub(crate) fn add_guard(&self, guard: Arc<dyn guard::Guard>) {
+    if let Ok(mut guards) = self.guards.write() {
+      guards.push(guard);
+    }
+  }
+
+  fn guards_accept(&self, ip: std::net::IpAddr) -> bool {
+    self
+      .guards
+      .read()
+      .map_or(true, |guards| guards.iter().all(|guard| guard.accepts(ip)))
+  }
+
+  /// The strictest admission of every guard.
+  fn guards_admit(&self, ip: std::net::IpAddr) -> guard::Admission {
+    self
+      .guards
+      .read()
+      .ok()
+      .and_then(|guards| guards.iter().map(|guard| guard.admit(ip)).max())
+      .unwrap_or(guard::Admission::Allow)
+  }
+
+  fn guards_record(&self, ip: std::net::IpAddr, status: i32) {
+    if let Ok(guards) = self.guards.read() {
+      for guard in guards.iter() {
+        guard.record(ip, status);
+      }
+    }
+  }
+
+  /// Route a request which arrived over another protocol, e.g., through the
+  /// HTTP gateway, returning its response, header, and footer.
+  #[cfg(any(feature = "http-gateway", feature = "gopher"))]
+  pub(crate) async fn dispatch(
+    &mut self,
+    peer_address: Option<SocketAddr>,
+    listener: SocketAddr,
+    url: Url,
+  ) -> (Response, String, String) {
+    let request_id = generate_request_id();
+    let span = telemetry::Span::request(&request_id);
+
+    span.record_url(&url);
+    span
+      .instrument(self.route(
+        Incoming {
+          peer_address,
+          listener,
+          url,
+          bad_request: None,
+          request_id,
+          certificate: None,
+          tls: TlsInfo::default(),
+          raw_request: crate::context::RawRequest::default(),
+          received_at: time::SystemTime::now(),
+          started_at: time::Instant::now(),
+          closed: crate::context::Closed::default(),
+        },
+        &span,
+      ))
+      .await
+  }
+
+  #[allow(
+    clippy::too_many_lines,
+    clippy::needless_pass_by_ref_mut,
+    clippy::significant_drop_in_scrutinee
+  )]
+  async fn route(
+    &mut self,
+    request: Incoming,
+    span: &telemetry::Span,
+  ) -> (Response, String, String) {
+    let Incoming {
+      peer_address,
+      listener,
+      url,
+      mut bad_request,
+      request_id,
+      certificate,
+      tls,
+      raw_request,
+      received_at,
+      started_at,
+      closed,
+    } = request;
+    let peer_address = || {
+      peer_address
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotConnected))
+    };
+    let listener_name = self
+      .listener_names
+      .read()
+      .ok()
+      .and_then(|names| names.get(&listener).cloned());
+    let mut footer = String::new();
+    let mut header = String::new();
+
+    if bad_request.is_none() && self.strict_urls {
+      bad_request = validate_url(&url).err();
+    }
+
+    if let Some(reason) = bad_request {
+      self.reject(
+        HookContext::new(
+          peer_address(),
+          listener,
+          url.clone(),
+          request_id.clone(),
+          None,
+          certificate.clone(),
+          tls.clone(),
+        )
+        .with_listener_name(listener_name.clone())
+        .with_request(raw_request.clone(), received_at),
+        &reason,
+      );
+
+      let content = (*self.error_handler)
+        .lock()
+        .await
+        .call(ErrorContext::new(
+          peer_address(),
+          url,
+          request_id.clone(),
+          certificate.clone(),
+          tls.clone(),
+          ErrorKind::BadRequest(reason),
+        ))
+        .await;
+
+      return (content, String::new(), String::new());
+    }
+
+    if !self.hostnames.is_empty()
+      && !url.host_str().is_some_and(|host| {
+        self
+          .hostnames
+          .iter()
+          .any(|hostname| hostname.eq_ignore_ascii_case(host))
+      })
+    {
+      self.reject(
+        HookContext::new(
+          peer_address(),
+          listener,
+          url.clone(),
+          request_id.clone(),
+          None,
+          certificate.clone(),
+          tls.clone(),
+        )
+        .with_listener_name(listener_name.clone())
+        .with_request(raw_request.clone(), received_at),
+        &Rejection::HostMismatch(url.host_str().map(ToString::to_string)),
+      );
+
+      return (
+        Response::proxy_refused("This server does not serve that host."),
+        String::new(),
+        String::new(),
+      );
+    }
+
+    let routes = self.routes.read().unwrap().clone();
+    let fixed_path = if self.fix_path {
+      routes
+        .fix_path(if url.path().is_empty() {
+          "/"
+        } else {
+          url.path()
+        })
+        .unwrap_or_else(|| url.path().to_string())
+    } else {
+      url.path().to_string()
+    };
+
+    if self.fix_path_policy == FixPathPolicy::Redirect
+      && fixed_path != url.path()
+    {
+      let mut canonical = url.clone();
+
+      canonical.set_path(&fixed_path);
+
+      return (
+        Response::permanent_redirect(canonical.as_str()),
+        String::new(),
+        String::new(),
+      );
+    }
+
+    let route = &mut routes.at(&fixed_path);
+    let parameters = route
+      .is_ok()
+      .then(|| crate::context::Parameters::lazy(routes.clone(), &fixed_path));
+
+    if let Ok(route) = route {
+      span.record_route(&route.value.info.pattern);
+    }
+
+    let peer_certificate = certificate.clone();
+    let tls_info = tls.clone();
+    let hook_context = HookContext::new(
+      peer_address(),
+      listener,
+      url.clone(),
+      request_id.clone(),
+      parameters.clone(),
+      peer_certificate.clone(),
+      tls_info.clone(),
+    )
+    .with_listener_name(listener_name.clone())
+    .with_request(raw_request.clone(), received_at);
+
+    // The same modules see both hooks of a request, even if modules are
+    // attached or detached while it is being handled.
+    let async_modules = self.async_module_snapshot();
+    let modules = self.module_snapshot();
+
+    for attached in async_modules.iter() {
+      unwind::async_module_hook(
+        "on_pre_route",
+        attached.location,
+        Some(&request_id),
+        attached
+          .module
+          .lock()
+          .await
+          .on_pre_route(hook_context.clone()),
+      )
+      .await;
+    }
+
+    for attached in modules.iter() {
+      if let Ok(mut module) = attached.module.lock() {
+        unwind::module_hook(
+          "on_pre_route",
+          attached.location,
+          Some(&request_id),
+          || {
+            module.on_pre_route(hook_context.clone());
+          },
+        );
+      }
+    }
+
+    if let Ok(mut callback) = self.pre_route_callback.lock() {
+      let location = callback.location;
+
+      unwind::catch(
+        "pre-route callback",
+        location,
+        &request_id,
+        url.path(),
+        || {
+          callback.handler.call(hook_context.clone());
+        },
+      );
+    }
+
+    let mut content = if let Some(refusal) =
+      self.authorize(&fixed_path, peer_certificate.as_ref())
+    {
+      refusal
+    } else if let Ok(ref route) = route {
+      let route_context = RouteContext::new(
+        peer_address(),
+        url.clone(),
+        request_id.clone(),
+        parameters.clone().unwrap_or_default(),
+        peer_certificate,
+        tls_info,
+      )
+      .with_routes(routes.clone())
+      .with_handler(route.value.handler_id())
+      .with_listener(listener, listener_name)
+      .with_request(raw_request, received_at)
+      .with_extensions(hook_context.extensions.clone())
+      .with_closed(closed);
+
+      let partial_context = route_context.clone();
+      let handler = route.value.handler.clone();
+      let mut middleware = self.middleware_for(url.path());
+      // A warmed response is not personalised, so it is only used for
+      // requests without a query or a certificate.
+      let warmed = (url.query().is_none() && certificate.is_none())
+        .then(|| self.warmed.get(&fixed_path))
+        .flatten();
+
+      middleware.extend(route.value.middleware.iter().cloned());
+
+      let handler = Box::pin(
+        Next::chain(
+          &middleware,
+          Next::new(move |context| {
+            Box::pin(async move {
+              match warmed {
+                Some(response) => response,
+                None => handler.call(context).await,
+              }
+            })
+          }),
+        )
+        .run(route_context),
+      );
+
+      let timeout = route
+        .value
+        .info
+        .timeout
+        .or_else(|| self.limits().handler_timeout);
+      let response = match runtime::timeout(
+        timeout,
+        unwind::CatchUnwind::new(handler),
+      )
+      .await
+      {
+        Ok(Ok(response)) => response,
+        Ok(Err(payload)) => {
+          error!(
+            "[{request_id}] route handler for {} panicked: {}",
+            url.path(),
+            unwind::panic_message(&*payload)
+          );
+
+          (*self.error_handler)
+            .lock()
+            .await
+            .call(ErrorContext::new(
+              peer_address(),
+              url.clone(),
+              request_id.clone(),
+              certificate.clone(),
+              tls.clone(),
+              ErrorKind::Panic(unwind::panic_message(&*payload)),
+            ))
+            .await
+        }
+        Err(_) => {
+          let timeout = timeout.unwrap_or_default();
+
+          warn!(
+            "[{request_id}] route handler for {} timed out after {timeout:?}",
+            url.path()
+          );
+
+          (*self.error_handler)
+            .lock()
+            .await
+            .call(ErrorContext::new(
+              peer_address(),
+              url.clone(),
+              request_id.clone(),
+              certificate.clone(),
+              tls.clone(),
+              ErrorKind::Timeout(timeout),
+            ))
+            .await
+        }
+      };
+
+      if let Some(e) = &response.error {
+        error!(
+          "[{request_id}] route handler for {} failed: {e}",
+          url.path()
+        );
+      }
+
+      let response = self
+        .customise_failure(response, |kind| {
+          ErrorContext::new(
+            peer_address(),
+            url.clone(),
+            request_id.clone(),
+            certificate.clone(),
+            tls.clone(),
+            kind,
+          )
+        })
+        .await;
+
+      (header, footer) = self.render_partials(
+        &partial_context.with_response(&response),
+        &request_id,
+      );
+
+      response
+    } else if let Some(not_found_handler) = &self.not_found_handler {
+      not_found_handler
+        .lock()
+        .await
+        .call(NotFoundContext::new(
+          peer_address(),
+          url.clone(),
+          request_id.clone(),
+          peer_certificate,
+          tls_info,
+        ))
+        .await
+    } else {
+      (*self.error_handler)
+        .lock()
+        .await
+        .call(ErrorContext::new(
+          peer_address(),
+          url.clone(),
+          request_id.clone(),
+          peer_certificate,
+          tls_info,
+          ErrorKind::RouteMiss,
+        ))
+        .await
+    };
+
+    #[cfg(feature = "templates")]
+    if let Err(e) = self.render_template(&mut content) {
+      error!(
+        "[{request_id}] could not render the template for {}: {e}",
+        url.path()
+      );
+
+      content = (*self.error_handler)
+        .lock()
+        .await
+        .call(ErrorContext::new(
+          peer_address(),
+          url.clone(),
+          request_id.clone(),
+          certificate.clone(),
+          tls.clone(),
+          ErrorKind::Handler(Arc::new(e)),
+        ))
+        .await;
+    }
+
+    span.record_response(content.status, started_at.elapsed());
+
+    if let Ok(route) = route {
+      self.stats.record(
+        &route.value.info.pattern,
+        content.status,
+        started_at.elapsed(),
+      );
+    }
+
+    let hook_context = HookContext {
+      elapsed: Some(started_at.elapsed()),
+      status: Some(content.status),
+      bytes: Some(content.content_bytes().len()),
+      ..hook_context
+    };
+
+    for attached in async_modules.iter() {
+      unwind::async_module_hook(
+        "on_post_route",
+        attached.location,
+        Some(&request_id),
+        attached
+          .module
+          .lock()
+          .await
+          .on_post_route(hook_context.clone()),
+      )
+      .await;
+    }
+
+    for attached in modules.iter() {
+      if let Ok(mut module) = attached.module.lock() {
+        unwind::module_hook(
+          "on_post_route",
+          attached.location,
+          Some(&request_id),
+          || {
+            module.on_post_route(hook_context.clone());
+          },
+        );
+      }
+    }
+
+    if let Ok(mut callback) = self.post_route_callback.lock() {
+      let location = callback.location;
+
+      unwind::catch(
+        "post-route callback",
+        location,
+        &request_id,
+        url.path(),
+        || {
+          callback.handler.call(hook_context.clone(), &mut content);
+        },
+      );
+    }
+
+    if content.status == 20
+      && !content.raw_body
+      && !content.is_streamed()
+      && content
+        .mime
+        .as_deref()
+        .unwrap_or("text/gemini")
+        .starts_with("text/gemini")
+    {
+      if let Ok(mut processors) = self.gemtext_processors.lock() {
+        if !processors.is_empty() {
+          let mut lines = crate::gemtext::parse(&format!(
+            "{header}{}\n{footer}",
+            content.content
+          ));
+
+          for processor in &mut *processors {
+            let location = processor.location;
+
+            unwind::catch(
+              "gemtext processor",
+              location,
+              &request_id,
+              url.path(),
+              || {
+                processor.handler.call(hook_context.clone(), &mut lines);
+              },
+            );
+          }
+
+          let mut document = crate::gemtext::Document::new();
+
+          for line in lines {
+            document.line(line);
+          }
+
+          content.content = document.to_string();
+
+          header.clear();
+          footer.clear();
+        }
+      }
+    }
+
+    (content, header, footer)
+  }
+
+  /// Render the content of a response produced with `Response::template`.
+  #[cfg(feature = "templates")]
+  fn render_template(
+    &self,
+    response: &mut Response,
+  ) -> Result<(), crate::template::TemplateError> {
+    let Some((name, context)) = response.template.take() else {
+      return Ok(());
+    };
+    let templates = self
+      .templates
+      .as_ref()
+      .ok_or(crate::template::TemplateError::Unconfigured)?;
+
+    response.content = templates.render(&name, &context)?;
+
+    Ok(())
+  }
+
+  /// Render the headers and footers within scope of a request, once its
+  /// handler has responded.
+  fn render_partials(
+    &self,
+    context: &RouteContext,
+    request_id: &str,
+  ) -> (String, String) {
+    let mut header = String::new();
+    let mut footer = String::new();
+
+    if let Ok(mut headers) = self.headers.lock() {
+      for (scope, partial_header) in &mut *headers {
+        if !scope.includes(context) {
+          continue;
+        }
+
+        let location = partial_header.location;
+
+        if let Some(partial) = unwind::catch(
+          "header",
+          location,
+          request_id,
+          context.url.path(),
+          || partial_header.handler.call(context.clone()),
+        ) {
+          header.push_str(&partial);
+          header.push('\n');
+        }
+      }
+    }
+
+    if let Ok(mut footers) = self.footers.lock() {
+      footer = footers
+        .iter_mut()
+        .filter(|(scope, _)| scope.includes(context))
+        .filter_map(|(_, partial_footer)| {
+          let location = partial_footer.location;
+
+          unwind::catch(
+            "footer",
+            location,
+            request_id,
+            context.url.path(),
+            || partial_footer.handler.call(context.clone()),
+          )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    }
+
+    (header, footer)
+  }
+
+  async fn write_response(
+    &self,
+    stream: &mut Stream,
+    mut content: Response,
+    header: &str,
+    footer: &str,
+  ) -> Result<(), Box<dyn Error>> {
+    let body = if content.status == 20 {
+      content.body.take().and_then(|body| body.take())
+    } else {
+      None
+    };
+    let Some((producer, receiver)) = body else {
+      return runtime::timeout(self.limits().write_timeout, async {
+        let output = self.encode_response(&content, header, Some(footer))?;
+
+        self.write_all(stream, &output).await?;
+        close(stream, self.close_mode).await
+      })
+      .await?;
+    };
+    let raw_body = content.raw_body;
+    let output = self.encode_response(&content, header, None)?;
+
+    runtime::timeout(
+      self.limits().write_timeout,
+      self.write_all(stream, &output),
+    )
+    .await??;
+
+    // A client which disconnects part way through a stream is not an error.
+    if !self.stream_body(stream, producer, &receiver).await? {
+      return Ok(());
+    }
+
+    runtime::timeout(self.limits().write_timeout, async {
+      if !raw_body {
+        self
+          .write_all(stream, format!("\n{footer}").as_bytes())
+          .await?;
+      }
+
+      close(stream, self.close_mode).await
+    })
+    .await?
+  }
+
+  /// Write the chunks of a streamed body as `producer` sends them, returning
+  /// whether the body was completed before the client disconnected.
+  async fn stream_body(
+    &self,
+    stream: &mut Stream,
+    producer: response::Production,
+    receiver: &response::BodyReceiver,
+  ) -> Result<bool, Box<dyn Error>> {
+    let writer = async {
+      loop {
+        let chunk = runtime::or(async { Some(receiver.recv().await) }, async {
+          disconnected(stream).await;
+
+          None
+        })
+        .await;
+
+        match chunk {
+          Some(Some(chunk)) =>
+            if runtime::timeout(
+              self.limits().write_timeout,
+              self.write_all(stream, chunk.as_bytes()),
+            )
+            .await?
+            .is_err()
+            {
+              return Ok(false);
+            },
+          Some(None) => return Ok(true),
+          None => return Ok(false),
+        }
+      }
+    };
+
+    runtime::or(
+      async {
+        producer.await;
+
+        // Keep writing until every chunk sent has been written.
+        std::future::pending().await
+      },
+      writer,
+    )
+    .await
+  }
+
+  /// Write `bytes` to the client, failing once it falls behind the minimum
+  /// write rate.
+  async fn write_all(
+    &self,
+    stream: &mut Stream,
+    bytes: &[u8],
+  ) -> std::io::Result<()> {
+    let Some(rate) = self.limits().min_write_rate else {
+      return stream.write_all(bytes).await;
+    };
+    let started_at = time::Instant::now();
+    let mut written = 0;
+
+    for chunk in bytes.chunks(WRITE_CHUNK_LENGTH) {
+      written += chunk.len();
+
+      #[allow(clippy::cast_precision_loss)]
+      let allowed = MIN_WRITE_RATE_GRACE
+        + time::Duration::from_secs_f64(written as f64 / rate.get() as f64);
+
+      let Ok(result) = runtime::timeout(
+        Some(allowed.saturating_sub(started_at.elapsed())),
+        stream.write_all(chunk),
+      )
+      .await
+      else {
+        warn!(
+          "closing connection to {}: client read below {rate} bytes per second",
+          stream
+            .get_ref()
+            .peer_addr()
+            .map_or_else(|_| "unknown peer".to_string(), |a| a.to_string())
+        );
+
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          "client read below the minimum write rate",
+        ));
+      };
+
+      result?;
+    }
+
+    Ok(())
+  }
+
+  /// The sanitised meta of the response header for `content`.
+  fn meta(&self, content: &Response) -> String {
+    let meta = match content.status {
+      20 => {
+        let mime = content.mime.as_deref().unwrap_or("text/gemini");
+        let character_set = content
+          .character_set
+          .as_deref()
+          .unwrap_or(&self.character_set);
+
+        if content.raw_body && !mime.starts_with("text/") {
+          mime.to_string()
+        } else if !self.minimal_meta {
+          format!(
+            "{mime}; charset={character_set}; lang={}",
+            content
+              .languages
+              .as_ref()
+              .unwrap_or(&self.languages)
+              .join(","),
+          )
+        } else if character_set.eq_ignore_ascii_case("utf-8") {
+          mime.to_string()
+        } else {
+          format!("{mime}; charset={character_set}")
+        }
+      }
+      _ => content.content.clone(),
+    };
+
+    response::sanitise_meta(&meta).into_owned()
+  }
+
+  /// Encode the header line and, for a successful response, the body of
+  /// `content`, followed by `footer` unless the body is yet to be streamed.
+  fn encode_response(
+    &self,
+    content: &Response,
+    header: &str,
+    footer: Option<&str>,
+  ) -> std::io::Result<buffers::Buffer> {
+    let mut output = self.buffers.take();
+
+    std::io::Write::write_fmt(
+      &mut *output,
+      format_args!("{} {}\r\n", content.status, self.meta(content)),
+    )?;
+
+    if content.status == 20 {
+      if content.raw_body {
+        output.extend_from_slice(content.content_bytes());
+      } else {
+        output.extend_from_slice(header.as_bytes());
+        output.extend_from_slice(content.content_bytes());
+
+        if let Some(footer) = footer {
+          output.push(b'\n');
+          output.extend_from_slice(footer.as_bytes());
+        }
+      }
+    }
+
+    Ok(output)
+  }
+
+  fn create_acceptor(&self) -> Result<(), crate::Error> {
+    let mut builder = self.tls_policy.builder()?;
+
+    self.certificate.apply_certificate(&mut builder)?;
+    self.private_key.apply_private_key(&mut builder)?;
+
+    if self.min_tls_version.is_some() {
+      builder.set_min_proto_version(self.min_tls_version)?;
+    }
+
+    if self.max_tls_version.is_some() {
+      builder.set_max_proto_version(self.max_tls_version)?;
+    }
+
+    if let Some(cipher_list) = &self.cipher_list {
+      builder.set_cipher_list(cipher_list)?;
+    }
+
+    if let Some(ciphersuites) = &self.ciphersuites {
+      builder.set_ciphersuites(ciphersuites)?;
+    }
+
+    if let Some(path) = &self.keylog_file {
+      let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|source| {
+          crate::Error::UnwritableKeylog {
+            path: path.display().to_string(),
+            source,
+          }
+        })?;
+      let file = Mutex::new(file);
+
+      warn!(
+        "writing TLS secrets to {}; anyone with this file can decrypt traffic",
+        path.display()
+      );
+      builder.set_keylog_callback(move |_, line| {
+        if let Ok(mut file) = file.lock() {
+          let _ = std::io::Write::write_all(
+            &mut *file,
+            format!("{line}\n").as_bytes(),
+          );
+        }
+      });
+    }
+
+    builder.check_private_key()?;
+    builder.set_verify_callback(ssl::SslVerifyMode::PEER, |_, _| true);
+    builder.set_session_id_context(
+      &self.session_id_context.as_ref().map_or_else(
+        || {
+          time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .to_string()
+            .into_bytes()
+        },
+        // Contexts are limited to 32 bytes, so longer ones are hashed down.
+        |context| openssl::sha::sha256(context).to_vec(),
+      ),
+    )?;
+
+    if !self.session_tickets {
+      builder.set_options(ssl::SslOptions::NO_TICKET);
+    }
+
+    *self.ssl_acceptor.write().unwrap() = Arc::new(builder.build());
+
+    Ok(())
+  }
+
+  /// The `SslAcceptor` new connections are accepted with.
+  fn acceptor(&self) -> Arc<SslAcceptor> {
+    self
+      .ssl_acceptor
+      .read()
+      .map_or_else(|e| e.into_inner().clone(), |acceptor| acceptor.clone())
+  }
+
+  /// Use a self-made `SslAcceptor`
+  ///
+  /// Every clone of a `Router` shares its `SslAcceptor`, so a running `Router`
+  /// accepts new connections with the replacement, e.g., a renewed
+  /// certificate, without restarting.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use openssl::ssl;
+  ///
+  /// windmark::router::Router::new().set_ssl_acceptor({
+  ///   let mut builder =
+  ///     ssl::SslAcceptor::mozilla_intermediate(ssl::SslMethod::tls()).unwrap();
+  ///
+  ///   builder
+  ///     .set_private_key_file("windmark_private.pem", ssl::SslFiletype::PEM)
+  ///     .unwrap();
+  ///   builder
+  ///     .set_certificate_file("windmark_public.pem", ssl::SslFiletype::PEM)
+  ///     .unwrap();
+  ///   builder.check_private_key().unwrap();
+  ///
+  ///   builder.build()
+  /// });
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the `SslAcceptor`'s lock is poisoned.
+  pub fn set_ssl_acceptor(&mut self, ssl_acceptor: SslAcceptor) -> &mut Self {
+    *self.ssl_acceptor.write().unwrap() = Arc::new(ssl_acceptor);
+    self.custom_ssl_acceptor = true;
+
+    self
+  }
+
+  /// Set how many days before the served certificate expires to start
+  /// warning about it, or `None` to never warn.
+  ///
+  /// The certificate is checked when the `Router` starts and then on every
+  /// interval set by [`Router::set_certificate_expiry_interval`]. Unless a
+  /// callback is set with [`Router::set_certificate_expiry_callback`], a
+  /// warning is logged.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_certificate_expiry_warning(Some(30));
+  /// ```
+  pub fn set_certificate_expiry_warning(
+    &mut self,
+    days: Option<u32>,
+  ) -> &mut Self {
+    self.certificate_expiry_warning = days;
+
+    self
+  }
+
+  /// Set how often the served certificate is checked for expiry, or `None` to
+  /// only check it when the `Router` starts.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_certificate_expiry_interval(Some(
+  ///   std::time::Duration::from_secs(60 * 60),
+  /// ));
+  /// ```
+  pub fn set_certificate_expiry_interval(
+    &mut self,
+    interval: Option<time::Duration>,
+  ) -> &mut Self {
+    self.certificate_expiry_interval = interval;
+
+    self
+  }
+
+  /// Set a callback to run, instead of logging a warning, when the served
+  /// certificate is within its expiry warning period.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_certificate_expiry_callback(
+  ///   |expiry: windmark::router::CertificateExpiry| {
+  ///     eprintln!("renew the certificate: it expires {}", expiry.not_after);
+  ///   },
+  /// );
+  /// ```
+  pub fn set_certificate_expiry_callback(
+    &mut self,
+    callback: impl CertificateExpiryHook + 'static,
+  ) -> &mut Self {
+    self.certificate_expiry_callback =
+      Some(Arc::new(Mutex::new(Box::new(callback))));
+
+    self
+  }
+
+  /// Set a callback to run with the address of each listener once it has been
+  /// bound, e.g., to discover the port the operating system assigned to a
+  /// listener bound to port 0.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_on_listen(
+  ///   |address: std::net::SocketAddr| {
+  ///     println!("listening on {address}");
+  ///   },
+  /// );
+  /// ```
+  pub fn set_on_listen(
+    &mut self,
+    callback: impl ListenHook + 'static,
+  ) -> &mut Self {
+    self.listen_callback = Some(Arc::new(Mutex::new(Box::new(callback))));
+
+    self
+  }
+
+  /// Set a callback which is called for each accepted connection before its
+  /// TLS handshake, and which closes the connection if it returns `false`.
+  ///
+  /// Connections refused here cost no handshake, so this is the place for IP
+  /// bans and connection rate limits.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::context::ConnectionContext;
+  ///
+  /// windmark::router::Router::new().set_connection_callback(
+  ///   |context: &ConnectionContext| {
+  ///     !context
+  ///       .peer_address
+  ///       .ip()
+  ///       .to_string()
+  ///       .starts_with("203.0.113.")
+  ///   },
+  /// );
+  /// ```
+  pub fn set_connection_callback(
+    &mut self,
+    callback: impl ConnectionHook + 'static,
+  ) -> &mut Self {
+    self.connection_callback = Some(Arc::new(Mutex::new(Box::new(callback))));
+
+    self
+  }
+
+  /// Set a callback which is called for each connection whose TLS handshake
+  /// fails or times out, e.g., to spot scanners and broken clients.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::router::{HandshakeError, HandshakeFailure};
+  ///
+  /// windmark::router::Router::new().set_handshake_error_callback(
+  ///   |failure: &HandshakeError| {
+  ///     if let HandshakeFailure::Ssl(e) = &failure.reason {
+  ///       eprintln!("{}: {:?}", failure.peer_address, e.ssl_error());
+  ///     }
+  ///   },
+  /// );
+  /// ```
+  pub fn set_handshake_error_callback(
+    &mut self,
+    callback: impl HandshakeErrorHook + 'static,
+  ) -> &mut Self {
+    self.handshake_error_callback =
+      Some(Arc::new(Mutex::new(Box::new(callback))));
+
+    self
+  }
+
+  /// Set how listeners react when accepting connections fails.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::router::{AcceptPolicy, Router};
+  ///
+  /// Router::new().set_accept_policy(AcceptPolicy {
+  ///   max_backoff: std::time::Duration::from_secs(5),
+  ///   fatal_after: Some(100),
+  ///   ..Default::default()
+  /// });
+  /// ```
+  pub fn set_accept_policy(&mut self, policy: AcceptPolicy) -> &mut Self {
+    self.accept_policy = policy;
+
+    self
+  }
+
+  /// Set a callback to run when a listener repeatedly fails to accept
+  /// connections, once it has failed as many times in a row as the accept
+  /// policy's `report_after`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_accept_error_callback(
+  ///   |failure: &windmark::router::AcceptError| {
+  ///     eprintln!(
+  ///       "{} failed {} times in a row: {}",
+  ///       failure.listener, failure.consecutive, failure.error
+  ///     );
+  ///   },
+  /// );
+  /// ```
+  pub fn set_accept_error_callback(
+    &mut self,
+    callback: impl AcceptErrorHook + 'static,
+  ) -> &mut Self {
+    self.accept_error_callback = Some(Arc::new(Mutex::new(Box::new(callback))));
+
+    self
+  }
+
+  /// Set how connections are closed once their response has been written.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::router::{CloseMode, Router};
+  ///
+  /// Router::new().set_close_mode(CloseMode::Linger);
+  /// ```
+  pub fn set_close_mode(&mut self, mode: CloseMode) -> &mut Self {
+    self.close_mode = mode;
+
+    self
+  }
+
+  /// Set the baseline protocol and cipher configuration of the built-in
+  /// `SslAcceptor`.
+  ///
+  /// This has no effect if a self-made `SslAcceptor` is used.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::router::{Router, TlsPolicy};
+  ///
+  /// Router::new().set_tls_policy(TlsPolicy::Modern);
+  /// ```
+  pub fn set_tls_policy(&mut self, tls_policy: TlsPolicy) -> &mut Self {
+    self.tls_policy = tls_policy;
+
+    self
+  }
+
+  /// Set the minimum TLS protocol version accepted from clients, or `None`
+  /// to use the minimum of the current `TlsPolicy`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_min_tls_version(Some(openssl::ssl::SslVersion::TLS1_2));
+  /// ```
+  pub fn set_min_tls_version(
+    &mut self,
+    min_tls_version: Option<SslVersion>,
+  ) -> &mut Self {
+    self.min_tls_version = min_tls_version;
+
+    self
+  }
+
+  /// Set the maximum TLS protocol version accepted from clients, or `None`
+  /// to use the maximum supported by OpenSSL.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_max_tls_version(Some(openssl::ssl::SslVersion::TLS1_2));
+  /// ```
+  pub fn set_max_tls_version(
+    &mut self,
+    max_tls_version: Option<SslVersion>,
+  ) -> &mut Self {
+    self.max_tls_version = max_tls_version;
+
+    self
+  }
+
+  /// Override the TLS 1.2 and below cipher list of the current `TlsPolicy`,
+  /// in OpenSSL's cipher list format.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_cipher_list(
+  ///   "ECDHE-ECDSA-AES256-GCM-SHA384:ECDHE-RSA-AES256-GCM-SHA384",
+  /// );
+  /// ```
+  pub fn set_cipher_list(
+    &mut self,
+    cipher_list: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    self.cipher_list = Some(cipher_list.into());
+
+    self
+  }
+
+  /// Override the TLS 1.3 ciphersuites of the current `TlsPolicy`, in
+  /// OpenSSL's ciphersuites format.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_ciphersuites("TLS_AES_256_GCM_SHA384:TLS_CHACHA20_POLY1305_SHA256");
+  /// ```
+  pub fn set_ciphersuites(
+    &mut self,
+    ciphersuites: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    self.ciphersuites = Some(ciphersuites.into());
+
+    self
+  }
+
+  /// Set a stable context for TLS sessions to be bound to.
+  ///
+  /// A session is only resumed within the context it began in, including
+  /// sessions which carry a client certificate. By default, the context is
+  /// derived from the time the TLS acceptor was built, so it differs between
+  /// restarts and between instances behind the same hostname.
+  ///
+  /// This has no effect if a self-made `SslAcceptor` is used.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_session_id_context("fuwn.me"); 
+  /// ```
+  pub fn set_session_id_context(
+    &mut self,
+    context: impl Into<Vec<u8>>,
+  ) -> &mut Self {
+    self.session_id_context = Some(context.into());
+
+    self
+  }
+
+  /// Enable or disable stateless session resumption with session tickets.
+  ///
+  /// Tickets are encrypted with keys which OpenSSL generates whenever the
+  /// TLS acceptor is built, so a ticket is only accepted by the instance
+  /// which issued it, and not after a restart or a reload of the
+  /// certificate. Instances which need to share ticket keys can set their own
+  /// acceptor with [`Router::set_ssl_acceptor`]. Tickets are enabled by
+  /// default.
+  ///
+  /// This has no effect if a self-made `SslAcceptor` is used.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_session_tickets(false); 
+  /// ```
+  pub fn set_session_tickets(&mut self, enabled: bool) -> &mut Self {
+    self.session_tickets = enabled;
+
+    self
+  }
+
+  /// Append the secrets of every TLS session to `path`, in the
+  /// `SSLKEYLOGFILE` format, so that captured traffic can be decrypted by,
+  /// e.g., Wireshark when debugging a client.
+  ///
+  /// Anyone who can read the file can decrypt the traffic of the sessions it
+  /// holds, so this should never be left enabled in production. `None`
+  /// disables the keylog, which is the default.
+  ///
+  /// This has no effect if a self-made `SslAcceptor` is used.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_keylog_file(std::env::var_os("SSLKEYLOGFILE"));
+  /// ```
+  pub fn set_keylog_file(
+    &mut self,
+    path: Option<impl Into<std::path::PathBuf>>,
+  ) -> &mut Self {
+    self.keylog_file = path.map(Into::into);
+
+    self
+  }
+
+  /// Enabled the default logger (the
+  /// [`pretty_env_logger`](https://crates.io/crates/pretty_env_logger) and
+  /// [`log`](https://crates.io/crates/log) crates).
+  ///
+  /// The logger is configured from the log level set on the `Router`, not
+  /// from the `RUST_LOG` environment variable, and is only installed if no
+  /// other logger has been.
+  #[cfg(feature = "logger")]
+  pub fn enable_default_logger(&mut self, enable: bool) -> &mut Self {
+    self.default_logger = enable;
+
+    self
+  }
+
+  /// Log one line for each request at the info level, with the requested
+  /// path, the status and meta of the response, the length of its body, and
+  /// how long it took to answer. Enabled by default.
+  ///
+  /// The length of a streamed body does not include its streamed chunks.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_request_logging(false); 
+  /// ```
+  #[cfg(feature = "logger")]
+  pub fn set_request_logging(&mut self, enable: bool) -> &mut Self {
+    self.log_requests = enable;
+
+    self
+  }
+
+  /// Set the default logger's log level.
+  ///
+  /// If you enable Windmark's default logger with `enable_default_logger`,
+  /// Windmark will only log, logs from itself. By setting a log level with
+  /// this method, you are overriding the default log level, so you must choose
+  /// to enable logs from Windmark with the `log_windmark` parameter.
+  ///
+  /// Log level "language" is detailed
+  /// [here](https://docs.rs/env_logger/0.9.0/env_logger/#enabling-logging).
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .enable_default_logger(true)
+  ///   .set_log_level("your_crate_name=trace", true);
+  /// // If you would only like to log, logs from your crate:
+  /// // .set_log_level("your_crate_name=trace", false);
+  /// ```
+  #[cfg(feature = "logger")]
+  pub fn set_log_level(
+    &mut self,
+    log_level: impl Into<String> + AsRef<str>,
+    log_windmark: bool,
+  ) -> &mut Self {
+    self.log_filter = format!(
+      "{}{}",
+      if log_windmark { "windmark," } else { "" },
+      log_level.into()
+    );
+
+    self
+  }
+
+  /// Set the default logger's log level for every crate, including Windmark.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .enable_default_logger(true)
+  ///   .set_log_level_filter(log::LevelFilter::Info);
+  /// ```
+  #[cfg(feature = "logger")]
+  pub fn set_log_level_filter(
+    &mut self,
+    level_filter: log::LevelFilter,
+  ) -> &mut Self {
+    self.log_filter = level_filter.to_string().to_lowercase();
+
+    self
+  }
+
+  /// Set a callback to run before a client response is delivered
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use log::info;
+  ///
+  /// windmark::router::Router::new().set_pre_route_callback(
+  ///   |context: windmark::context::HookContext| {
+  ///     info!(
+  ///       "accepted connection from {}",
+  ///       context.peer_address.unwrap().ip(),
+  ///     )
+  ///   },
+  /// );
+  /// ```
+  #[track_caller]
+  pub fn set_pre_route_callback(
+    &mut self,
+    callback: impl PreRouteHook + 'static,
+  ) -> &mut Self {
+    self.pre_route_callback =
+      Arc::new(Mutex::new(Registered::new(Box::new(callback))));
+
+    self
+  }
+
+  /// Set a callback to run after a client response is delivered
+  ///
+  /// The `elapsed` and `status` fields of the `HookContext` passed to the
+  /// callback are populated with the timing and status of the response.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use log::info;
+  ///
+  /// windmark::router::Router::new().set_post_route_callback(
+  ///   |context: windmark::context::HookContext,
+  ///    _content: &mut windmark::response::Response| {
+  ///     info!(
+  ///       "closed connection from {}",
+  ///       context.peer_address.unwrap().ip(),
+  ///     )
+  ///   },
+  /// );
+  /// ```
+  #[track_caller]
+  pub fn set_post_route_callback(
+    &mut self,
+    callback: impl PostRouteHook + 'static,
+  ) -> &mut Self {
+    self.post_route_callback =
+      Arc::new(Mutex::new(Registered::new(Box::new(callback))));
+
+    self
+  }
+
+  /// Set a callback which is called with the reason for each request rejected
+  /// before it could be routed, e.g., for a malformed URL or a host which is
+  /// not served.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::context::{HookContext, Rejection};
+  ///
+  /// windmark::router::Router::new().set_rejection_callback(
+  ///   |context: HookContext, rejection: &Rejection| {
+  ///     eprintln!("rejected {}: {}", context.request_id, rejection.code());
+  ///   },
+  /// );
+  /// ```
+  pub fn set_rejection_callback(
+    &mut self,
+    callback: impl RejectionHook + 'static,
+  ) -> &mut Self {
+    self.rejection_callback = Some(Arc::new(Mutex::new(Box::new(callback))));
+
+    self
+  }
+
+  /// Attach a stateless module to a `Router`.
+  ///
+  /// A module is an extension or middleware to a `Router`. Modules get full
+  /// access to the `Router`, but can be extended by a third party.
+  ///
+  /// # Examples
+  ///
+  /// ## Integrated Module
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// windmark::router::Router::new().attach_stateless(|r| {
+  ///   r.mount(
+  ///     "/module",
+  ///     Box::new(|_| Response::success("This is a module!")),
+  ///   );
+  ///   r.set_error_handler(Box::new(|_| {
+  ///     Response::not_found(
+  ///       "This error handler has been implemented by a module!",
+  ///     )
+  ///   }));
+  /// });
+  /// ```
+  ///
+  /// ## External Module
+  ///
+  /// ```rust
+  /// use windmark::response::Response;
+  ///
+  /// mod windmark_example {
+  ///   pub fn module(router: &mut windmark::router::Router) {
+  ///     router.mount(
+  ///       "/module",
+  ///       Box::new(|_| {
+  ///         windmark::response::Response::success("This is a module!")
+  ///       }),
+  ///     );
+  ///   }
+  /// }
+  ///
+  /// windmark::router::Router::new().attach_stateless(windmark_example::module);
+  /// ```
+  pub fn attach_stateless<F>(&mut self, mut module: F) -> &mut Self
+  where F: FnMut(&mut Self) {
+    module(self);
 
-      if request.contains("\r\n") {
-        break;
-      }
-    }
+    self
+  }
 
-    let fixed_path = if self.fix_path {
-      self
-        .routes
-        .fix_path(if url.path().is_empty() {
-          "/"
-        } else {
-          url.path()
-        })
-        .unwrap_or_else(|| url.path().to_string())
-    } else {
-      url.path().to_string()
-    };
-    let route = &mut self.routes.at(&fixed_path);
-    let peer_certificate = stream.ssl().peer_certificate();
-    let hook_context = HookContext::new(
-      stream.get_ref().peer_addr(),
-      url.clone(),
-      route
-        .as_ref()
-        .map_or(None, |route| Some(route.params.clone())),
-      peer_certificate.clone(),
-    );
+  /// Attach a stateful module to a `Router`; with async support
+  ///
+  /// Like a stateless module is an extension or middleware to a `Router`.
+  /// Modules get full access to the `Router` and can be extended by a third
+  /// party, but also, can create hooks will be executed through various parts
+  /// of a routes' lifecycle. Stateful modules also have state, so variables can
+  /// be stored for further access.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the stateful module cannot be attached.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use log::info;
+  /// use windmark::{context::HookContext, router::Router};
+  ///
+  /// #[derive(Default)]
+  /// struct Clicker {
+  ///   clicks: isize,
+  /// }
+  ///
+  /// #[async_trait::async_trait]
+  /// impl windmark::module::AsyncModule for Clicker {
+  ///   async fn on_attach(&mut self, _: &mut Router) {
+  ///     info!("clicker has been attached!");
+  ///   }
+  ///
+  ///   async fn on_pre_route(
+  ///     &mut self,
+  ///     context: HookContext,
+  ///   ) -> windmark::module::ModuleResult {
+  ///     self.clicks += 1;
+  ///
+  ///     info!(
+  ///       "clicker has been called pre-route on {} with {} clicks!",
+  ///       context.url.path(),
+  ///       self.clicks
+  ///     );
+  ///
+  ///     Ok(())
+  ///   }
+  ///
+  ///   async fn on_post_route(
+  ///     &mut self,
+  ///     context: HookContext,
+  ///   ) -> windmark::module::ModuleResult {
+  ///     info!(
+  ///       "clicker has been called post-route on {} with {} clicks!",
+  ///       context.url.path(),
+  ///       self.clicks
+  ///     );
+  ///
+  ///     Ok(())
+  ///   }
+  /// }
+  ///
+  /// Router::new().attach_async(Clicker::default());
+  /// ```
+  #[track_caller]
+  pub fn attach_async(
+    &mut self,
+    module: impl AsyncModule + 'static,
+  ) -> &mut Self {
+    self.attach_async_module(None, module)
+  }
 
-    for module in &mut *self.async_modules.lock().await {
-      module.on_pre_route(hook_context.clone()).await;
-    }
+  /// Attach an asynchronous stateful module to a `Router` under a `name`,
+  /// which can later be passed to [`Router::detach`].
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// struct Clicker;
+  ///
+  /// #[async_trait::async_trait]
+  /// impl windmark::module::AsyncModule for Clicker {}
+  ///
+  /// async fn attach(router: &mut windmark::router::Router) {
+  ///   router.attach_async_named("clicker", Clicker);
+  /// }
+  /// ```
+  #[track_caller]
+  pub fn attach_async_named(
+    &mut self,
+    name: impl Into<String> + AsRef<str>,
+    module: impl AsyncModule + 'static,
+  ) -> &mut Self {
+    self.attach_async_module(Some(name.into()), module)
+  }
 
-    if let Ok(mut modules) = self.modules.lock() {
-      for module in &mut *modules {
-        module.on_pre_route(hook_context.clone());
-      }
-    }
+  #[track_caller]
+  fn attach_async_module(
+    &mut self,
+    name: Option<String>,
+    mut module: impl AsyncModule + 'static,
+  ) -> &mut Self {
+    let location = std::panic::Location::caller();
 
-    if let Ok(mut callback) = self.pre_route_callback.lock() {
-      callback.call(hook_context.clone());
-    }
+    block!({
+      module.on_attach(self).await;
 
-    let mut content = if let Ok(ref route) = route {
-      let footers_length = (*self.footers.lock().unwrap()).len();
-      let route_context = RouteContext::new(
-        stream.get_ref().peer_addr(),
-        url.clone(),
-        &route.params,
-        peer_certificate,
+      let module = Arc::new(AsyncMutex::new(module));
+
+      Arc::make_mut(&mut self.async_modules.write().unwrap()).push(
+        modules::Attached {
+          name,
+          instance: module.clone(),
+          module: module.clone(),
+          location,
+        },
       );
 
-      if let Ok(mut headers) = self.headers.lock() {
-        for partial_header in &mut *headers {
-          header.push_str(&format!(
-            "{}\n",
-            partial_header.call(route_context.clone()),
-          ));
-        }
+      if let Some(&address) = self.listening_addresses().first() {
+        unwind::async_module_hook(
+          "on_start",
+          location,
+          None,
+          module.lock().await.on_start(address),
+        )
+        .await;
       }
+    });
 
-      for (i, partial_footer) in {
-        #[allow(clippy::needless_borrow, clippy::explicit_auto_deref)]
-        (&mut *self.footers.lock().unwrap()).iter_mut().enumerate()
-      } {
-        footer.push_str(&format!(
-          "{}{}",
-          partial_footer.call(route_context.clone()),
-          if footers_length > 1 && i != footers_length - 1 {
-            "\n"
-          } else {
-            ""
-          },
-        ));
-      }
+    self
+  }
 
-      let mut lock = (*route.value).lock().await;
-      let handler = lock.call(route_context);
+  /// Attach a stateful module to a `Router`.
+  ///
+  /// Like a stateless module is an extension or middleware to a `Router`.
+  /// Modules get full access to the `Router` and can be extended by a third
+  /// party, but also, can create hooks will be executed through various parts
+  /// of a routes' lifecycle. Stateful modules also have state, so variables can
+  /// be stored for further access.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the stateful module cannot be attached.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use log::info;
+  /// use windmark::{context::HookContext, response::Response, router::Router};
+  ///
+  /// #[derive(Default)]
+  /// struct Clicker {
+  ///   clicks: isize,
+  /// }
+  ///
+  /// impl windmark::module::Module for Clicker {
+  ///   fn on_attach(&mut self, _: &mut Router) {
+  ///     info!("clicker has been attached!");
+  ///   }
+  ///
+  ///   fn on_pre_route(&mut self, context: HookContext) {
+  ///     self.clicks += 1;
+  ///
+  ///     info!(
+  ///       "clicker has been called pre-route on {} with {} clicks!",
+  ///       context.url.path(),
+  ///       self.clicks
+  ///     );
+  ///   }
+  ///
+  ///   fn on_post_route(&mut self, context: HookContext) {
+  ///     info!(
+  ///       "clicker has been called post-route on {} with {} clicks!",
+  ///       context.url.path(),
+  ///       self.clicks
+  ///     );
+  ///   }
+  /// }
+  ///
+  /// Router::new().attach(Clicker::default());
+  /// ```
+  #[track_caller]
+  pub fn attach(&mut self, module: impl Module + 'static + Send) -> &mut Self {
+    self.attach_module(None, module)
+  }
 
-      handler.await
-    } else {
-      (*self.error_handler)
-        .lock()
-        .await
-        .call(ErrorContext::new(
-          stream.get_ref().peer_addr(),
-          url.clone(),
-          peer_certificate,
-        ))
-        .await
-    };
+  /// Attach a stateful module to a `Router` under a `name`, which can later
+  /// be passed to [`Router::detach`].
+  ///
+  /// # Panics
+  ///
+  /// May panic if the stateful module cannot be attached.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// struct Clicker;
+  ///
+  /// impl windmark::module::Module for Clicker {}
+  ///
+  /// windmark::router::Router::new().attach_named("clicker", Clicker);
+  /// ```
+  #[track_caller]
+  pub fn attach_named(
+    &mut self,
+    name: impl Into<String> + AsRef<str>,
+    module: impl Module + 'static + Send,
+  ) -> &mut Self {
+    self.attach_module(Some(name.into()), module)
+  }
 
-    for module in &mut *self.async_modules.lock().await {
-      module.on_post_route(hook_context.clone()).await;
-    }
+  #[track_caller]
+  fn attach_module(
+    &mut self,
+    name: Option<String>,
+    mut module: impl Module + 'static + Send,
+  ) -> &mut Self {
+    let location = std::panic::Location::caller();
+
+    module.on_attach(self);
 
-    if let Ok(mut modules) = self.modules.lock() {
-      for module in &mut *modules {
-        module.on_post_route(hook_context.clone());
+    let module = Arc::new(Mutex::new(module));
+
+    Arc::make_mut(&mut self.modules.write().unwrap()).push(modules::Attached {
+      name,
+      instance: module.clone(),
+      module: module.clone(),
+      location,
+    });
+
+    if let Some(&address) = self.listening_addresses().first() {
+      if let Ok(mut module) = module.lock() {
+        unwind::module_hook("on_start", location, None, || {
+          module.on_start(address);
+        });
       }
     }
 
-    if let Ok(mut callback) = self.post_route_callback.lock() {
-      callback.call(hook_context.clone(), &mut content);
-    }
-
-    stream
-      .write_all(
-        format!(
-          "{}{}\r\n{}",
-          if content.status == 21
-            || content.status == 22
-            || content.status == 23
-          {
-            20
-          } else {
-            content.status
-          },
-          match content.status {
-            20 =>
-              format!(
-                " {}; charset={}; lang={}",
-                content.mime.unwrap_or_else(|| "text/gemini".to_string()),
-                content
-                  .character_set
-                  .unwrap_or_else(|| self.character_set.clone()),
-                content
-                  .languages
-                  .unwrap_or_else(|| self.languages.clone())
-                  .join(","),
-              ),
-            21 => content.mime.unwrap_or_default(),
-            #[cfg(feature = "auto-deduce-mime")]
-            22 => format!(" {}", content.mime.unwrap_or_default()),
-            _ => format!(" {}", content.content),
-          },
-          match content.status {
-            20 => format!("{header}{}\n{footer}", content.content),
-            21 | 22 => content.content,
-            _ => String::new(),
-          }
-        )
-        .as_bytes(),
-      )
-      .await?;
+    self
+  }
+
+  /// Look up the first attached stateful module of type `T`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// #[derive(Default)]
+  /// struct Clicker {
+  ///   clicks: usize,
+  /// }
+  ///
+  /// impl windmark::module::Module for Clicker {}
+  ///
+  /// let mut router = windmark::router::Router::new();
+  ///
+  /// router.attach(Clicker::default());
+  ///
+  /// assert_eq!(
+  ///   router.module::<Clicker>().unwrap().lock().unwrap().clicks,
+  ///   0
+  /// );
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the module list's lock is poisoned.
+  #[must_use]
+  pub fn module<T: Module + Send + 'static>(&self) -> Option<Arc<Mutex<T>>> {
+    self
+      .modules
+      .read()
+      .unwrap()
+      .iter()
+      .find_map(modules::Attached::downcast)
+  }
+
+  /// Look up the first attached asynchronous stateful module of type `T`.
+  ///
+  /// The module is guarded by the async mutex of the enabled runtime.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the module list's lock is poisoned.
+  #[must_use]
+  pub fn async_module<T: AsyncModule + 'static>(
+    &self,
+  ) -> Option<Arc<AsyncMutex<T>>> {
+    self
+      .async_modules
+      .read()
+      .unwrap()
+      .iter()
+      .find_map(modules::Attached::downcast)
+  }
 
-    #[cfg(feature = "tokio")]
-    stream.shutdown().await?;
-    #[cfg(feature = "async-std")]
-    stream.get_mut().shutdown(std::net::Shutdown::Both)?;
+  /// The attached stateful modules.
+  ///
+  /// Hooks are dispatched from a snapshot, so concurrent requests only share
+  /// the module list's read lock for as long as it takes to clone an `Arc`,
+  /// and hooks may attach or detach modules without deadlocking.
+  fn module_snapshot(&self) -> Arc<modules::Modules> {
+    self
+      .modules
+      .read()
+      .map_or_else(|_| Arc::default(), |modules| modules.clone())
+  }
 
-    Ok(())
+  /// The attached asynchronous modules, dispatched from a snapshot like
+  /// [`Router::module_snapshot`].
+  fn async_module_snapshot(&self) -> Arc<modules::AsyncModules> {
+    self
+      .async_modules
+      .read()
+      .map_or_else(|_| Arc::default(), |modules| modules.clone())
   }
 
-  fn create_acceptor(&mut self) -> Result<(), Box<dyn Error>> {
-    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+  /// Detach every module, stateful or asynchronous, which was attached under
+  /// `name`, returning whether any were.
+  ///
+  /// Detached modules receive no further hooks. Every clone of a `Router`
+  /// shares its modules, so modules may be attached to and detached from a
+  /// clone while the server is running, e.g., to enable diagnostics on
+  /// demand; modules detached from a running `Router` have their
+  /// `on_shutdown` hook called.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// struct Clicker;
+  ///
+  /// impl windmark::module::Module for Clicker {}
+  ///
+  /// let mut router = windmark::router::Router::new();
+  ///
+  /// router.attach_named("clicker", Clicker);
+  ///
+  /// assert!(router.detach("clicker"));
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// May panic if the module list's lock is poisoned.
+  pub fn detach(&mut self, name: impl AsRef<str>) -> bool {
+    let name = name.as_ref();
+    let detached =
+      modules::detach(Arc::make_mut(&mut self.modules.write().unwrap()), name);
+    let async_detached = modules::detach(
+      Arc::make_mut(&mut self.async_modules.write().unwrap()),
+      name,
+    );
+    let any_detached = !detached.is_empty() || !async_detached.is_empty();
 
-    if self.certificate_content.is_some() {
-      builder.set_certificate(
-        openssl::x509::X509::from_pem(
-          self.certificate_content.clone().unwrap().as_bytes(),
-        )?
-        .as_ref(),
-      )?;
-    } else {
-      builder.set_certificate_file(
-        &self.certificate_file_name,
-        ssl::SslFiletype::PEM,
-      )?;
-    }
-
-    if self.private_key_content.is_some() {
-      builder.set_private_key(
-        openssl::pkey::PKey::private_key_from_pem(
-          self.private_key_content.clone().unwrap().as_bytes(),
-        )?
-        .as_ref(),
-      )?;
-    } else {
-      builder.set_private_key_file(
-        &self.private_key_file_name,
-        ssl::SslFiletype::PEM,
-      )?;
+    if self.listening_addresses().is_empty() {
+      return any_detached;
     }
 
-    builder.check_private_key()?;
-    builder.set_verify_callback(ssl::SslVerifyMode::PEER, |_, _| true);
-    builder.set_session_id_context(
-      time::SystemTime::now()
-        .duration_since(time::UNIX_EPOCH)?
-        .as_secs()
-        .to_string()
-        .as_bytes(),
-    )?;
+    for attached in detached {
+      if let Ok(mut module) = attached.module.lock() {
+        unwind::module_hook("on_shutdown", attached.location, None, || {
+          module.on_shutdown();
+        });
+      }
+    }
 
-    self.ssl_acceptor = Arc::new(builder.build());
+    if !async_detached.is_empty() {
+      block!({
+        for attached in async_detached {
+          unwind::async_module_hook(
+            "on_shutdown",
+            attached.location,
+            None,
+            attached.module.lock().await.on_shutdown(),
+          )
+          .await;
+        }
+      });
+    }
 
-    Ok(())
+    any_detached
   }
 
-  /// Use a self-made `SslAcceptor`
+  /// The addresses the `Router` is currently listening on, or none if it is
+  /// not running.
+  fn listening_addresses(&self) -> Vec<SocketAddr> {
+    self
+      .listening
+      .lock()
+      .map(|listening| listening.clone())
+      .unwrap_or_default()
+  }
+
+  /// Specify a custom character set.
+  ///
+  /// Will be over-ridden if a character set is specified in a [`Response`].
+  ///
+  /// Defaults to `"utf-8"`.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// use openssl::ssl;
+  /// windmark::router::Router::new().set_character_set("utf-8"); 
+  /// ```
+  pub fn set_character_set(
+    &mut self,
+    character_set: impl Into<String> + AsRef<str>,
+  ) -> &mut Self {
+    self.character_set = character_set.into();
+
+    self
+  }
+
+  /// Specify a custom language.
   ///
-  /// windmark::router::Router::new().set_ssl_acceptor({
-  ///   let mut builder =
-  ///     ssl::SslAcceptor::mozilla_intermediate(ssl::SslMethod::tls()).unwrap();
+  /// Will be over-ridden if a language is specified in a [`Response`].
   ///
-  ///   builder
-  ///     .set_private_key_file("windmark_private.pem", ssl::SslFiletype::PEM)
-  ///     .unwrap();
-  ///   builder
-  ///     .set_certificate_file("windmark_public.pem", ssl::SslFiletype::PEM)
-  ///     .unwrap();
-  ///   builder.check_private_key().unwrap();
+  /// Defaults to `"en"`.
   ///
-  ///   builder.build()
-  /// });
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_languages(["en"]); 
   /// ```
-  pub fn set_ssl_acceptor(&mut self, ssl_acceptor: SslAcceptor) -> &mut Self {
-    self.ssl_acceptor = Arc::new(ssl_acceptor);
+  pub fn set_languages<S>(&mut self, language: impl AsRef<[S]>) -> &mut Self
+  where S: Into<String> + AsRef<str> {
+    self.languages = language
+      .as_ref()
+      .iter()
+      .map(|s| s.as_ref().to_string())
+      .collect::<Vec<String>>();
 
     self
   }
 
-  /// Enabled the default logger (the
-  /// [`pretty_env_logger`](https://crates.io/crates/pretty_env_logger) and
-  /// [`log`](https://crates.io/crates/log) crates).
-  #[cfg(feature = "logger")]
-  pub fn enable_default_logger(&mut self, enable: bool) -> &mut Self {
-    self.default_logger = enable;
-
-    std::env::set_var("RUST_LOG", "windmark=trace");
+  /// Specify a custom port.
+  ///
+  /// Defaults to `1965`. Port 0 lets the operating system assign a free port,
+  /// which can be discovered with [`Router::set_on_listen`] or the
+  /// [`ServerHandle`] returned by [`Router::start`].
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::router::Router::new().set_port(1965); 
+  /// ```
+  pub fn set_port(&mut self, port: i32) -> &mut Self {
+    self.port = port;
 
     self
   }
 
-  /// Set the default logger's log level.
-  ///
-  /// If you enable Windmark's default logger with `enable_default_logger`,
-  /// Windmark will only log, logs from itself. By setting a log level with
-  /// this method, you are overriding the default log level, so you must choose
-  /// to enable logs from Windmark with the `log_windmark` parameter.
+  /// Add an address for the `Router` to listen on, such as `"[::]:1965"` or
+  /// `"127.0.0.1:1966"`.
   ///
-  /// Log level "language" is detailed
-  /// [here](https://docs.rs/env_logger/0.9.0/env_logger/#enabling-logging).
+  /// The `Router` accepts connections on every added address concurrently. If
+  /// no listeners are added, the `Router` listens on `0.0.0.0` using the port
+  /// set with [`Router::set_port`].
   ///
   /// # Examples
   ///
   /// ```rust
   /// windmark::router::Router::new()
-  ///   .enable_default_logger(true)
-  ///   .set_log_level("your_crate_name=trace", true);
-  /// // If you would only like to log, logs from your crate:
-  /// // .set_log_level("your_crate_name=trace", false);
+  ///   .add_listener("0.0.0.0:1965")
+  ///   .add_listener("127.0.0.1:1966");
   /// ```
-  #[cfg(feature = "logger")]
-  pub fn set_log_level(
+  pub fn add_listener(
     &mut self,
-    log_level: impl Into<String> + AsRef<str>,
-    log_windmark: bool,
+    address: impl Into<String> + AsRef<str>,
   ) -> &mut Self {
-    std::env::set_var(
-      "RUST_LOG",
-      format!(
-        "{}{}",
-        if log_windmark { "windmark," } else { "" },
-        log_level.into()
-      ),
-    );
+    self.listeners.push((address.into(), None));
 
     self
   }
 
-  /// Set a callback to run before a client response is delivered
+  /// Add an address for the `Router` to listen on, like
+  /// [`Router::add_listener`], under a name which the contexts of requests
+  /// arriving on it carry, so that hooks can tell listeners apart.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// use log::info;
+  /// use windmark::context::HookContext;
   ///
-  /// windmark::router::Router::new().set_pre_route_callback(
-  ///   |context: windmark::context::HookContext| {
-  ///     info!(
-  ///       "accepted connection from {}",
-  ///       context.peer_address.unwrap().ip(),
-  ///     )
-  ///   },
-  /// );
+  /// windmark::router::Router::new()
+  ///   .add_named_listener("public", "0.0.0.0:1965")
+  ///   .add_named_listener("internal", "127.0.0.1:1966")
+  ///   .set_pre_route_callback(|context: HookContext| {
+  ///     if context.listener_name.as_deref() == Some("internal") {
+  ///       println!("internal request for {}", context.url.path());
+  ///     }
+  ///   });
   /// ```
-  pub fn set_pre_route_callback(
+  pub fn add_named_listener(
     &mut self,
-    callback: impl PreRouteHook + 'static,
+    name: impl Into<String> + AsRef<str>,
+    address: impl Into<String> + AsRef<str>,
   ) -> &mut Self {
-    self.pre_route_callback = Arc::new(Mutex::new(Box::new(callback)));
+    self.listeners.push((address.into(), Some(name.into())));
 
     self
   }
 
-  /// Set a callback to run after a client response is delivered
+  /// Add a hostname which requests are accepted for.
+  ///
+  /// If any hostnames are added, requests for other hosts are refused with a
+  /// status of 53 (proxy request refused).
   ///
   /// # Examples
   ///
   /// ```rust
-  /// use log::info;
-  ///
-  /// windmark::router::Router::new().set_post_route_callback(
-  ///   |context: windmark::context::HookContext,
-  ///    _content: &mut windmark::response::Response| {
-  ///     info!(
-  ///       "closed connection from {}",
-  ///       context.peer_address.unwrap().ip(),
-  ///     )
-  ///   },
-  /// );
+  /// windmark::router::Router::new()
+  ///   .add_hostname("example.com")
+  ///   .add_hostname("www.example.com");
   /// ```
-  pub fn set_post_route_callback(
+  pub fn add_hostname(
     &mut self,
-    callback: impl PostRouteHook + 'static,
+    hostname: impl Into<String> + AsRef<str>,
   ) -> &mut Self {
-    self.post_route_callback = Arc::new(Mutex::new(Box::new(callback)));
+    self.hostnames.push(hostname.into());
 
     self
   }
 
-  /// Attach a stateless module to a `Router`.
+  /// Set the options applied to listening and accepted sockets.
   ///
-  /// A module is an extension or middleware to a `Router`. Modules get full
-  /// access to the `Router`, but can be extended by a third party.
+  /// The backlog only applies to listeners bound by the `Router`, not to
+  /// listeners passed to [`Router::run_with_listener`].
   ///
   /// # Examples
   ///
-  /// ## Integrated Module
-  ///
   /// ```rust
-  /// use windmark::response::Response;
+  /// use windmark::router::{Router, SocketOptions};
   ///
-  /// windmark::router::Router::new().attach_stateless(|r| {
-  ///   r.mount(
-  ///     "/module",
-  ///     Box::new(|_| Response::success("This is a module!")),
-  ///   );
-  ///   r.set_error_handler(Box::new(|_| {
-  ///     Response::not_found(
-  ///       "This error handler has been implemented by a module!",
-  ///     )
-  ///   }));
+  /// Router::new().set_socket_options(SocketOptions {
+  ///   nodelay: true,
+  ///   keepalive: Some(std::time::Duration::from_secs(60)),
+  ///   ..Default::default()
   /// });
   /// ```
+  pub fn set_socket_options(
+    &mut self,
+    socket_options: SocketOptions,
+  ) -> &mut Self {
+    self.socket_options = socket_options;
+
+    self
+  }
+
+  /// Performs a case-insensitive lookup of routes, using the case corrected
+  /// path if successful. Missing/ extra trailing slashes are also corrected.
   ///
-  /// ## External Module
+  /// # Examples
   ///
   /// ```rust
-  /// use windmark::response::Response;
-  ///
-  /// mod windmark_example {
-  ///   pub fn module(router: &mut windmark::router::Router) {
-  ///     router.mount(
-  ///       "/module",
-  ///       Box::new(|_| {
-  ///         windmark::response::Response::success("This is a module!")
-  ///       }),
-  ///     );
-  ///   }
-  /// }
-  ///
-  /// windmark::router::Router::new().attach_stateless(windmark_example::module);
+  /// windmark::router::Router::new().set_fix_path(true); 
   /// ```
-  pub fn attach_stateless<F>(&mut self, mut module: F) -> &mut Self
-  where F: FnMut(&mut Self) {
-    module(self);
+  pub fn set_fix_path(&mut self, fix_path: bool) -> &mut Self {
+    self.fix_path = fix_path;
 
     self
   }
 
-  /// Attach a stateful module to a `Router`; with async support
+  /// Set whether a path corrected by [`Router::set_fix_path`] is served
+  /// directly or redirected to.
   ///
-  /// Like a stateless module is an extension or middleware to a `Router`.
-  /// Modules get full access to the `Router` and can be extended by a third
-  /// party, but also, can create hooks will be executed through various parts
-  /// of a routes' lifecycle. Stateful modules also have state, so variables can
-  /// be stored for further access.
+  /// # Examples
   ///
-  /// # Panics
+  /// ```rust
+  /// use windmark::router::{FixPathPolicy, Router};
   ///
-  /// May panic if the stateful module cannot be attached.
+  /// Router::new()
+  ///   .set_fix_path(true)
+  ///   .set_fix_path_policy(FixPathPolicy::Redirect);
+  /// ```
+  pub fn set_fix_path_policy(
+    &mut self,
+    fix_path_policy: FixPathPolicy,
+  ) -> &mut Self {
+    self.fix_path_policy = fix_path_policy;
+
+    self
+  }
+
+  /// Reject requests whose URL is not a plain Gemini URL: the scheme must be
+  /// `gemini`, a host must be present, and neither userinfo nor a fragment
+  /// may be included.
+  ///
+  /// Rejected requests are passed to the error handler as a bad request.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// use log::info;
-  /// use windmark::{context::HookContext, router::Router};
-  ///
-  /// #[derive(Default)]
-  /// struct Clicker {
-  ///   clicks: isize,
-  /// }
+  /// windmark::router::Router::new().set_strict_urls(true); 
+  /// ```
+  pub fn set_strict_urls(&mut self, strict_urls: bool) -> &mut Self {
+    self.strict_urls = strict_urls;
+
+    self
+  }
+
+  /// Only include the MIME type in the meta of successful responses, adding
+  /// the character set only when it is not UTF-8 and never adding languages.
   ///
-  /// #[async_trait::async_trait]
-  /// impl windmark::module::AsyncModule for Clicker {
-  ///   async fn on_attach(&mut self, _: &mut Router) {
-  ///     info!("clicker has been attached!");
-  ///   }
+  /// # Examples
   ///
-  ///   async fn on_pre_route(&mut self, context: HookContext) {
-  ///     self.clicks += 1;
+  /// ```rust
+  /// windmark::router::Router::new().set_minimal_meta(true); 
+  /// ```
+  pub fn set_minimal_meta(&mut self, minimal_meta: bool) -> &mut Self {
+    self.minimal_meta = minimal_meta;
+
+    self
+  }
+
+  /// The current limits on connections. They are plain values, so a lock
+  /// poisoned while they were set still holds valid ones.
+  fn limits(&self) -> ConnectionLimits {
+    *self
+      .limits
+      .read()
+      .unwrap_or_else(std::sync::PoisonError::into_inner)
+  }
+
+  fn limits_mut(&self) -> std::sync::RwLockWriteGuard<'_, ConnectionLimits> {
+    self
+      .limits
+      .write()
+      .unwrap_or_else(std::sync::PoisonError::into_inner)
+  }
+
+  /// Set the maximum time a client may take to complete the TLS handshake.
   ///
-  ///     info!(
-  ///       "clicker has been called pre-route on {} with {} clicks!",
-  ///       context.url.path(),
-  ///       self.clicks
-  ///     );
-  ///   }
+  /// `None` disables the timeout. Defaults to ten seconds.
   ///
-  ///   async fn on_post_route(&mut self, context: HookContext) {
-  ///     info!(
-  ///       "clicker has been called post-route on {} with {} clicks!",
-  ///       context.url.path(),
-  ///       self.clicks
-  ///     );
-  ///   }
-  /// }
+  /// # Examples
   ///
-  /// Router::new().attach_async(Clicker::default());
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_handshake_timeout(Some(std::time::Duration::from_secs(5)));
   /// ```
-  pub fn attach_async(
+  pub fn set_handshake_timeout(
     &mut self,
-    mut module: impl AsyncModule + 'static,
+    timeout: Option<time::Duration>,
   ) -> &mut Self {
-    block!({
-      module.on_attach(self).await;
-
-      (*self.async_modules.lock().await).push(Box::new(module));
-    });
+    self.limits_mut().handshake_timeout = timeout;
 
     self
   }
 
-  /// Attach a stateful module to a `Router`.
+  /// Set the maximum time a client may take to send its request line.
   ///
-  /// Like a stateless module is an extension or middleware to a `Router`.
-  /// Modules get full access to the `Router` and can be extended by a third
-  /// party, but also, can create hooks will be executed through various parts
-  /// of a routes' lifecycle. Stateful modules also have state, so variables can
-  /// be stored for further access.
+  /// `None` disables the timeout. Defaults to ten seconds.
   ///
-  /// # Panics
+  /// # Examples
   ///
-  /// May panic if the stateful module cannot be attached.
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_read_timeout(Some(std::time::Duration::from_secs(5)));
+  /// ```
+  pub fn set_read_timeout(
+    &mut self,
+    timeout: Option<time::Duration>,
+  ) -> &mut Self {
+    self.limits_mut().read_timeout = timeout;
+
+    self
+  }
+
+  /// Set the maximum time writing a response to a client may take.
+  ///
+  /// `None` disables the timeout. Defaults to thirty seconds.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// use log::info;
-  /// use windmark::{context::HookContext, response::Response, router::Router};
+  /// windmark::router::Router::new()
+  ///   .set_write_timeout(Some(std::time::Duration::from_secs(15)));
+  /// ```
+  pub fn set_write_timeout(
+    &mut self,
+    timeout: Option<time::Duration>,
+  ) -> &mut Self {
+    self.limits_mut().write_timeout = timeout;
+
+    self
+  }
+
+  /// Set the minimum rate, in bytes per second, at which a client must read
+  /// its response.
   ///
-  /// #[derive(Default)]
-  /// struct Clicker {
-  ///   clicks: isize,
-  /// }
+  /// A client which stalls, e.g., to pin a large response in memory, has its
+  /// connection closed, and is logged, once it falls behind this rate, with a
+  /// few seconds of grace. Unlike the write timeout, this allows large
+  /// responses to take as long as they need for a client reading them
+  /// steadily. `None` disables the minimum, which is the default.
   ///
-  /// impl windmark::module::Module for Clicker {
-  ///   fn on_attach(&mut self, _: &mut Router) {
-  ///     info!("clicker has been attached!");
-  ///   }
+  /// # Examples
   ///
-  ///   fn on_pre_route(&mut self, context: HookContext) {
-  ///     self.clicks += 1;
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_min_write_rate(std::num::NonZeroU64::new(1024));
+  /// ```
+  pub fn set_min_write_rate(
+    &mut self,
+    rate: Option<std::num::NonZeroU64>,
+  ) -> &mut Self {
+    self.limits_mut().min_write_rate = rate;
+
+    self
+  }
+
+  /// Set the maximum time a route handler, along with its middleware, may
+  /// take to respond.
   ///
-  ///     info!(
-  ///       "clicker has been called pre-route on {} with {} clicks!",
-  ///       context.url.path(),
-  ///       self.clicks
-  ///     );
-  ///   }
+  /// A handler which exceeds the deadline is abandoned, and the error handler
+  /// responds in its place with [`ErrorKind::Timeout`]. Routes mounted with
+  /// [`Router::mount_with_timeout`] override this deadline. `None` disables
+  /// the timeout, which is the default.
   ///
-  ///   fn on_post_route(&mut self, context: HookContext) {
-  ///     info!(
-  ///       "clicker has been called post-route on {} with {} clicks!",
-  ///       context.url.path(),
-  ///       self.clicks
-  ///     );
-  ///   }
-  /// }
+  /// # Examples
   ///
-  /// Router::new().attach(Clicker::default());
+  /// ```rust
+  /// windmark::router::Router::new()
+  ///   .set_handler_timeout(Some(std::time::Duration::from_secs(5)));
   /// ```
-  pub fn attach(
+  pub fn set_handler_timeout(
     &mut self,
-    mut module: impl Module + 'static + Send,
+    timeout: Option<time::Duration>,
   ) -> &mut Self {
-    module.on_attach(self);
-
-    (*self.modules.lock().unwrap()).push(Box::new(module));
+    self.limits_mut().handler_timeout = timeout;
 
     self
   }
 
-  /// Specify a custom character set.
+  /// Set the maximum number of connections handled at once.
   ///
-  /// Will be over-ridden if a character set is specified in a [`Response`].
+  /// Once the limit is reached, new connections are not accepted until an
+  /// open connection closes. `None` disables the limit, which is the default.
   ///
-  /// Defaults to `"utf-8"`.
+  /// Unlike the other limits, this one is fixed once the `Router` starts
+  /// serving, so changing it takes effect the next time it is run.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// windmark::router::Router::new().set_character_set("utf-8"); 
+  /// windmark::router::Router::new().set_max_connections(Some(512)); 
   /// ```
-  pub fn set_character_set(
+  pub fn set_max_connections(
     &mut self,
-    character_set: impl Into<String> + AsRef<str>,
+    max_connections: Option<usize>,
   ) -> &mut Self {
-    self.character_set = character_set.into();
+    self.max_connections = max_connections;
 
     self
   }
 
-  /// Specify a custom language.
-  ///
-  /// Will be over-ridden if a language is specified in a [`Response`].
+  /// Set the maximum number of connections handled at once from a single IP
+  /// address.
   ///
-  /// Defaults to `"en"`.
+  /// Connections over the limit are closed immediately. `None` disables the
+  /// limit, which is the default.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// windmark::router::Router::new().set_languages(["en"]); 
+  /// windmark::router::Router::new().set_max_peer_connections(Some(16));
   /// ```
-  pub fn set_languages<S>(&mut self, language: impl AsRef<[S]>) -> &mut Self
-  where S: Into<String> + AsRef<str> {
-    self.languages = language
-      .as_ref()
-      .iter()
-      .map(|s| s.as_ref().to_string())
-      .collect::<Vec<String>>();
+  pub fn set_max_peer_connections(
+    &mut self,
+    max_peer_connections: Option<usize>,
+  ) -> &mut Self {
+    self.limits_mut().max_peer_connections = max_peer_connections;
 
     self
   }
 
-  /// Specify a custom port.
+  /// Apply a preset bundle of settings.
   ///
-  /// Defaults to `1965`.
+  /// Every setting applied by the profile can be overridden by calling its
+  /// setter after this method.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// windmark::router::Router::new().set_port(1965); 
+  /// use windmark::router::{Profile, Router};
+  ///
+  /// Router::new()
+  ///   .with_profile(Profile::Hardened)
+  ///   .set_fix_path(true);
   /// ```
-  pub fn set_port(&mut self, port: i32) -> &mut Self {
-    self.port = port;
+  pub fn with_profile(&mut self, profile: Profile) -> &mut Self {
+    profile.apply(self);
 
     self
   }
 
-  /// Performs a case-insensitive lookup of routes, using the case corrected
-  /// path if successful. Missing/ extra trailing slashes are also corrected.
+  /// Create a `Router` from the settings in a TOML file.
+  ///
+  /// # Errors
+  ///
+  /// if the file could not be read or is not a valid configuration.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,no_run
+  /// let mut router =
+  ///   windmark::router::Router::from_config_file("windmark.toml").unwrap();
+  /// ```
+  #[cfg(feature = "config")]
+  pub fn from_config_file(
+    path: impl AsRef<std::path::Path>,
+  ) -> Result<Self, ConfigError> {
+    let mut router = Self::new();
+
+    router.apply_config(&Config::from_file(path.as_ref())?);
+    router.config_path = Some(path.as_ref().to_path_buf());
+
+    Ok(router)
+  }
+
+  /// Apply the settings of a [`Config`].
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  #[cfg(feature = "config")]
+  pub fn apply_config(&mut self, config: &Config) -> &mut Self {
+    config.apply(self);
+
+    *self.applied_config.lock().unwrap() = Some(config.clone());
+
+    self
+  }
+
+  /// Re-read the configuration file the `Router` was created from with
+  /// [`Router::from_config_file`], applying the settings which can change
+  /// while it is running: static directories, redirects, authorization, MIME
+  /// types, the favicon, limits other than `max_connections`, and the
+  /// certificate and private key. Other settings take effect the next time
+  /// the `Router` is run.
+  ///
+  /// Static directories, redirects, and restrictions which were removed from
+  /// the file are undone, and the routes are swapped at once, so requests
+  /// made during a reload are never answered as though they were unmounted.
+  /// Nothing is changed if the file, certificate, or private key can not be
+  /// loaded, or if the file's routes conflict with other mounted routes.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,no_run
+  /// let mut router =
+  ///   windmark::router::Router::from_config_file("windmark.toml").unwrap();
+  ///
+  /// router.reload_config().unwrap();
+  /// ```
+  ///
+  /// # Errors
+  ///
+  /// if the file could not be read or is not a valid configuration, the
+  /// certificate or private key could not be loaded, or the file's routes
+  /// conflict with other mounted routes.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route table's lock is poisoned.
+  #[cfg(feature = "config")]
+  pub fn reload_config(&mut self) -> Result<(), ConfigError> {
+    let Some(path) = self.config_path.clone() else {
+      return Ok(());
+    };
+    let config = Config::from_file(path)?;
+
+    // The acceptor is rebuilt from a copy so that nothing changes unless the
+    // new certificate and private key are valid. A `Router` which has not been
+    // finalized builds its acceptor when it is.
+    if self.finalized && !self.custom_ssl_acceptor {
+      let mut router = self.clone();
+
+      config.apply_tls(&mut router);
+      router
+        .certificate
+        .check("certificate")
+        .map_err(ConfigError::Tls)?;
+      router
+        .private_key
+        .check("private key")
+        .map_err(ConfigError::Tls)?;
+      router.create_acceptor().map_err(ConfigError::Tls)?;
+    }
+
+    let previous = self.applied_config.lock().unwrap().take();
+
+    if let Err(e) = config.reapply(previous.as_ref(), self) {
+      *self.applied_config.lock().unwrap() = previous;
+
+      return Err(ConfigError::Routes(e));
+    }
+
+    config.apply_tls(self);
+    *self.applied_config.lock().unwrap() = Some(config);
+
+    Ok(())
+  }
+
+  /// Reload the configuration file the `Router` was created from, as with
+  /// [`Router::reload_config`], whenever the process receives `SIGHUP`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,no_run
+  /// windmark::router::Router::from_config_file("windmark.toml")
+  ///   .unwrap()
+  ///   .set_reload_config_on_sighup(true);
+  /// ```
+  #[cfg(all(unix, feature = "config"))]
+  pub fn set_reload_config_on_sighup(&mut self, reload: bool) -> &mut Self {
+    self.reload_config_on_sighup = reload;
+
+    self
+  }
+
+  #[cfg(all(unix, feature = "config"))]
+  fn reload_config_on_hangup(&self) {
+    let hangup = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+    if let Err(e) =
+      signal_hook::flag::register(signal_hook::consts::SIGHUP, hangup.clone())
+    {
+      error!("could not listen for SIGHUP: {e}");
+
+      return;
+    }
+
+    let mut router = self.clone();
+
+    runtime::detach(async move {
+      while runtime::or(
+        async {
+          runtime::sleep(time::Duration::from_secs(1)).await;
+
+          true
+        },
+        async {
+          router.shutdown.triggered().await;
+
+          false
+        },
+      )
+      .await
+      {
+        if hangup.swap(false, std::sync::atomic::Ordering::SeqCst) {
+          match router.reload_config() {
+            Ok(()) => info!("reloaded the configuration"),
+            Err(e) => error!("{e}"),
+          }
+        }
+      }
+    });
+  }
+
+  /// Set the secret keys used for signing tokens.
+  ///
+  /// New tokens are signed with `current`, while tokens signed with any of
+  /// the `previous` keys continue to verify, allowing secrets to be rotated
+  /// gradually.
+  ///
+  /// Defaults to a randomly generated key.
   ///
   /// # Examples
   ///
   /// ```rust
-  /// windmark::router::Router::new().set_fix_path(true); 
+  /// windmark::router::Router::new().set_secret_keys("new secret", ["old secret"]);
   /// ```
-  pub fn set_fix_path(&mut self, fix_path: bool) -> &mut Self {
-    self.fix_path = fix_path;
+  pub fn set_secret_keys<K>(
+    &mut self,
+    current: impl AsRef<[u8]>,
+    previous: impl IntoIterator<Item = K>,
+  ) -> &mut Self
+  where
+    K: AsRef<[u8]>,
+  {
+    self.secret_keys.set(current, previous);
 
     self
   }
+
+  /// Get a handle to the secret keys of the `Router`.
+  ///
+  /// The handle shares state with the `Router`, so it can be used to rotate
+  /// keys while the `Router` is running.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let mut router = windmark::router::Router::new();
+  /// let keys = router.secret_keys();
+  ///
+  /// router.mount("/token", move |context| {
+  ///   windmark::response::Response::success(
+  ///     keys.sign(context.url.path()).unwrap(),
+  ///   )
+  /// });
+  /// ```
+  #[must_use]
+  pub fn secret_keys(&self) -> SecretKeys { self.secret_keys.clone() }
 }
 impl Default for Router {
+  #[allow(clippy::too_many_lines)]
   fn default() -> Self {
     Self {
-      routes: matchit::Router::new(),
-      error_handler: Arc::new(AsyncMutex::new(Box::new(|_| {
-        async {
-          Response::not_found(
-            "This capsule has not implemented an error handler...",
-          )
-        }
-      }))),
-      private_key_file_name: String::new(),
-      certificate_file_name: String::new(),
+      routes: Arc::new(RwLock::new(Arc::new(RouteTable::default()))),
+      error_handler: Arc::new(AsyncMutex::new(Box::new(
+        |context: ErrorContext| {
+          async move {
+            match context.kind {
+              ErrorKind::RouteMiss =>
+                Response::not_found(
+                  "This capsule has not implemented an error handler...",
+                ),
+              ErrorKind::Handler(_) =>
+                Response::temporary_failure(
+                  "This capsule encountered an error while handling your \
+                   request...",
+                ),
+              ErrorKind::Panic(_) =>
+                Response::cgi_error(
+                  "This capsule encountered an error while handling your \
+                   request...",
+                ),
+              ErrorKind::Timeout(_) =>
+                Response::cgi_error(
+                  "This capsule took too long to handle your request...",
+                ),
+              ErrorKind::BadRequest(reason) =>
+                Response::bad_request(format!(
+                  "The server (Windmark) received a bad request: {reason}"
+                )),
+              ErrorKind::Failure(failure) => failure.into(),
+            }
+          }
+        },
+      ))),
+      failure_handlers: HashMap::new(),
+      not_found_handler: None,
+      private_key: tls::Material::default(),
+      certificate: tls::Material::default(),
+      tls_policy: TlsPolicy::default(),
+      close_mode: CloseMode::default(),
+      min_tls_version: None,
+      max_tls_version: None,
+      cipher_list: None,
+      ciphersuites: None,
+      keylog_file: None,
+      session_id_context: None,
+      session_tickets: true,
       headers: Arc::new(Mutex::new(vec![])),
       footers: Arc::new(Mutex::new(vec![])),
-      ssl_acceptor: Arc::new(
+      gemtext_processors: Arc::new(Mutex::new(vec![])),
+      ssl_acceptor: Arc::new(RwLock::new(Arc::new(
         SslAcceptor::mozilla_intermediate(SslMethod::tls())
           .unwrap()
           .build(),
-      ),
+      ))),
+      custom_ssl_acceptor: false,
+      finalized: false,
+      unmounted: Arc::new(Mutex::new(vec![])),
+      certificate_expiry_warning: Some(14),
+      certificate_expiry_interval: Some(time::Duration::from_secs(86_400)),
+      certificate_expiry_callback: None,
+      listen_callback: None,
+      accept_policy: AcceptPolicy::default(),
+      accept_error_callback: None,
+      connection_callback: None,
+      handshake_error_callback: None,
+      rejection_callback: None,
       #[cfg(feature = "logger")]
       default_logger: false,
-      pre_route_callback: Arc::new(Mutex::new(Box::new(|_| {}))),
-      post_route_callback: Arc::new(Mutex::new(Box::new(
+      #[cfg(feature = "logger")]
+      log_filter: "windmark=trace".to_string(),
+      #[cfg(feature = "logger")]
+      log_requests: true,
+      pre_route_callback: Arc::new(Mutex::new(Registered::new(Box::new(
+        |_| {},
+      )))),
+      post_route_callback: Arc::new(Mutex::new(Registered::new(Box::new(
         |_, _: &'_ mut Response| {},
-      ))),
+      )))),
       character_set: "utf-8".to_string(),
       languages: vec!["en".to_string()],
       port: 1965,
-      modules: Arc::new(Mutex::new(vec![])),
-      async_modules: Arc::new(AsyncMutex::new(vec![])),
+      modules: Arc::default(),
+      shutdown: shutdown::Shutdown::default(),
+      listening: Arc::new(Mutex::new(vec![])),
+      listener_names: Arc::default(),
+      tasks: Arc::default(),
+      warmed: warm::Warmed::default(),
+      middleware: Arc::new(Mutex::new(vec![])),
+      restrictions: Arc::new(RwLock::new(vec![])),
+      guards: Arc::new(RwLock::new(vec![])),
+      #[cfg(feature = "templates")]
+      templates: None,
+      mime_types: MimeTypes::default(),
+      buffers: Arc::default(),
+      stats: Stats::default(),
+      #[cfg(feature = "config")]
+      config_path: None,
+      #[cfg(feature = "config")]
+      applied_config: Arc::default(),
+      #[cfg(all(unix, feature = "config"))]
+      reload_config_on_sighup: false,
+      async_modules: Arc::default(),
       fix_path: false,
-      private_key_content: None,
-      certificate_content: None,
+      fix_path_policy: FixPathPolicy::default(),
+      strict_urls: false,
+      minimal_meta: false,
+      limits: Arc::new(RwLock::new(ConnectionLimits {
+        handshake_timeout:    Some(time::Duration::from_secs(10)),
+        read_timeout:         Some(time::Duration::from_secs(10)),
+        write_timeout:        Some(time::Duration::from_secs(30)),
+        handler_timeout:      None,
+        min_write_rate:       None,
+        max_peer_connections: None,
+      })),
+      max_connections: None,
+      connection_slots: None,
+      serving: Arc::default(),
+      peer_connections: connections::PeerConnections::default(),
+      listeners: vec![],
+      hostnames: vec![],
+      socket_options: SocketOptions::default(),
+      secret_keys: SecretKeys::random(),
     }
   }
 }
+
+/// The URL given to the contexts of a request whose URL could not be parsed,
+/// on the `invalid` top-level domain, which is reserved to never resolve.
+const UNPARSED_URL: &str = "gemini://invalid/";
+
+/// The longest request accepted, a URL of at most 1024 bytes followed by
+/// `\r\n`.
+const MAX_REQUEST_LENGTH: usize = 1024 + 2;
+
+/// Responses are written in chunks of this length when a minimum write rate is
+/// set, so that a stalled client is noticed part way through a response.
+const WRITE_CHUNK_LENGTH: usize = 16 * 1024;
+
+/// The time a client is allowed on top of the minimum write rate, so that
+/// network latency does not count against small responses.
+const MIN_WRITE_RATE_GRACE: time::Duration = time::Duration::from_secs(5);
+
+/// The longest a connection closed with [`CloseMode::Linger`] waits for its
+/// client to close its side.
+const LINGER_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+/// Resolve once the client of `stream` disconnects.
+///
+/// Clients send nothing after their request, so a read only completes once the
+/// client has disconnected.
+async fn disconnected(stream: &mut Stream) {
+  let mut probe = [0u8; 64];
+
+  while !matches!(stream.read(&mut probe).await, Ok(0) | Err(_)) {}
+}
+
+/// Close a connection once its response has been written.
+async fn close(
+  stream: &mut Stream,
+  mode: CloseMode,
+) -> Result<(), Box<dyn Error>> {
+  #[cfg(feature = "tokio")]
+  if mode == CloseMode::TcpOnly {
+    stream.get_mut().shutdown().await?;
+  } else {
+    // Sends `close_notify` before shutting down the TCP connection.
+    stream.shutdown().await?;
+  }
+  #[cfg(any(feature = "async-std", feature = "smol"))]
+  if mode == CloseMode::TcpOnly {
+    stream.get_mut().shutdown(std::net::Shutdown::Write)?;
+  } else {
+    // Sends `close_notify` before shutting down the TCP connection.
+    std::future::poll_fn(|context| {
+      std::pin::Pin::new(&mut *stream).poll_close(context)
+    })
+    .await?;
+  }
+
+  if mode == CloseMode::Linger {
+    let _ = runtime::timeout(Some(LINGER_TIMEOUT), disconnected(stream)).await;
+  }
+
+  Ok(())
+}
+
+fn describe_partial((scope, partial): &ScopedPartial) -> PartialInfo {
+  PartialInfo {
+    location: partial.location,
+    scope:    scope.clone(),
+  }
+}
+
+fn describe_partials(partials: &[ScopedPartial]) -> Vec<PartialInfo> {
+  partials.iter().map(describe_partial).collect()
+}
+
+fn retain_partials(
+  partials: &mut Vec<ScopedPartial>,
+  mut keep: impl FnMut(&PartialInfo) -> bool,
+) {
+  partials.retain(|partial| keep(&describe_partial(partial)));
+}
+
+/// Whether `path` is `prefix` or lies beneath it.
+pub(crate) fn is_beneath(path: &str, prefix: &str) -> bool {
+  path
+    .strip_prefix(prefix.trim_end_matches('/'))
+    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Parse the URL from the bytes of a request line, or describe why the request
+/// is bad.
+fn parse_request(
+  request: &[u8],
+) -> Result<(Url, Option<Rejection>), url::ParseError> {
+  let line = request
+    .iter()
+    .position(|byte| *byte == b'\n')
+    .map_or(request, |end| &request[..end]);
+  let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+  let url = if line.is_empty() {
+    Err(Rejection::Empty)
+  } else if line.len() > MAX_REQUEST_LENGTH - 2 {
+    Err(Rejection::Oversized)
+  } else {
+    std::str::from_utf8(line)
+      .map_err(|_| Rejection::InvalidUtf8)
+      .and_then(|line| Url::parse(line).map_err(Rejection::InvalidUrl))
+  };
+
+  match url {
+    Ok(url) => Ok((url, None)),
+    Err(reason) => Ok((Url::parse(UNPARSED_URL)?, Some(reason))),
+  }
+}
+
+/// Whether `hostname` is exactly the host a request for it would contain.
+fn is_valid_hostname(hostname: &str) -> bool {
+  Url::parse(&format!("gemini://{hostname}/")).is_ok_and(|url| {
+    url
+      .host_str()
+      .is_some_and(|host| host.eq_ignore_ascii_case(hostname))
+  })
+}
+
+fn validate_url(url: &Url) -> Result<(), Rejection> {
+  if url.scheme() != "gemini" {
+    return Err(Rejection::SchemeMismatch(url.scheme().to_string()));
+  }
+
+  if !url.has_host() {
+    return Err(Rejection::MissingHost);
+  }
+
+  if !url.username().is_empty() || url.password().is_some() {
+    return Err(Rejection::Userinfo);
+  }
+
+  if url.fragment().is_some() {
+    return Err(Rejection::Fragment);
+  }
+
+  Ok(())
+}
+
+fn generate_request_id() -> String {
+  let mut bytes = [0u8; 16];
+
+  if openssl::rand::rand_bytes(&mut bytes).is_err() {
+    warn!("could not generate random bytes for a request ID");
+  }
+
+  // Mark the bytes as a version 4, variant 1 UUID.
+  bytes[6] = (bytes[6] & 0x0f) | 0x40;
+  bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+  let hex = crate::utilities::hex(&bytes);
+
+  format!(
+    "{}-{}-{}-{}-{}",
+    &hex[0..8],
+    &hex[8..12],
+    &hex[12..16],
+    &hex[16..20],
+    &hex[20..32]
+  )
+}
@@ -0,0 +1,91 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{io, net::SocketAddr, time::Duration};
+
+/// How a listener reacts when accepting connections fails, e.g., because the
+/// process has run out of file descriptors.
+///
+/// Errors which only concern a single connection, such as a client resetting
+/// its connection before it is accepted, are ignored. Any other error is
+/// followed by a pause, doubled after each consecutive failure, so that a
+/// listener which keeps failing does not spin.
+#[derive(Clone, Copy, Debug)]
+pub struct AcceptPolicy {
+  /// The pause after the first failure.
+  pub backoff:      Duration,
+  /// The longest pause between consecutive failures.
+  pub max_backoff:  Duration,
+  /// The number of consecutive failures after which the accept error
+  /// callback is called, on every failure, until a connection is accepted.
+  pub report_after: usize,
+  /// The number of consecutive failures after which the `Router` stops and
+  /// `run` returns the last error. `None` keeps accepting indefinitely.
+  pub fatal_after:  Option<usize>,
+}
+
+impl Default for AcceptPolicy {
+  fn default() -> Self {
+    Self {
+      backoff:      Duration::from_millis(10),
+      max_backoff:  Duration::from_secs(1),
+      report_after: 1,
+      fatal_after:  None,
+    }
+  }
+}
+
+impl AcceptPolicy {
+  /// The pause after the given number of consecutive failures.
+  pub(super) fn backoff(&self, consecutive: usize) -> Duration {
+    let exponent = u32::try_from(consecutive.saturating_sub(1))
+      .unwrap_or(u32::MAX)
+      .min(16);
+
+    self
+      .backoff
+      .saturating_mul(1 << exponent)
+      .min(self.max_backoff)
+  }
+
+  pub(super) fn is_fatal(&self, consecutive: usize) -> bool {
+    self.fatal_after.is_some_and(|limit| consecutive >= limit)
+  }
+}
+
+/// A failure to accept a connection, as passed to the accept error callback.
+#[derive(Debug)]
+pub struct AcceptError {
+  /// The address of the listener which failed.
+  pub listener:    SocketAddr,
+  /// The number of times in a row the listener has failed.
+  pub consecutive: usize,
+  pub error:       io::Error,
+}
+
+/// Whether `error` only concerns a single connection, rather than the
+/// listener.
+pub(super) fn is_transient(error: &io::Error) -> bool {
+  matches!(
+    error.kind(),
+    io::ErrorKind::ConnectionAborted
+      | io::ErrorKind::ConnectionReset
+      | io::ErrorKind::ConnectionRefused
+      | io::ErrorKind::Interrupted
+      | io::ErrorKind::WouldBlock
+  )
+}
@@ -0,0 +1,70 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use openssl::x509::X509Ref;
+
+use crate::known_clients::KnownClients;
+
+/// The client certificates which may request routes beneath a prefix, as
+/// restricted with [`Router::restrict`](super::Router::restrict).
+///
+/// A certificate is authorised if its fingerprint is listed. Subjects are not
+/// matched, as any client can present a self-signed certificate claiming any
+/// subject.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::router::CertificatePolicy;
+///
+/// CertificatePolicy::new().fingerprint(
+///   "b7205a1f9636e9b2a3680163026268654238ee0a503c488257a260a2321f8c49",
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(default, deny_unknown_fields))]
+pub struct CertificatePolicy {
+  /// Hexadecimal SHA-256 fingerprints, with or without colons.
+  pub fingerprints: Vec<String>,
+}
+
+impl CertificatePolicy {
+  /// A policy which authorises no certificates.
+  #[must_use]
+  pub fn new() -> Self { Self::default() }
+
+  /// Authorise the certificate with `fingerprint`.
+  #[must_use]
+  pub fn fingerprint(mut self, fingerprint: impl Into<String>) -> Self {
+    self.fingerprints.push(fingerprint.into());
+
+    self
+  }
+
+  /// Whether `certificate` is authorised.
+  #[must_use]
+  pub fn allows(&self, certificate: &X509Ref) -> bool {
+    let Some(fingerprint) = KnownClients::fingerprint(certificate) else {
+      return false;
+    };
+
+    self.fingerprints.iter().any(|allowed| {
+      allowed.replace(':', "").eq_ignore_ascii_case(&fingerprint)
+    })
+  }
+}
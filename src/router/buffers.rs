@@ -0,0 +1,90 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  ops::{Deref, DerefMut},
+  sync::{Arc, Mutex, PoisonError},
+};
+
+/// The most buffers kept for reuse at once.
+const MAX_POOLED: usize = 64;
+/// Buffers which have grown beyond this many bytes are freed rather than kept,
+/// so a single large response does not pin its memory.
+const MAX_RETAINED_CAPACITY: usize = 64 * 1024;
+
+/// Byte buffers shared between connections, so reading requests and writing
+/// responses reuses memory instead of allocating anew for every request.
+#[derive(Default)]
+pub struct BufferPool {
+  buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+  /// Take an empty buffer from the pool, or allocate one if none are free.
+  ///
+  /// The buffer is returned to the pool when dropped.
+  pub fn take(self: &Arc<Self>) -> Buffer {
+    let buffer = self
+      .buffers
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .pop()
+      .unwrap_or_default();
+
+    Buffer {
+      buffer,
+      pool: self.clone(),
+    }
+  }
+}
+
+/// A buffer borrowed from a [`BufferPool`].
+pub struct Buffer {
+  buffer: Vec<u8>,
+  pool:   Arc<BufferPool>,
+}
+
+impl Deref for Buffer {
+  type Target = Vec<u8>;
+
+  fn deref(&self) -> &Self::Target { &self.buffer }
+}
+
+impl DerefMut for Buffer {
+  fn deref_mut(&mut self) -> &mut Self::Target { &mut self.buffer }
+}
+
+impl Drop for Buffer {
+  fn drop(&mut self) {
+    if self.buffer.capacity() > MAX_RETAINED_CAPACITY {
+      return;
+    }
+
+    let mut buffers = self
+      .pool
+      .buffers
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner);
+
+    if buffers.len() < MAX_POOLED {
+      let mut buffer = std::mem::take(&mut self.buffer);
+
+      buffer.clear();
+      buffers.push(buffer);
+    }
+  }
+}
@@ -0,0 +1,246 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::future::IntoFuture;
+
+use super::{Profile, Router};
+use crate::{
+  context::{ErrorContext, RouteContext},
+  module::{AsyncModule, Module},
+  response::{IntoResponse, Response},
+};
+
+/// A consuming builder for a [`Router`], for when chaining through `&mut`
+/// references is awkward.
+///
+/// Only the most common settings have builder methods; any other setting can
+/// be reached through [`RouterBuilder::configure`].
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::response::Response;
+///
+/// let router = windmark::router::Router::builder()
+///   .port(1965)
+///   .private_key_file("windmark_private.pem")
+///   .certificate_file("windmark_public.pem")
+///   .mount("/", |_| Response::success("Hello, World!"))
+///   .build();
+/// ```
+#[must_use]
+pub struct RouterBuilder {
+  router: Router,
+}
+
+impl RouterBuilder {
+  pub(super) fn new() -> Self {
+    Self {
+      router: Router::new(),
+    }
+  }
+
+  /// See [`Router::with_profile`].
+  pub fn profile(mut self, profile: Profile) -> Self {
+    self.router.with_profile(profile);
+
+    self
+  }
+
+  /// See [`Router::set_port`].
+  pub fn port(mut self, port: i32) -> Self {
+    self.router.set_port(port);
+
+    self
+  }
+
+  /// See [`Router::add_listener`].
+  pub fn listener(mut self, address: impl Into<String> + AsRef<str>) -> Self {
+    self.router.add_listener(address);
+
+    self
+  }
+
+  /// See [`Router::add_named_listener`].
+  pub fn named_listener(
+    mut self,
+    name: impl Into<String> + AsRef<str>,
+    address: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    self.router.add_named_listener(name, address);
+
+    self
+  }
+
+  /// See [`Router::set_private_key_file`].
+  pub fn private_key_file(
+    mut self,
+    private_key_file_name: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    self.router.set_private_key_file(private_key_file_name);
+
+    self
+  }
+
+  /// See [`Router::set_private_key`].
+  pub fn private_key(
+    mut self,
+    private_key_content: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    self.router.set_private_key(private_key_content);
+
+    self
+  }
+
+  /// See [`Router::set_certificate_file`].
+  pub fn certificate_file(
+    mut self,
+    certificate_name: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    self.router.set_certificate_file(certificate_name);
+
+    self
+  }
+
+  /// See [`Router::set_certificate_chain_file`].
+  pub fn certificate_chain_file(
+    mut self,
+    certificate_chain_name: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    self
+      .router
+      .set_certificate_chain_file(certificate_chain_name);
+
+    self
+  }
+
+  /// See [`Router::set_certificate`].
+  pub fn certificate(
+    mut self,
+    certificate_content: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    self.router.set_certificate(certificate_content);
+
+    self
+  }
+
+  /// See [`Router::set_character_set`].
+  pub fn character_set(
+    mut self,
+    character_set: impl Into<String> + AsRef<str>,
+  ) -> Self {
+    self.router.set_character_set(character_set);
+
+    self
+  }
+
+  /// See [`Router::set_languages`].
+  pub fn languages<S>(mut self, languages: impl AsRef<[S]>) -> Self
+  where S: Into<String> + AsRef<str> {
+    self.router.set_languages(languages);
+
+    self
+  }
+
+  /// See [`Router::mount`].
+  ///
+  /// # Panics
+  ///
+  /// May panic if the route cannot be mounted.
+  pub fn mount<R>(
+    mut self,
+    route: impl Into<String> + AsRef<str>,
+    handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> Self
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.router.mount(route, handler);
+
+    self
+  }
+
+  /// See [`Router::set_error_handler`].
+  pub fn error_handler<R>(
+    mut self,
+    handler: impl FnMut(ErrorContext) -> R + Send + Sync + 'static,
+  ) -> Self
+  where
+    R: IntoFuture<Output = Response> + Send + 'static,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.router.set_error_handler(handler);
+
+    self
+  }
+
+  /// See [`Router::set_failure_handler`].
+  pub fn failure_handler<R>(
+    mut self,
+    status: i32,
+    handler: impl FnMut(ErrorContext) -> R + Send + Sync + 'static,
+  ) -> Self
+  where
+    R: IntoFuture<Output = Response> + Send + 'static,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    self.router.set_failure_handler(status, handler);
+
+    self
+  }
+
+  /// See [`Router::attach`].
+  ///
+  /// # Panics
+  ///
+  /// May panic if the module cannot be attached.
+  pub fn attach(mut self, module: impl Module + 'static + Send) -> Self {
+    self.router.attach(module);
+
+    self
+  }
+
+  /// See [`Router::attach_async`].
+  pub fn attach_async(mut self, module: impl AsyncModule + 'static) -> Self {
+    self.router.attach_async(module);
+
+    self
+  }
+
+  /// Apply any other setting through the `Router`'s own setters.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let router = windmark::router::Router::builder()
+  ///   .configure(|router| {
+  ///     router.set_fix_path(true);
+  ///   })
+  ///   .build();
+  /// ```
+  pub fn configure(mut self, configure: impl FnOnce(&mut Router)) -> Self {
+    configure(&mut self.router);
+
+    self
+  }
+
+  /// Finish building the `Router`.
+  #[must_use]
+  pub fn build(self) -> Router { self.router }
+}
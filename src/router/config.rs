@@ -0,0 +1,292 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  collections::BTreeMap,
+  fmt,
+  io,
+  num::NonZeroU64,
+  path::PathBuf,
+  time::Duration,
+};
+
+use serde::Deserialize;
+
+use super::Router;
+
+/// Settings which can be loaded into a `Router` from a TOML file.
+///
+/// Every setting is optional; settings left out keep their current value.
+///
+/// ```toml
+/// port = 1965
+/// certificate = "fullchain.pem"
+/// private_key = "privkey.pem"
+/// hostnames = ["example.com"]
+/// languages = ["en"]
+/// character_set = "utf-8"
+/// favicon = "🦀"
+///
+/// [static]
+/// "/files" = "./public"
+///
+/// [redirects]
+/// "/old" = "/new"
+///
+/// [mime_types]
+/// ts = "video/mp2t"
+///
+/// [authorization."/admin"]
+/// fingerprints = [
+///   "b7205a1f9636e9b2a3680163026268654238ee0a503c488257a260a2321f8c49",
+/// ]
+///
+/// [limits]
+/// max_connections = 1024
+/// max_peer_connections = 32
+/// read_timeout = 10
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub port:               Option<i32>,
+  /// Addresses to listen on, in addition to or instead of `port`.
+  pub listeners:          Vec<String>,
+  /// A certificate chain file.
+  pub certificate:        Option<String>,
+  pub private_key:        Option<String>,
+  /// The hostnames requests are accepted for; all hostnames are accepted if
+  /// empty.
+  pub hostnames:          Vec<String>,
+  pub languages:          Option<Vec<String>>,
+  pub character_set:      Option<String>,
+  /// An emoji served at `/favicon.txt`.
+  pub favicon:            Option<String>,
+  /// Directories to serve, keyed by the route they are mounted at.
+  #[serde(rename = "static")]
+  pub static_directories: BTreeMap<String, PathBuf>,
+  /// Permanent redirects, keyed by the route they are mounted at.
+  pub redirects:          BTreeMap<String, String>,
+  /// MIME types for served files, keyed by extension.
+  pub mime_types:         BTreeMap<String, String>,
+  /// The client certificates which may request routes, keyed by the prefix
+  /// they are restricted beneath.
+  pub authorization:      BTreeMap<String, super::CertificatePolicy>,
+  pub limits:             Limits,
+}
+
+/// Connection limits and timeouts, with timeouts in seconds.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+  pub max_connections:      Option<usize>,
+  pub max_peer_connections: Option<usize>,
+  pub handshake_timeout:    Option<u64>,
+  pub read_timeout:         Option<u64>,
+  pub write_timeout:        Option<u64>,
+  pub handler_timeout:      Option<u64>,
+  /// The minimum rate, in bytes per second, at which clients must read.
+  pub min_write_rate:       Option<u64>,
+}
+
+/// An error encountered while loading a [`Config`].
+#[derive(Debug)]
+pub enum ConfigError {
+  Io(io::Error),
+  Parse(toml::de::Error),
+  /// The certificate or private key could not be loaded while reloading.
+  Tls(crate::Error),
+  /// Routes in the file conflict with other mounted routes while reloading.
+  Routes(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "could not read configuration: {e}"),
+      Self::Parse(e) => write!(f, "could not parse configuration: {e}"),
+      Self::Tls(e) => write!(f, "could not reload configuration: {e}"),
+      Self::Routes(routes) =>
+        write!(f, "could not reload configuration: {}", routes.join("; ")),
+    }
+  }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+  /// Load settings from a TOML file.
+  ///
+  /// # Errors
+  ///
+  /// if the file could not be read or is not a valid configuration.
+  pub fn from_file(
+    path: impl AsRef<std::path::Path>,
+  ) -> Result<Self, ConfigError> {
+    let content = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+    toml::from_str(&content).map_err(ConfigError::Parse)
+  }
+
+  pub(super) fn apply(&self, router: &mut Router) {
+    if let Some(port) = self.port {
+      router.set_port(port);
+    }
+
+    for listener in &self.listeners {
+      router.add_listener(listener.as_str());
+    }
+
+    for hostname in &self.hostnames {
+      router.add_hostname(hostname.as_str());
+    }
+
+    if let Some(languages) = &self.languages {
+      router.set_languages(languages);
+    }
+
+    if let Some(character_set) = &self.character_set {
+      router.set_character_set(character_set.as_str());
+    }
+
+    self.apply_tls(router);
+    self.apply_routes(router);
+    self.apply_settings(router);
+  }
+
+  pub(super) fn apply_tls(&self, router: &mut Router) {
+    if let Some(certificate) = &self.certificate {
+      router.set_certificate_chain_file(certificate.as_str());
+    }
+
+    if let Some(private_key) = &self.private_key {
+      router.set_private_key_file(private_key.as_str());
+    }
+  }
+
+  /// Replace the settings which are shared by every clone of a `Router`, and
+  /// so can be changed while it is running, with these, undoing those of
+  /// `previous` which were removed.
+  ///
+  /// New settings are applied before stale ones are undone, and the routes
+  /// are swapped at once between the two, so no request sees neither. Nothing
+  /// is changed if the routes conflict with other mounted routes, which are
+  /// returned.
+  pub(super) fn reapply(
+    &self,
+    previous: Option<&Self>,
+    router: &mut Router,
+  ) -> Result<(), Vec<String>> {
+    let staged = router.stage_routes(|router| {
+      if let Some(previous) = previous {
+        previous.unapply_routes(router);
+      }
+
+      self.apply_routes(router);
+    })?;
+
+    self.apply_settings(router);
+    router.install_routes(staged);
+
+    if let Some(previous) = previous {
+      for extension in previous.mime_types.keys() {
+        if !self.mime_types.contains_key(extension) {
+          router.mime_types.remove(extension);
+        }
+      }
+
+      for prefix in previous.authorization.keys() {
+        if !self.authorization.contains_key(prefix) {
+          router.unrestrict(prefix);
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn apply_routes(&self, router: &mut Router) {
+    if let Some(favicon) = &self.favicon {
+      router.set_favicon(favicon.as_str());
+    }
+
+    for (route, directory) in &self.static_directories {
+      router.mount_directory(route.as_str(), directory);
+    }
+
+    router.redirect_all(&self.redirects, super::RedirectKind::Permanent);
+  }
+
+  fn apply_settings(&self, router: &mut Router) {
+    for (extension, mime) in &self.mime_types {
+      router.add_mime_type(extension, mime.as_str());
+    }
+
+    for (prefix, policy) in &self.authorization {
+      router.restrict(prefix.as_str(), policy.clone());
+    }
+
+    self.limits.apply(router);
+  }
+
+  fn unapply_routes(&self, router: &mut Router) {
+    if self.favicon.is_some() {
+      router.unmount("/favicon.txt");
+    }
+
+    for route in self.static_directories.keys() {
+      router.unmount(format!("{}/*path", route.trim_end_matches('/')));
+      router.unmount(route);
+    }
+
+    for route in self.redirects.keys() {
+      router.unmount(route);
+    }
+  }
+}
+
+impl Limits {
+  fn apply(&self, router: &mut Router) {
+    if self.max_connections.is_some() {
+      router.set_max_connections(self.max_connections);
+    }
+
+    if self.max_peer_connections.is_some() {
+      router.set_max_peer_connections(self.max_peer_connections);
+    }
+
+    if let Some(seconds) = self.handshake_timeout {
+      router.set_handshake_timeout(Some(Duration::from_secs(seconds)));
+    }
+
+    if let Some(seconds) = self.read_timeout {
+      router.set_read_timeout(Some(Duration::from_secs(seconds)));
+    }
+
+    if let Some(seconds) = self.write_timeout {
+      router.set_write_timeout(Some(Duration::from_secs(seconds)));
+    }
+
+    if let Some(seconds) = self.handler_timeout {
+      router.set_handler_timeout(Some(Duration::from_secs(seconds)));
+    }
+
+    if self.min_write_rate.is_some() {
+      router.set_min_write_rate(self.min_write_rate.and_then(NonZeroU64::new));
+    }
+  }
+}
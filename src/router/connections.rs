@@ -0,0 +1,68 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  collections::HashMap,
+  net::IpAddr,
+  sync::{Arc, Mutex},
+};
+
+/// Tracks the number of open connections per peer IP address.
+#[derive(Clone, Default)]
+pub struct PeerConnections {
+  counts: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl PeerConnections {
+  /// Register a connection from `ip`, returning `None` if the peer already has
+  /// `limit` open connections.
+  pub fn try_acquire(&self, ip: IpAddr, limit: usize) -> Option<PeerPermit> {
+    let mut counts = self.counts.lock().ok()?;
+    let count = counts.entry(ip).or_insert(0);
+
+    if *count >= limit {
+      return None;
+    }
+
+    *count += 1;
+
+    Some(PeerPermit {
+      counts: self.counts.clone(),
+      ip,
+    })
+  }
+}
+
+/// Releases a connection registered with [`PeerConnections`] when dropped.
+pub struct PeerPermit {
+  counts: Arc<Mutex<HashMap<IpAddr, usize>>>,
+  ip:     IpAddr,
+}
+
+impl Drop for PeerPermit {
+  fn drop(&mut self) {
+    if let Ok(mut counts) = self.counts.lock() {
+      if let Some(count) = counts.get_mut(&self.ip) {
+        *count -= 1;
+
+        if *count == 0 {
+          counts.remove(&self.ip);
+        }
+      }
+    }
+  }
+}
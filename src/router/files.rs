@@ -0,0 +1,64 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::path::{Component, Path, PathBuf};
+
+use super::runtime;
+use crate::{mime::MimeTypes, response::Response};
+
+/// Serve the file at `path` within `directory`, falling back to an
+/// `index.gmi` for directories.
+pub async fn serve(
+  directory: PathBuf,
+  path: String,
+  mime_types: MimeTypes,
+) -> Response {
+  let Some(file) = runtime::unblock(move || resolve(&directory, &path)).await
+  else {
+    return Response::not_found("The requested resource could not be found.");
+  };
+
+  runtime::read(&file).await.map_or_else(
+    |_| Response::not_found("The requested resource could not be found."),
+    |content| Response::success_bytes(&content, mime_types.for_path(&file)),
+  )
+}
+
+/// Join `path` onto `directory`, refusing any path which could escape it,
+/// including through symbolic links, and falling back to an `index.gmi` for
+/// directories.
+fn resolve(directory: &Path, path: &str) -> Option<PathBuf> {
+  let mut resolved = directory.to_path_buf();
+
+  for component in Path::new(path.trim_start_matches('/')).components() {
+    match component {
+      Component::Normal(segment) => resolved.push(segment),
+      Component::CurDir => {}
+      _ => return None,
+    }
+  }
+
+  if resolved.is_dir() {
+    resolved.push("index.gmi");
+  }
+
+  resolved
+    .canonicalize()
+    .ok()?
+    .starts_with(directory.canonicalize().ok()?)
+    .then_some(resolved)
+}
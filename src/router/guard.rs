@@ -0,0 +1,43 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{net::IpAddr, time::Duration};
+
+/// How a request is treated before it is routed, ordered from the most to the
+/// least lenient.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Admission {
+  Allow,
+  /// Route the request once the duration has passed.
+  Delay(Duration),
+  /// Answer with `44`, asking the client to wait for the duration.
+  SlowDown(Duration),
+}
+
+/// A gate which built-in modules use to turn away abusive peers, consulted
+/// before a connection's TLS handshake and before each request is routed.
+pub trait Guard: Send + Sync {
+  /// Whether a connection from `ip` is accepted at all.
+  fn accepts(&self, ip: IpAddr) -> bool;
+
+  /// How a request from `ip` is treated before it is routed.
+  fn admit(&self, ip: IpAddr) -> Admission;
+
+  /// Record the status a request from `ip` was answered with, including
+  /// requests which could not be parsed.
+  fn record(&self, ip: IpAddr, status: i32);
+}
@@ -0,0 +1,89 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::net::SocketAddr;
+
+use super::{runtime, shutdown::Shutdown};
+
+/// A handle to a `Router` running in the background, returned by
+/// [`Router::start`](super::Router::start).
+///
+/// Dropping the handle does not stop the server.
+pub struct ServerHandle {
+  addresses: Vec<SocketAddr>,
+  shutdown:  Shutdown,
+  task:      Option<runtime::JoinHandle<Result<(), crate::Error>>>,
+}
+
+impl ServerHandle {
+  pub(super) const fn new(
+    addresses: Vec<SocketAddr>,
+    shutdown: Shutdown,
+    task: runtime::JoinHandle<Result<(), crate::Error>>,
+  ) -> Self {
+    Self {
+      addresses,
+      shutdown,
+      task: Some(task),
+    }
+  }
+
+  /// The address of the first listener, with the port assigned by the
+  /// operating system if the `Router` was bound to port 0.
+  #[must_use]
+  pub fn local_addr(&self) -> SocketAddr { self.addresses[0] }
+
+  /// The addresses of every listener, in the order they were bound.
+  #[must_use]
+  pub fn local_addrs(&self) -> &[SocketAddr] { &self.addresses }
+
+  /// Stop accepting connections, as with
+  /// [`Router::shutdown`](super::Router::shutdown), without waiting for the
+  /// server to stop.
+  pub fn shutdown(&self) { self.shutdown.trigger(); }
+
+  /// Wait for the server to stop, returning the error which stopped it, if
+  /// any.
+  ///
+  /// # Errors
+  ///
+  /// if the server stopped because of an error.
+  pub async fn wait(mut self) -> Result<(), crate::Error> {
+    match self.task.take() {
+      Some(task) => runtime::join(task).await.unwrap_or(Ok(())),
+      None => Ok(()),
+    }
+  }
+
+  /// Shut the server down and wait for its open connections to be handled.
+  ///
+  /// # Errors
+  ///
+  /// if the server stopped because of an error.
+  pub async fn stop(self) -> Result<(), crate::Error> {
+    self.shutdown();
+    self.wait().await
+  }
+}
+
+impl Drop for ServerHandle {
+  fn drop(&mut self) {
+    if let Some(task) = self.task.take() {
+      runtime::detach_handle(task);
+    }
+  }
+}
@@ -0,0 +1,47 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fmt, net::SocketAddr, time::Duration};
+
+/// A failed TLS handshake, as passed to the handshake error callback.
+#[derive(Debug)]
+pub struct HandshakeError {
+  pub peer_address: SocketAddr,
+  /// The address of the listener the connection arrived on.
+  pub listener:     SocketAddr,
+  pub reason:       HandshakeFailure,
+}
+
+/// Why a TLS handshake failed.
+#[derive(Debug)]
+pub enum HandshakeFailure {
+  /// OpenSSL could not complete the handshake; carries its error, whose
+  /// [`ssl_error`](openssl::ssl::Error::ssl_error) holds the error stack.
+  Ssl(openssl::ssl::Error),
+  /// The client did not finish the handshake within the handshake timeout;
+  /// carries the timeout.
+  Timeout(Duration),
+}
+
+impl fmt::Display for HandshakeFailure {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Ssl(e) => write!(f, "{e}"),
+      Self::Timeout(timeout) => write!(f, "timed out after {timeout:?}"),
+    }
+  }
+}
@@ -0,0 +1,48 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::RouteInfo;
+use crate::gemtext::Document;
+
+/// Render a gemtext listing of every route which is not hidden, labelled by
+/// its title and followed by its description.
+///
+/// Routes with parameters or wildcards are listed without a link, as they do
+/// not name a single page.
+pub fn render(routes: &[RouteInfo]) -> String {
+  let mut document = Document::new();
+
+  document.heading(1, "Index");
+
+  for route in routes.iter().filter(|route| !route.hidden) {
+    let label = route.title.as_deref().unwrap_or(&route.pattern);
+
+    document.blank();
+
+    if route.pattern.contains([':', '*']) {
+      document.list_item(format!("{label} ({})", route.pattern));
+    } else {
+      document.link(route.pattern.as_str(), label);
+    }
+
+    if let Some(description) = &route.description {
+      document.text(description);
+    }
+  }
+
+  document.to_string()
+}
@@ -0,0 +1,68 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  any::Any,
+  panic::Location,
+  sync::{Arc, Mutex},
+};
+
+use super::AsyncMutex;
+use crate::module::{AsyncModule, Module};
+
+pub type Modules = Vec<Attached<Mutex<dyn Module + Send>>>;
+pub type AsyncModules = Vec<Attached<AsyncMutex<dyn AsyncModule + Send>>>;
+
+/// A module attached to a `Router`, kept alongside a type-erased handle to
+/// itself so that it can be looked up by its concrete type.
+pub struct Attached<M: ?Sized> {
+  pub name:     Option<String>,
+  pub module:   Arc<M>,
+  pub instance: Arc<dyn Any + Send + Sync>,
+  pub location: &'static Location<'static>,
+}
+
+impl<M: ?Sized> Clone for Attached<M> {
+  fn clone(&self) -> Self {
+    Self {
+      name:     self.name.clone(),
+      module:   self.module.clone(),
+      instance: self.instance.clone(),
+      location: self.location,
+    }
+  }
+}
+
+impl<M: ?Sized> Attached<M> {
+  pub fn downcast<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+    self.instance.clone().downcast::<T>().ok()
+  }
+}
+
+/// Remove and return the modules attached under `name`.
+pub fn detach<M: ?Sized>(
+  modules: &mut Vec<Attached<M>>,
+  name: &str,
+) -> Vec<Attached<M>> {
+  let (detached, attached) = std::mem::take(modules)
+    .into_iter()
+    .partition(|attached| attached.name.as_deref() == Some(name));
+
+  *modules = attached;
+
+  detached
+}
@@ -0,0 +1,85 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{num::NonZeroU64, time::Duration};
+
+use openssl::ssl::SslVersion;
+
+use super::Router;
+
+/// A preset bundle of `Router` settings.
+///
+/// A profile is applied once, when passed to [`Router::with_profile`]; any
+/// setting changed afterwards takes precedence over the profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+  /// Lenient settings for local development: paths are fixed up, URLs are
+  /// not strictly validated, and timeouts and connection limits are disabled.
+  Development,
+  /// Sensible settings for serving a public capsule: strict URL validation,
+  /// the default timeouts, generous connection limits, and TLS 1.2 or newer.
+  Production,
+  /// Conservative settings for exposed deployments: strict URL validation, a
+  /// minimal response meta, short timeouts, a minimum write rate, tight
+  /// connection limits, and no protocol versions older than TLS 1.2.
+  Hardened,
+}
+
+impl Profile {
+  pub(super) fn apply(self, router: &mut Router) {
+    match self {
+      Self::Development => {
+        router
+          .set_fix_path(true)
+          .set_strict_urls(false)
+          .set_minimal_meta(false)
+          .set_handshake_timeout(None)
+          .set_read_timeout(None)
+          .set_write_timeout(None)
+          .set_min_write_rate(None)
+          .set_max_connections(None)
+          .set_max_peer_connections(None)
+          .set_min_tls_version(None);
+      }
+      Self::Production => {
+        router
+          .set_fix_path(false)
+          .set_strict_urls(true)
+          .set_minimal_meta(false)
+          .set_handshake_timeout(Some(Duration::from_secs(10)))
+          .set_read_timeout(Some(Duration::from_secs(10)))
+          .set_write_timeout(Some(Duration::from_secs(30)))
+          .set_max_connections(Some(1024))
+          .set_max_peer_connections(Some(32))
+          .set_min_tls_version(Some(SslVersion::TLS1_2));
+      }
+      Self::Hardened => {
+        router
+          .set_fix_path(false)
+          .set_strict_urls(true)
+          .set_minimal_meta(true)
+          .set_handshake_timeout(Some(Duration::from_secs(5)))
+          .set_read_timeout(Some(Duration::from_secs(5)))
+          .set_write_timeout(Some(Duration::from_secs(15)))
+          .set_min_write_rate(NonZeroU64::new(1024))
+          .set_max_connections(Some(256))
+          .set_max_peer_connections(Some(8))
+          .set_min_tls_version(Some(SslVersion::TLS1_2));
+      }
+    }
+  }
+}
@@ -0,0 +1,53 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::response::Response;
+
+/// The status a redirect is answered with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RedirectKind {
+  /// A 30 (temporary redirect); clients keep requesting the original URL.
+  Temporary,
+  /// A 31 (permanent redirect); clients should request the new URL from now
+  /// on.
+  #[default]
+  Permanent,
+}
+
+impl RedirectKind {
+  pub(super) fn response(
+    self,
+    target: impl Into<String> + AsRef<str>,
+  ) -> Response {
+    match self {
+      Self::Temporary => Response::temporary_redirect(target),
+      Self::Permanent => Response::permanent_redirect(target),
+    }
+  }
+}
+
+/// How a request for a path which only matches a route once corrected by
+/// [`Router::set_fix_path`](super::Router::set_fix_path) is handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FixPathPolicy {
+  /// Serve the corrected route as if it had been requested.
+  #[default]
+  Rewrite,
+  /// Answer with a 31 (permanent redirect) to the corrected path, so clients
+  /// learn the canonical URL.
+  Redirect,
+}
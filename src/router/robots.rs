@@ -0,0 +1,66 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+/// A crawler, identified by one of the virtual user agents of the Gemini
+/// `robots.txt` companion specification, or by its own name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Crawler {
+  /// Every crawler.
+  All,
+  /// Crawlers building public, long-term archives.
+  Archiver,
+  /// Crawlers building search engine indexes.
+  Indexer,
+  /// Crawlers collecting data for research.
+  Researcher,
+  /// Proxies serving Gemini content over the web.
+  WebProxy,
+  /// A crawler which identifies itself by a specific name.
+  Named(String),
+}
+
+impl Crawler {
+  /// The user agent the crawler is addressed by in `robots.txt`.
+  #[must_use]
+  pub fn user_agent(&self) -> &str {
+    match self {
+      Self::All => "*",
+      Self::Archiver => "archiver",
+      Self::Indexer => "indexer",
+      Self::Researcher => "researcher",
+      Self::WebProxy => "webproxy",
+      Self::Named(name) => name,
+    }
+  }
+}
+
+/// Which crawlers may request a route, as published by the
+/// [`Robots`](crate::module::Robots) module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum CrawlPolicy {
+  /// Every crawler may request the route.
+  #[default]
+  Allow,
+  /// The listed crawlers may not request the route.
+  Disallow(Vec<Crawler>),
+}
+
+impl CrawlPolicy {
+  /// Disallow every crawler.
+  #[must_use]
+  pub fn disallow_all() -> Self { Self::Disallow(vec![Crawler::All]) }
+}
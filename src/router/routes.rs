@@ -0,0 +1,262 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{future::IntoFuture, sync::Arc};
+
+use super::{runtime, shutdown::Shutdown, AsyncMutex};
+use crate::{
+  context::RouteContext,
+  handler::{Middleware, RouteResponse},
+  response::{IntoResponse, Response},
+};
+
+/// A description of a mounted route.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RouteInfo {
+  /// The pattern the route was mounted at, e.g. `/posts/:id`.
+  pub pattern:      String,
+  /// A short, human-readable title supplied when the route was mounted.
+  pub title:        Option<String>,
+  /// A human-readable description supplied when the route was mounted.
+  pub description:  Option<String>,
+  /// Which crawlers may request the route.
+  pub crawl_policy: super::CrawlPolicy,
+  /// The maximum time the route's handler may take to respond, overriding
+  /// the router's handler timeout.
+  pub timeout:      Option<std::time::Duration>,
+  /// Whether the route is left out of listings such as the
+  /// [`Sitemap`](crate::module::Sitemap).
+  pub hidden:       bool,
+}
+
+impl RouteInfo {
+  pub(crate) fn new(pattern: String) -> Self {
+    Self {
+      pattern,
+      title: None,
+      description: None,
+      crawl_policy: super::CrawlPolicy::default(),
+      timeout: None,
+      hidden: false,
+    }
+  }
+}
+
+/// Documentation for a route, supplied with
+/// [`Router::mount_with_metadata`](super::Router::mount_with_metadata).
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::router::RouteMetadata;
+///
+/// RouteMetadata::new()
+///   .title("Guestbook")
+///   .description("Leave a message for future visitors");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteMetadata {
+  pub title:       Option<String>,
+  pub description: Option<String>,
+  pub hidden:      bool,
+}
+
+impl RouteMetadata {
+  #[must_use]
+  pub fn new() -> Self { Self::default() }
+
+  #[must_use]
+  pub fn title(mut self, title: impl Into<String>) -> Self {
+    self.title = Some(title.into());
+
+    self
+  }
+
+  #[must_use]
+  pub fn description(mut self, description: impl Into<String>) -> Self {
+    self.description = Some(description.into());
+
+    self
+  }
+
+  /// Leave the route out of listings, while still serving it.
+  #[must_use]
+  pub const fn hidden(mut self) -> Self {
+    self.hidden = true;
+
+    self
+  }
+}
+
+/// A mounted route handler along with its description and middleware.
+#[derive(Clone)]
+pub struct Route {
+  pub info:       RouteInfo,
+  pub handler:    Handler,
+  pub middleware: Vec<Arc<dyn Middleware>>,
+}
+
+impl Route {
+  /// What tells the route's handler apart from every other, whichever pattern
+  /// it is reached through.
+  pub fn handler_id(&self) -> usize { self.handler.id() }
+}
+
+/// A route handler, and how requests are handed to it.
+#[derive(Clone)]
+pub enum Handler {
+  /// Locked for the whole of each request, as it may hold state of its own.
+  Exclusive(Arc<AsyncMutex<Box<dyn RouteResponse>>>),
+  /// Called for many requests at once, each on a thread set aside for
+  /// blocking work, and tracked as a connection of the `Router` it was
+  /// mounted on until it returns.
+  Blocking(
+    Arc<dyn Fn(RouteContext) -> Response + Send + Sync>,
+    Shutdown,
+  ),
+}
+
+impl Handler {
+  /// Wrap a handler which may be synchronous or asynchronous.
+  pub fn exclusive<R>(
+    mut handler: impl FnMut(RouteContext) -> R + Send + Sync + 'static,
+  ) -> Self
+  where
+    R: IntoFuture + Send + 'static,
+    <R as IntoFuture>::Output: IntoResponse,
+    <R as IntoFuture>::IntoFuture: Send,
+  {
+    Self::Exclusive(Arc::new(AsyncMutex::new(Box::new(
+      move |context: RouteContext| {
+        let response = handler(context).into_future();
+
+        async move { response.await.into_response() }
+      },
+    ))))
+  }
+
+  /// Respond to `context`.
+  ///
+  /// A blocking handler can not be stopped once it has been called, so it
+  /// holds up shutdown until it returns, even if its request is abandoned,
+  /// e.g., because the handler timed out.
+  pub async fn call(&self, context: RouteContext) -> Response {
+    match self {
+      Self::Exclusive(handler) => handler.lock().await.call(context).await,
+      Self::Blocking(handler, shutdown) => {
+        let handler = handler.clone();
+        let connection = shutdown.connection();
+
+        runtime::unblock(move || {
+          let _connection = connection;
+
+          handler(context)
+        })
+        .await
+      }
+    }
+  }
+
+  fn id(&self) -> usize {
+    match self {
+      Self::Exclusive(handler) => Arc::as_ptr(handler).cast::<()>() as usize,
+      Self::Blocking(handler, _) => Arc::as_ptr(handler).cast::<()>() as usize,
+    }
+  }
+}
+
+/// The routes mounted on a `Router`, in the order they were mounted.
+#[derive(Clone, Default)]
+pub struct RouteTable {
+  matcher: matchit::Router<Route>,
+  routes:  Vec<Route>,
+}
+
+impl RouteTable {
+  /// Add a route, or describe why it conflicts with the mounted routes.
+  pub fn insert(&mut self, route: Route) -> Result<(), String> {
+    self
+      .matcher
+      .insert(route.info.pattern.clone(), route.clone())
+      .map_err(|e| format!("could not mount {}: {e}", route.info.pattern))?;
+    self.routes.push(route);
+
+    Ok(())
+  }
+
+  /// Remove the route mounted at `pattern`, returning whether there was one.
+  pub fn remove(&mut self, pattern: &str) -> bool {
+    let count = self.routes.len();
+
+    self.routes.retain(|route| route.info.pattern != pattern);
+
+    if self.routes.len() == count {
+      return false;
+    }
+
+    self.rebuild();
+
+    true
+  }
+
+  /// Swap the handler of the route mounted at `pattern`, returning whether
+  /// there was one.
+  pub fn replace(&mut self, pattern: &str, handler: Handler) -> bool {
+    let Some(route) = self
+      .routes
+      .iter_mut()
+      .find(|route| route.info.pattern == pattern)
+    else {
+      return false;
+    };
+
+    route.handler = handler;
+
+    self.rebuild();
+
+    true
+  }
+
+  /// `matchit` can not remove or modify routes in place, so the matcher is
+  /// rebuilt from the remaining routes.
+  fn rebuild(&mut self) {
+    let mut matcher = matchit::Router::new();
+
+    for route in &self.routes {
+      // Every route was accepted when it was first mounted, and removing
+      // routes can not introduce a conflict.
+      let _ = matcher.insert(route.info.pattern.clone(), route.clone());
+    }
+
+    self.matcher = matcher;
+  }
+
+  pub fn at<'m, 'p>(
+    &'m self,
+    path: &'p str,
+  ) -> Result<matchit::Match<'m, 'p, &'m Route>, matchit::MatchError> {
+    self.matcher.at(path)
+  }
+
+  pub fn fix_path(&self, path: &str) -> Option<String> {
+    self.matcher.fix_path(path)
+  }
+
+  pub fn routes(&self) -> Vec<RouteInfo> {
+    self.routes.iter().map(|route| route.info.clone()).collect()
+  }
+}
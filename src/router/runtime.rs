@@ -0,0 +1,198 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{future::Future, io, sync::Arc, time::Duration};
+
+#[cfg(feature = "async-std")]
+pub use async_lock::{Semaphore, SemaphoreGuardArc as Permit};
+#[cfg(feature = "async-std")]
+pub use async_std::{
+  net::{TcpListener, TcpStream},
+  task::spawn,
+};
+#[cfg(feature = "smol")]
+pub use smol::{
+  lock::{Semaphore, SemaphoreGuardArc as Permit},
+  net::{TcpListener, TcpStream},
+  spawn,
+};
+#[cfg(feature = "tokio")]
+pub use tokio::{
+  net::{TcpListener, TcpStream},
+  spawn,
+  sync::{OwnedSemaphorePermit as Permit, Semaphore},
+};
+
+/// A spawned task which resolves to its output.
+#[cfg(feature = "async-std")]
+pub type JoinHandle<T> = async_std::task::JoinHandle<T>;
+#[cfg(feature = "smol")]
+pub type JoinHandle<T> = smol::Task<T>;
+#[cfg(feature = "tokio")]
+pub type JoinHandle<T> = tokio::task::JoinHandle<T>;
+
+/// Wait for a spawned task's output, or `None` if the task was cancelled.
+///
+/// A panic within the task is resumed in the waiting task.
+pub async fn join<T>(task: JoinHandle<T>) -> Option<T> {
+  #[cfg(feature = "tokio")]
+  let output = match task.await {
+    Ok(output) => Some(output),
+    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+    Err(_) => None,
+  };
+  #[cfg(any(feature = "async-std", feature = "smol"))]
+  let output = Some(task.await);
+
+  output
+}
+
+/// Let a spawned task run to completion after its handle is dropped.
+pub fn detach_handle<T>(task: JoinHandle<T>) {
+  #[cfg(any(feature = "tokio", feature = "async-std"))]
+  drop(task);
+  #[cfg(feature = "smol")]
+  task.detach();
+}
+
+/// Spawn `future` without waiting on its output.
+///
+/// `smol` cancels tasks whose handle is dropped, so they must be detached
+/// explicitly.
+pub fn detach<F>(future: F)
+where F: Future<Output = ()> + Send + 'static {
+  #[cfg(any(feature = "tokio", feature = "async-std"))]
+  drop(spawn(future));
+  #[cfg(feature = "smol")]
+  spawn(future).detach();
+}
+
+/// Convert a non-blocking standard library listener into this runtime's
+/// listener.
+// Only the conversion of the `async-std` runtime can not fail.
+#[cfg_attr(feature = "async-std", allow(clippy::unnecessary_wraps))]
+pub fn listener_from_std(
+  listener: std::net::TcpListener,
+) -> io::Result<TcpListener> {
+  #[cfg(feature = "tokio")]
+  let listener = TcpListener::from_std(listener)?;
+  #[cfg(feature = "async-std")]
+  let listener = TcpListener::from(listener);
+  #[cfg(feature = "smol")]
+  let listener = TcpListener::try_from(listener)?;
+
+  Ok(listener)
+}
+
+/// Await `future`, failing with [`io::ErrorKind::TimedOut`] if it does not
+/// complete within `duration`. No timeout is applied if `duration` is `None`.
+pub async fn timeout<F: Future>(
+  duration: Option<Duration>,
+  future: F,
+) -> io::Result<F::Output> {
+  let Some(duration) = duration else {
+    return Ok(future.await);
+  };
+
+  #[cfg(feature = "tokio")]
+  let output = tokio::time::timeout(duration, future).await.ok();
+  #[cfg(feature = "async-std")]
+  let output = async_std::future::timeout(duration, future).await.ok();
+  #[cfg(feature = "smol")]
+  let output = smol::future::or(async { Some(future.await) }, async {
+    smol::Timer::after(duration).await;
+
+    None
+  })
+  .await;
+
+  output.ok_or_else(|| {
+    io::Error::new(io::ErrorKind::TimedOut, "operation timed out")
+  })
+}
+
+/// Wait for a permit from `semaphore`, held until the returned permit is
+/// dropped.
+pub async fn acquire(semaphore: &Arc<Semaphore>) -> Option<Permit> {
+  #[cfg(feature = "tokio")]
+  let permit = semaphore.clone().acquire_owned().await.ok();
+  #[cfg(any(feature = "async-std", feature = "smol"))]
+  let permit = Some(semaphore.acquire_arc().await);
+
+  permit
+}
+
+/// Read the whole of the file at `path`.
+pub async fn read(path: impl AsRef<std::path::Path>) -> io::Result<Vec<u8>> {
+  #[cfg(feature = "tokio")]
+  let content = tokio::fs::read(path.as_ref()).await;
+  #[cfg(feature = "async-std")]
+  let content = async_std::fs::read(path.as_ref()).await;
+  #[cfg(feature = "smol")]
+  let content = smol::fs::read(path.as_ref()).await;
+
+  content
+}
+
+/// Suspend the current task for `duration`.
+pub async fn sleep(duration: Duration) {
+  #[cfg(feature = "tokio")]
+  tokio::time::sleep(duration).await;
+  #[cfg(feature = "async-std")]
+  async_std::task::sleep(duration).await;
+  #[cfg(feature = "smol")]
+  smol::Timer::after(duration).await;
+}
+
+/// Run the blocking `function` on a thread set aside for blocking work, so
+/// that it does not stall the executor.
+///
+/// A panic within `function` is resumed in the waiting task.
+pub async fn unblock<T: Send + 'static>(
+  function: impl FnOnce() -> T + Send + 'static,
+) -> T {
+  #[cfg(feature = "tokio")]
+  let output = match tokio::task::spawn_blocking(function).await {
+    Ok(output) => output,
+    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+    Err(e) => panic!("blocking task was cancelled: {e}"),
+  };
+  #[cfg(feature = "async-std")]
+  let output = async_std::task::spawn_blocking(function).await;
+  #[cfg(feature = "smol")]
+  let output = smol::unblock(function).await;
+
+  output
+}
+
+/// Wait for whichever of `future` and `other` completes first.
+pub async fn or<T>(
+  future: impl Future<Output = T>,
+  other: impl Future<Output = T>,
+) -> T {
+  let mut future = std::pin::pin!(future);
+  let mut other = std::pin::pin!(other);
+
+  std::future::poll_fn(|context| {
+    if let std::task::Poll::Ready(output) = future.as_mut().poll(context) {
+      return std::task::Poll::Ready(output);
+    }
+
+    other.as_mut().poll(context)
+  })
+  .await
+}
@@ -0,0 +1,42 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fmt::Display, future::poll_fn};
+
+use tower_service::Service;
+
+use crate::{context::RouteContext, response::Response};
+
+/// Drive `service` to readiness and call it, answering with a temporary
+/// failure if it errors.
+pub async fn call<S>(mut service: S, context: RouteContext) -> Response
+where
+  S: Service<RouteContext, Response = Response>,
+  S::Error: Display,
+{
+  let request_id = context.request_id.clone();
+  let response = match poll_fn(|context| service.poll_ready(context)).await {
+    Ok(()) => service.call(context).await,
+    Err(e) => Err(e),
+  };
+
+  response.unwrap_or_else(|e| {
+    error!("[{request_id}] service error: {e}");
+
+    Response::temporary_failure("The service could not handle the request.")
+  })
+}
@@ -0,0 +1,118 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  future::poll_fn,
+  sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+    Mutex,
+  },
+  task::{Poll, Waker},
+};
+
+/// Shared shutdown state of a `Router` and all of its clones.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+  inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+  triggered:   AtomicBool,
+  connections: AtomicUsize,
+  wakers:      Mutex<Vec<Waker>>,
+}
+
+impl Shutdown {
+  pub fn trigger(&self) {
+    if !self.inner.triggered.swap(true, Ordering::SeqCst) {
+      self.wake();
+    }
+  }
+
+  pub fn is_triggered(&self) -> bool {
+    self.inner.triggered.load(Ordering::SeqCst)
+  }
+
+  /// Resolve once shutdown has been triggered.
+  pub async fn triggered(&self) { self.until(Self::is_triggered).await }
+
+  /// Resolve once every tracked connection has closed.
+  pub async fn drained(&self) {
+    self
+      .until(|shutdown| shutdown.inner.connections.load(Ordering::SeqCst) == 0)
+      .await;
+  }
+
+  /// Track an open connection until the returned guard is dropped.
+  pub fn connection(&self) -> Connection {
+    self.inner.connections.fetch_add(1, Ordering::SeqCst);
+
+    Connection {
+      shutdown: self.clone(),
+    }
+  }
+
+  async fn until(&self, condition: fn(&Self) -> bool) {
+    poll_fn(|context| {
+      if condition(self) {
+        return Poll::Ready(());
+      }
+
+      if let Ok(mut wakers) = self.inner.wakers.lock() {
+        if !wakers.iter().any(|waker| waker.will_wake(context.waker())) {
+          wakers.push(context.waker().clone());
+        }
+      }
+
+      // The condition may have changed while the waker was being registered.
+      if condition(self) {
+        Poll::Ready(())
+      } else {
+        Poll::Pending
+      }
+    })
+    .await;
+  }
+
+  fn wake(&self) {
+    if let Ok(mut wakers) = self.inner.wakers.lock() {
+      for waker in wakers.drain(..) {
+        waker.wake();
+      }
+    }
+  }
+}
+
+pub struct Connection {
+  shutdown: Shutdown,
+}
+
+impl Drop for Connection {
+  fn drop(&mut self) {
+    if self
+      .shutdown
+      .inner
+      .connections
+      .fetch_sub(1, Ordering::SeqCst)
+      == 1
+    {
+      self.shutdown.wake();
+    }
+  }
+}
@@ -0,0 +1,121 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  io,
+  net::{SocketAddr, ToSocketAddrs},
+  time::Duration,
+};
+
+#[cfg(feature = "tokio")]
+use socket2::SockRef;
+use socket2::{Domain, Protocol, Socket, TcpKeepalive, Type};
+
+/// Options applied to the listening socket and to every accepted socket.
+#[derive(Clone, Copy, Debug)]
+pub struct SocketOptions {
+  /// Set `TCP_NODELAY` on accepted sockets, disabling Nagle's algorithm.
+  pub nodelay:   bool,
+  /// Enable TCP keepalive on accepted sockets, probing after the given idle
+  /// time.
+  pub keepalive: Option<Duration>,
+  /// The maximum number of pending connections queued by the listener.
+  pub backlog:   u32,
+}
+
+impl Default for SocketOptions {
+  fn default() -> Self {
+    Self {
+      nodelay:   false,
+      keepalive: None,
+      backlog:   1024,
+    }
+  }
+}
+
+impl SocketOptions {
+  pub(super) fn bind(
+    &self,
+    address: impl ToSocketAddrs,
+  ) -> io::Result<std::net::TcpListener> {
+    let mut last_error = None;
+
+    for address in address.to_socket_addrs()? {
+      match self.bind_address(address) {
+        Ok(listener) => return Ok(listener),
+        Err(e) => last_error = Some(e),
+      }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "could not resolve to any address",
+      )
+    }))
+  }
+
+  fn bind_address(
+    &self,
+    address: SocketAddr,
+  ) -> io::Result<std::net::TcpListener> {
+    let socket = Socket::new(
+      Domain::for_address(address),
+      Type::STREAM,
+      Some(Protocol::TCP),
+    )?;
+
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+
+    if let Some(keepalive) = self.keepalive {
+      socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(keepalive))?;
+    }
+
+    socket.bind(&address.into())?;
+    socket.listen(i32::try_from(self.backlog).unwrap_or(i32::MAX))?;
+    socket.set_nonblocking(true)?;
+
+    Ok(socket.into())
+  }
+
+  #[cfg(feature = "tokio")]
+  pub(super) fn configure(
+    &self,
+    stream: &super::runtime::TcpStream,
+  ) -> io::Result<()> {
+    let socket = SockRef::from(stream);
+
+    socket.set_nodelay(self.nodelay)?;
+
+    if let Some(keepalive) = self.keepalive {
+      socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(keepalive))?;
+    }
+
+    Ok(())
+  }
+
+  // `async-std` and `smol` streams do not expose their underlying socket, so
+  // keepalive is inherited from the listening socket instead.
+  #[cfg(any(feature = "async-std", feature = "smol"))]
+  pub(super) fn configure(
+    &self,
+    stream: &super::runtime::TcpStream,
+  ) -> io::Result<()> {
+    stream.set_nodelay(self.nodelay)
+  }
+}
@@ -0,0 +1,187 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  collections::{HashMap, VecDeque},
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+/// How many of a route's most recent response times its percentiles are
+/// computed from.
+const LATENCY_SAMPLES: usize = 1024;
+
+#[derive(Default)]
+struct Recorder {
+  hits:      u64,
+  errors:    u64,
+  latencies: VecDeque<Duration>,
+}
+
+/// Per-route request statistics, shared by a [`Router`](super::Router) and
+/// every handle obtained from [`Router::stats`](super::Router::stats).
+///
+/// Requests are counted under the pattern of the route they matched;
+/// requests which matched no route are not counted.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut router = windmark::router::Router::new();
+/// let stats = router.stats();
+///
+/// router.mount("/popular", move |_| {
+///   let mut document = windmark::gemtext::Document::new();
+///
+///   for route in stats.routes().iter().take(10) {
+///     document.list_item(format!("{}: {} hits", route.pattern, route.hits));
+///   }
+///
+///   windmark::response::Response::success(document.to_string())
+/// });
+/// ```
+#[derive(Clone, Default)]
+pub struct Stats {
+  routes: Arc<Mutex<HashMap<String, Recorder>>>,
+}
+
+/// A snapshot of the statistics of a single route.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RouteStats {
+  pub pattern: String,
+  pub hits:    u64,
+  /// Responses with a temporary or permanent failure status (`4x` or `5x`).
+  pub errors:  u64,
+  /// The median response time of recent requests.
+  pub p50:     Duration,
+  /// The 99th percentile response time of recent requests.
+  pub p99:     Duration,
+}
+
+impl RouteStats {
+  /// The fraction of hits which were errors, from `0.0` to `1.0`.
+  #[must_use]
+  #[allow(clippy::cast_precision_loss)]
+  pub fn error_rate(&self) -> f64 {
+    if self.hits == 0 {
+      0.0
+    } else {
+      self.errors as f64 / self.hits as f64
+    }
+  }
+}
+
+impl Stats {
+  pub(crate) fn record(&self, pattern: &str, status: i32, elapsed: Duration) {
+    let Ok(mut routes) = self.routes.lock() else {
+      return;
+    };
+
+    // Avoid allocating the pattern for routes which have already been seen.
+    if !routes.contains_key(pattern) {
+      routes.insert(pattern.to_string(), Recorder::default());
+    }
+
+    let Some(recorder) = routes.get_mut(pattern) else {
+      return;
+    };
+
+    recorder.hits += 1;
+
+    if (40..60).contains(&status) {
+      recorder.errors += 1;
+    }
+
+    if recorder.latencies.len() == LATENCY_SAMPLES {
+      recorder.latencies.pop_front();
+    }
+
+    recorder.latencies.push_back(elapsed);
+  }
+
+  /// The statistics of every route which has been requested, most requested
+  /// first.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the statistics' lock is poisoned.
+  #[must_use]
+  pub fn routes(&self) -> Vec<RouteStats> {
+    let mut routes = self
+      .routes
+      .lock()
+      .unwrap()
+      .iter()
+      .map(|(pattern, recorder)| snapshot(pattern, recorder))
+      .collect::<Vec<_>>();
+
+    routes.sort_by(|left, right| {
+      right
+        .hits
+        .cmp(&left.hits)
+        .then_with(|| left.pattern.cmp(&right.pattern))
+    });
+
+    routes
+  }
+
+  /// The statistics of the route mounted at `pattern`, if it has been
+  /// requested.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the statistics' lock is poisoned.
+  #[must_use]
+  pub fn route(&self, pattern: &str) -> Option<RouteStats> {
+    self
+      .routes
+      .lock()
+      .unwrap()
+      .get(pattern)
+      .map(|recorder| snapshot(pattern, recorder))
+  }
+
+  /// Forget every route's statistics.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the statistics' lock is poisoned.
+  pub fn clear(&self) { self.routes.lock().unwrap().clear(); }
+}
+
+fn snapshot(pattern: &str, recorder: &Recorder) -> RouteStats {
+  let mut latencies = recorder.latencies.iter().copied().collect::<Vec<_>>();
+
+  latencies.sort_unstable();
+
+  RouteStats {
+    pattern: pattern.to_string(),
+    hits:    recorder.hits,
+    errors:  recorder.errors,
+    p50:     percentile(&latencies, 50),
+    p99:     percentile(&latencies, 99),
+  }
+}
+
+/// The nearest-rank `percent`th percentile of the sorted `latencies`.
+fn percentile(latencies: &[Duration], percent: usize) -> Duration {
+  if latencies.is_empty() {
+    return Duration::ZERO;
+  }
+
+  latencies[(latencies.len() * percent).div_ceil(100).max(1) - 1]
+}
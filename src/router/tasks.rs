@@ -0,0 +1,124 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+use std::{future::Future, panic::Location, pin::Pin, time::Duration};
+
+use super::{
+  runtime,
+  shutdown::Shutdown,
+  unwind::{panic_message, CatchUnwind},
+};
+
+type Job = Box<dyn FnMut() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// A job run periodically while a `Router` is running.
+pub struct Task {
+  interval:  Duration,
+  immediate: bool,
+  job:       Job,
+  location:  &'static Location<'static>,
+}
+
+impl Task {
+  pub fn new(
+    interval: Duration,
+    job: Job,
+    location: &'static Location<'static>,
+  ) -> Self {
+    Self {
+      interval,
+      immediate: false,
+      job,
+      location,
+    }
+  }
+
+  /// Also run the job as soon as the task starts.
+  pub const fn immediately(mut self) -> Self {
+    self.immediate = true;
+
+    self
+  }
+
+  /// Run the job every interval until `shutdown` is triggered, letting a run
+  /// in progress complete.
+  fn spawn(mut self, shutdown: Shutdown) -> runtime::JoinHandle<()> {
+    runtime::spawn(async move {
+      if self.immediate && !shutdown.is_triggered() {
+        self.run().await;
+      }
+
+      while runtime::or(
+        async {
+          runtime::sleep(self.interval).await;
+
+          true
+        },
+        async {
+          shutdown.triggered().await;
+
+          false
+        },
+      )
+      .await
+      {
+        self.run().await;
+      }
+    })
+  }
+
+  async fn run(&mut self) {
+    if let Err(payload) = CatchUnwind::new((self.job)()).await {
+      error!(
+        "background task registered at {} panicked: {}",
+        self.location,
+        panic_message(&*payload)
+      );
+    }
+  }
+}
+
+/// The background tasks of a `Router`, which are started when it starts
+/// accepting connections.
+#[derive(Default)]
+pub struct Tasks {
+  pending:  Vec<Task>,
+  running:  Vec<runtime::JoinHandle<()>>,
+  shutdown: Option<Shutdown>,
+}
+
+impl Tasks {
+  /// Start `task` if the tasks have been started, or once they are.
+  pub fn add(&mut self, task: Task) {
+    match &self.shutdown {
+      Some(shutdown) => self.running.push(task.spawn(shutdown.clone())),
+      None => self.pending.push(task),
+    }
+  }
+
+  pub fn start(&mut self, shutdown: &Shutdown) {
+    self.shutdown = Some(shutdown.clone());
+
+    for task in std::mem::take(&mut self.pending) {
+      self.running.push(task.spawn(shutdown.clone()));
+    }
+  }
+
+  /// The running tasks, which stop once shutdown is triggered.
+  pub fn stop(&mut self) -> Vec<runtime::JoinHandle<()>> {
+    std::mem::take(&mut self.running)
+  }
+}
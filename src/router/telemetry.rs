@@ -0,0 +1,101 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Optional `tracing` spans. Without the `tracing` feature, every span is a
+//! no-op.
+
+use std::{future::Future, net::SocketAddr, time::Duration};
+
+#[cfg(feature = "tracing")]
+use tracing::{field::Empty, Instrument};
+
+/// A span covering either a connection or a single request.
+#[derive(Clone)]
+pub struct Span {
+  #[cfg(feature = "tracing")]
+  span: tracing::Span,
+}
+
+#[cfg(feature = "tracing")]
+impl Span {
+  pub fn connection(peer_address: SocketAddr, listener: SocketAddr) -> Self {
+    Self {
+      span: tracing::info_span!(
+        "connection",
+        peer_address = %peer_address,
+        listener = %listener,
+      ),
+    }
+  }
+
+  /// Fields of a request span are recorded as they become known.
+  pub fn request(request_id: &str) -> Self {
+    Self {
+      span: tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        url = Empty,
+        route = Empty,
+        status = Empty,
+        elapsed_ms = Empty,
+      ),
+    }
+  }
+
+  pub fn record_url(&self, url: &url::Url) {
+    self.span.record("url", url.as_str());
+  }
+
+  pub fn record_route(&self, pattern: &str) {
+    self.span.record("route", pattern);
+  }
+
+  pub fn record_response(&self, status: i32, elapsed: Duration) {
+    self.span.record("status", status);
+    #[allow(clippy::cast_possible_truncation)]
+    self.span.record("elapsed_ms", elapsed.as_millis() as u64);
+  }
+}
+
+#[cfg(not(feature = "tracing"))]
+#[allow(clippy::unused_self)]
+impl Span {
+  pub const fn connection(_: SocketAddr, _: SocketAddr) -> Self { Self {} }
+
+  /// Fields of a request span are recorded as they become known.
+  pub const fn request(_: &str) -> Self { Self {} }
+
+  pub const fn record_url(&self, _: &url::Url) {}
+
+  pub const fn record_route(&self, _: &str) {}
+
+  pub const fn record_response(&self, _: i32, _: Duration) {}
+}
+
+impl Span {
+  /// Run `future` within this span.
+  #[allow(clippy::unused_self)]
+  pub fn instrument<F: Future>(
+    &self,
+    future: F,
+  ) -> impl Future<Output = F::Output> {
+    #[cfg(feature = "tracing")]
+    let future = future.instrument(self.span.clone());
+
+    future
+  }
+}
@@ -0,0 +1,183 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use openssl::{
+  asn1::Asn1Time,
+  error::ErrorStack,
+  pkey::PKey,
+  ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod},
+  x509::X509,
+};
+
+/// The source of a certificate or private key.
+#[derive(Clone)]
+pub enum Material {
+  /// A path to a PEM-encoded file.
+  File(String),
+  /// A path to a PEM-encoded file containing a certificate followed by any
+  /// intermediate certificates.
+  ChainFile(String),
+  /// PEM-encoded content, possibly containing a certificate chain.
+  Pem(Vec<u8>),
+  /// DER-encoded content.
+  Der(Vec<u8>),
+}
+
+impl Default for Material {
+  fn default() -> Self { Self::File(String::new()) }
+}
+
+impl Material {
+  /// Check that the material was set and, if it is a file, that it can be
+  /// read, so that misconfigurations are reported more clearly than by
+  /// OpenSSL.
+  pub fn check(&self, kind: &'static str) -> Result<(), crate::Error> {
+    match self {
+      Self::File(path) | Self::ChainFile(path) if path.is_empty() =>
+        Err(crate::Error::MissingTlsMaterial(kind)),
+      Self::File(path) | Self::ChainFile(path) =>
+        std::fs::File::open(path).map(|_| ()).map_err(|source| {
+          crate::Error::UnreadableTlsMaterial {
+            kind,
+            path: path.clone(),
+            source,
+          }
+        }),
+      Self::Pem(content) | Self::Der(content) if content.is_empty() =>
+        Err(crate::Error::MissingTlsMaterial(kind)),
+      Self::Pem(_) | Self::Der(_) => Ok(()),
+    }
+  }
+
+  pub fn apply_certificate(
+    &self,
+    builder: &mut SslAcceptorBuilder,
+  ) -> Result<(), ErrorStack> {
+    match self {
+      Self::File(path) => builder.set_certificate_file(path, SslFiletype::PEM),
+      Self::ChainFile(path) => builder.set_certificate_chain_file(path),
+      Self::Pem(pem) => {
+        let mut chain = X509::stack_from_pem(pem)?.into_iter();
+        let certificate = chain.next().map_or_else(
+          // Surfaces OpenSSL's "no start line" error.
+          || X509::from_pem(pem),
+          Ok,
+        )?;
+
+        builder.set_certificate(&certificate)?;
+
+        for intermediate in chain {
+          builder.add_extra_chain_cert(intermediate)?;
+        }
+
+        Ok(())
+      }
+      Self::Der(der) => {
+        let certificate = X509::from_der(der)?;
+
+        builder.set_certificate(&certificate)
+      }
+    }
+  }
+
+  pub fn apply_private_key(
+    &self,
+    builder: &mut SslAcceptorBuilder,
+  ) -> Result<(), ErrorStack> {
+    match self {
+      Self::File(path) | Self::ChainFile(path) =>
+        builder.set_private_key_file(path, SslFiletype::PEM),
+      Self::Pem(pem) => {
+        let private_key = PKey::private_key_from_pem(pem)?;
+
+        builder.set_private_key(&private_key)
+      }
+      Self::Der(der) => {
+        let private_key = PKey::private_key_from_der(der)
+          .or_else(|_| PKey::private_key_from_pkcs8(der))?;
+
+        builder.set_private_key(&private_key)
+      }
+    }
+  }
+}
+
+/// The baseline protocol and cipher configuration of the built-in
+/// `SslAcceptor`.
+///
+/// Individual protocol versions and ciphers can be further restricted with
+/// the `Router`'s TLS setters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TlsPolicy {
+  /// Mozilla's "intermediate" configuration, compatible with the widest range
+  /// of Gemini clients, including those limited to TLS 1.2.
+  #[default]
+  Intermediate,
+  /// Mozilla's "modern" configuration, which only accepts TLS 1.3.
+  Modern,
+}
+
+impl TlsPolicy {
+  pub(super) fn builder(self) -> Result<SslAcceptorBuilder, ErrorStack> {
+    match self {
+      Self::Intermediate => SslAcceptor::mozilla_intermediate(SslMethod::tls()),
+      Self::Modern => SslAcceptor::mozilla_modern_v5(SslMethod::tls()),
+    }
+  }
+}
+
+/// How a `Router` closes a connection once its response has been written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CloseMode {
+  /// Send a TLS `close_notify` alert, which tells the client the response is
+  /// complete, then shut down the TCP connection for writing, as the Gemini
+  /// specification requires. This is the same for every runtime.
+  #[default]
+  CloseNotify,
+  /// Close as with `CloseNotify`, then wait a few seconds for the client to
+  /// close its side of the connection.
+  ///
+  /// Dropping a connection while the client still has data in flight can
+  /// make the operating system reset it, and some clients then discard the
+  /// end of the response and report it as incomplete.
+  Linger,
+  /// Shut down the TCP connection without sending `close_notify`, for
+  /// clients which mishandle it. Clients can not tell a response closed
+  /// this way from a truncated one.
+  TcpOnly,
+}
+
+/// The validity of the certificate served by a `Router`.
+#[derive(Clone, Debug)]
+pub struct CertificateExpiry {
+  /// The certificate's "notAfter" time, e.g. `"Jan  1 00:00:00 2030 GMT"`.
+  pub not_after:      String,
+  /// Whole days until the certificate expires; negative once it has expired.
+  pub days_remaining: i32,
+}
+
+impl CertificateExpiry {
+  pub(super) fn new(acceptor: &SslAcceptor) -> Option<Self> {
+    let not_after = acceptor.context().certificate()?.not_after();
+    let difference = Asn1Time::days_from_now(0).ok()?.diff(not_after).ok()?;
+
+    Some(Self {
+      not_after:      not_after.to_string(),
+      days_remaining: difference.days,
+    })
+  }
+}
@@ -0,0 +1,168 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+  any::Any,
+  future::Future,
+  panic::{self, AssertUnwindSafe, Location},
+  pin::Pin,
+  task::{Context, Poll},
+};
+
+/// A future which resolves to `Err` with the panic payload if the wrapped
+/// future panics while being polled.
+pub struct CatchUnwind<F> {
+  future: F,
+}
+
+impl<F> CatchUnwind<F> {
+  pub const fn new(future: F) -> Self {
+    Self {
+      future,
+    }
+  }
+}
+
+impl<F> Future for CatchUnwind<F>
+where F: Future + Unpin
+{
+  type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+  fn poll(
+    mut self: Pin<&mut Self>,
+    context: &mut Context<'_>,
+  ) -> Poll<Self::Output> {
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+      Pin::new(&mut self.future).poll(context)
+    })) {
+      Ok(Poll::Pending) => Poll::Pending,
+      Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+      Err(payload) => Poll::Ready(Err(payload)),
+    }
+  }
+}
+
+/// Extract a human-readable message from a panic payload.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+  payload
+    .downcast_ref::<&str>()
+    .map(|message| (*message).to_string())
+    .or_else(|| payload.downcast_ref::<String>().cloned())
+    .unwrap_or_else(|| "unknown panic payload".to_string())
+}
+
+/// A handler along with the location it was registered from, used to point
+/// diagnostics at the offending registration.
+pub struct Registered<T: ?Sized> {
+  pub handler:  Box<T>,
+  pub location: &'static Location<'static>,
+}
+
+impl<T: ?Sized> Registered<T> {
+  // `Location::caller` is not yet stable in `const` contexts on the pinned
+  // toolchain.
+  #[allow(clippy::missing_const_for_fn)]
+  #[track_caller]
+  pub fn new(handler: Box<T>) -> Self {
+    Self {
+      handler,
+      location: Location::caller(),
+    }
+  }
+}
+
+/// Run `operation`, logging a diagnostic and returning `None` if it panics.
+pub fn catch<T>(
+  kind: &str,
+  location: &Location<'_>,
+  request_id: &str,
+  path: &str,
+  operation: impl FnOnce() -> T,
+) -> Option<T> {
+  match panic::catch_unwind(AssertUnwindSafe(operation)) {
+    Ok(output) => Some(output),
+    Err(payload) => {
+      error!(
+        "[{request_id}] {kind} registered at {location} panicked on {path}: {}",
+        panic_message(&*payload)
+      );
+
+      None
+    }
+  }
+}
+
+/// Run a module hook, logging a diagnostic if it panics.
+pub fn module_hook(
+  hook: &str,
+  location: &Location<'_>,
+  request_id: Option<&str>,
+  operation: impl FnOnce(),
+) {
+  if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(operation)) {
+    module_failure(
+      hook,
+      location,
+      request_id,
+      "panicked",
+      &panic_message(&*payload),
+    );
+  }
+}
+
+/// Await an asynchronous module hook, logging a diagnostic if it fails or
+/// panics.
+pub async fn async_module_hook<F>(
+  hook: &str,
+  location: &Location<'_>,
+  request_id: Option<&str>,
+  future: F,
+) where
+  F: Future<Output = crate::module::ModuleResult> + Unpin,
+{
+  match CatchUnwind::new(future).await {
+    Ok(Ok(())) => {}
+    Ok(Err(e)) =>
+      module_failure(hook, location, request_id, "failed", &e.to_string()),
+    Err(payload) =>
+      module_failure(
+        hook,
+        location,
+        request_id,
+        "panicked",
+        &panic_message(&*payload),
+      ),
+  }
+}
+
+fn module_failure(
+  hook: &str,
+  location: &Location<'_>,
+  request_id: Option<&str>,
+  outcome: &str,
+  message: &str,
+) {
+  match request_id {
+    Some(request_id) =>
+      error!(
+        "[{request_id}] {hook} of the module attached at {location} \
+         {outcome}: {message}"
+      ),
+    None =>
+      error!("{hook} of the module attached at {location} {outcome}: {message}"),
+  }
+}
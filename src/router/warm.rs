@@ -0,0 +1,45 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+use std::{
+  collections::HashMap,
+  sync::{Arc, RwLock},
+};
+
+use crate::response::Response;
+
+/// The responses rendered ahead of time for warmed routes, by path.
+#[derive(Clone, Default)]
+pub struct Warmed {
+  responses: Arc<RwLock<HashMap<String, Response>>>,
+}
+
+impl Warmed {
+  /// The response rendered for `path`, if it is warmed and has rendered
+  /// successfully.
+  pub fn get(&self, path: &str) -> Option<Response> {
+    self.responses.read().ok()?.get(path).cloned()
+  }
+
+  pub fn set(&self, path: &str, response: Option<Response>) {
+    if let Ok(mut responses) = self.responses.write() {
+      match response {
+        Some(response) => responses.insert(path.to_string(), response),
+        None => responses.remove(path),
+      };
+    }
+  }
+}
@@ -0,0 +1,305 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Secret key management for signing tokens
+
+use std::{
+  sync::{Arc, RwLock},
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use openssl::{
+  error::ErrorStack,
+  hash::MessageDigest,
+  pkey::PKey,
+  sign::Signer,
+  x509::X509Ref,
+};
+
+use crate::{known_clients::KnownClients, utilities::hex};
+
+#[derive(Default)]
+struct Keys {
+  current:  Vec<u8>,
+  previous: Vec<Vec<u8>>,
+}
+
+/// A shared set of secret keys used to sign and verify tokens.
+///
+/// New signatures are always produced with the current key, while
+/// verification also accepts signatures produced by any previous key, so
+/// secrets can be rotated without invalidating every outstanding token at
+/// once.
+///
+/// Cloning a `SecretKeys` is cheap and every clone observes rotations.
+#[derive(Clone, Default)]
+pub struct SecretKeys {
+  keys: Arc<RwLock<Keys>>,
+}
+
+impl SecretKeys {
+  /// Create a new set of secret keys from a current key and any number of
+  /// previous keys.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// windmark::secret::SecretKeys::new("current", ["previous"]); 
+  /// ```
+  pub fn new<K>(
+    current: impl AsRef<[u8]>,
+    previous: impl IntoIterator<Item = K>,
+  ) -> Self
+  where
+    K: AsRef<[u8]>,
+  {
+    let keys = Self::default();
+
+    keys.set(current, previous);
+
+    keys
+  }
+
+  /// Create a new set of secret keys with a randomly generated current key.
+  ///
+  /// # Panics
+  ///
+  /// if random bytes could not be generated.
+  #[must_use]
+  pub fn random() -> Self {
+    let mut current = [0u8; 32];
+
+    openssl::rand::rand_bytes(&mut current).unwrap();
+
+    Self::new(current, Vec::<Vec<u8>>::new())
+  }
+
+  /// Replace the current and previous keys.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the keys cannot be replaced.
+  pub fn set<K>(
+    &self,
+    current: impl AsRef<[u8]>,
+    previous: impl IntoIterator<Item = K>,
+  ) where
+    K: AsRef<[u8]>,
+  {
+    let mut keys = self.keys.write().unwrap();
+
+    keys.current = current.as_ref().to_vec();
+    keys.previous = previous
+      .into_iter()
+      .map(|key| key.as_ref().to_vec())
+      .collect();
+  }
+
+  /// Make `current` the signing key, demoting the existing current key to the
+  /// front of the previous keys.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the keys cannot be rotated.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// let keys = windmark::secret::SecretKeys::new("old", [""; 0]);
+  /// let token = keys.sign("message").unwrap();
+  ///
+  /// keys.rotate("new");
+  ///
+  /// assert!(keys.verify("message", &token));
+  /// ```
+  pub fn rotate(&self, current: impl AsRef<[u8]>) {
+    let mut keys = self.keys.write().unwrap();
+    let previous =
+      std::mem::replace(&mut keys.current, current.as_ref().to_vec());
+
+    keys.previous.insert(0, previous);
+  }
+
+  /// Drop all previous keys, invalidating any signature not produced by the
+  /// current key.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the keys cannot be modified.
+  pub fn retire_previous(&self) { self.keys.write().unwrap().previous.clear(); }
+
+  /// Sign a message with the current key, returning a hex-encoded
+  /// HMAC-SHA256 signature.
+  ///
+  /// # Errors
+  ///
+  /// if the signature could not be computed.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the keys cannot be read.
+  pub fn sign(&self, message: impl AsRef<[u8]>) -> Result<String, ErrorStack> {
+    Ok(hex(&hmac(
+      &self.keys.read().unwrap().current,
+      message.as_ref(),
+    )?))
+  }
+
+  /// Verify a hex-encoded signature against the current and all previous
+  /// keys.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the keys cannot be read.
+  pub fn verify(&self, message: impl AsRef<[u8]>, signature: &str) -> bool {
+    let keys = self.keys.read().unwrap();
+
+    std::iter::once(&keys.current)
+      .chain(keys.previous.iter())
+      .filter_map(|key| hmac(key, message.as_ref()).ok())
+      .any(|expected| {
+        let expected = hex(&expected);
+
+        expected.len() == signature.len()
+          && openssl::memcmp::eq(expected.as_bytes(), signature.as_bytes())
+      })
+  }
+
+  /// Issue a token which lets the client presenting `certificate` perform
+  /// `action`, such as `"delete-post/1"`, until `lifetime` has passed.
+  ///
+  /// Tokens guard routes which change state against requests forged by
+  /// crafted links: embed the token in the link to the route, and check it
+  /// with [`SecretKeys::verify_token`] before acting. Tokens only contain
+  /// digits, hexadecimal, and a `.`, so they can be placed in a path or query
+  /// as is.
+  ///
+  /// # Errors
+  ///
+  /// if the certificate could not be fingerprinted or the token could not be
+  /// signed.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the keys cannot be read.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use std::time::Duration;
+  ///
+  /// use windmark::{context::RouteContext, response::Response};
+  ///
+  /// let mut router = windmark::router::Router::new();
+  /// let keys = router.secret_keys();
+  ///
+  /// router.mount("/posts/:id", {
+  ///   let keys = keys.clone();
+  ///
+  ///   move |context: RouteContext| {
+  ///     let Some(certificate) = &context.certificate else {
+  ///       return Response::client_certificate_required("Who are you?");
+  ///     };
+  ///     let id = context.parameters.get("id").unwrap();
+  ///     let token = keys
+  ///       .issue_token(
+  ///         &format!("delete/{id}"),
+  ///         certificate,
+  ///         Duration::from_secs(600),
+  ///       )
+  ///       .unwrap();
+  ///
+  ///     Response::success(format!("=> /delete/{id}/{token} Delete"))
+  ///   }
+  /// });
+  /// router.mount("/delete/:id/:token", move |context: RouteContext| {
+  ///   let id = context.parameters.get("id").unwrap();
+  ///   let token = context.parameters.get("token").unwrap();
+  ///
+  ///   match &context.certificate {
+  ///     Some(certificate)
+  ///       if keys.verify_token(token, &format!("delete/{id}"), certificate) =>
+  ///       Response::success("Deleted."),
+  ///     _ => Response::certificate_not_authorised("This link has expired."),
+  ///   }
+  /// });
+  /// ```
+  pub fn issue_token(
+    &self,
+    action: &str,
+    certificate: &X509Ref,
+    lifetime: Duration,
+  ) -> Result<String, ErrorStack> {
+    let expires = now().saturating_add(lifetime.as_secs());
+    let message = token_message(action, certificate, expires)
+      .ok_or_else(ErrorStack::get)?;
+
+    Ok(format!("{expires}.{}", self.sign(message)?))
+  }
+
+  /// Whether `token` was issued by [`SecretKeys::issue_token`] for `action`
+  /// and the client presenting `certificate`, and has not yet expired.
+  ///
+  /// # Panics
+  ///
+  /// May panic if the keys cannot be read.
+  #[must_use]
+  pub fn verify_token(
+    &self,
+    token: &str,
+    action: &str,
+    certificate: &X509Ref,
+  ) -> bool {
+    let Some((expires, signature)) = token.split_once('.') else {
+      return false;
+    };
+    let Ok(expires) = expires.parse::<u64>() else {
+      return false;
+    };
+
+    expires >= now()
+      && token_message(action, certificate, expires)
+        .is_some_and(|message| self.verify(message, signature))
+  }
+}
+
+/// The message an action token signs.
+fn token_message(
+  action: &str,
+  certificate: &X509Ref,
+  expires: u64,
+) -> Option<String> {
+  Some(format!(
+    "{action}\n{}\n{expires}",
+    KnownClients::fingerprint(certificate)?
+  ))
+}
+
+fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs()
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+  let key = PKey::hmac(key)?;
+  let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+
+  signer.update(message)?;
+  signer.sign_to_vec()
+}
@@ -0,0 +1,172 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Key-value persistence shared by modules and handlers
+
+#![allow(clippy::significant_drop_tightening)]
+
+#[cfg(feature = "file-store")]
+mod file;
+#[cfg(feature = "sled")]
+mod sled;
+
+use std::{collections::BTreeMap, io, sync::Mutex};
+
+use async_trait::async_trait;
+
+#[cfg(feature = "file-store")]
+pub use self::file::FileStore;
+#[cfg(feature = "sled")]
+pub use self::sled::SledStore;
+
+/// A store of byte values by string key, e.g., for sessions, counters, or
+/// the records of a module.
+///
+/// Stores are shared between tasks, so each operation must be atomic on its
+/// own. Keys are conventionally namespaced with a prefix, e.g.,
+/// `"known-clients/"`.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::store::{KeyValueStore, MemoryStore};
+///
+/// async fn visit(store: &dyn KeyValueStore) -> std::io::Result<i64> {
+///   store.increment("visits/home", 1).await
+/// }
+///
+/// # let _ = visit(&MemoryStore::new());
+/// ```
+#[async_trait]
+pub trait KeyValueStore: Send + Sync {
+  /// The value of `key`, if any.
+  async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+  /// Set the value of `key`, replacing any value set before.
+  async fn set(&self, key: &str, value: Vec<u8>) -> io::Result<()>;
+
+  /// Set the value of `key` only if it has none, returning whether it was
+  /// set.
+  ///
+  /// The default implementation is not atomic, so stores which can check and
+  /// set atomically should override it.
+  async fn set_if_absent(&self, key: &str, value: Vec<u8>) -> io::Result<bool> {
+    if self.get(key).await?.is_some() {
+      return Ok(false);
+    }
+
+    self.set(key, value).await?;
+
+    Ok(true)
+  }
+
+  /// Remove `key`, returning whether it had a value.
+  async fn remove(&self, key: &str) -> io::Result<bool>;
+
+  /// Every key which starts with `prefix`, in order.
+  async fn keys(&self, prefix: &str) -> io::Result<Vec<String>>;
+
+  /// Add `by` to the counter at `key`, which starts at zero, returning its
+  /// new value.
+  ///
+  /// The default implementation is not atomic, so stores which can increment
+  /// atomically should override it.
+  async fn increment(&self, key: &str, by: i64) -> io::Result<i64> {
+    let value = counter(self.get(key).await?.as_deref()) + by;
+
+    self.set(key, value.to_string().into_bytes()).await?;
+
+    Ok(value)
+  }
+}
+
+/// The value of a counter stored as its decimal representation.
+fn counter(value: Option<&[u8]>) -> i64 {
+  value
+    .and_then(|value| std::str::from_utf8(value).ok())
+    .and_then(|value| value.parse().ok())
+    .unwrap_or_default()
+}
+
+/// A store held in memory, lost when the process exits.
+#[derive(Default)]
+pub struct MemoryStore {
+  values: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+  #[must_use]
+  pub fn new() -> Self { Self::default() }
+
+  fn lock(
+    &self,
+  ) -> io::Result<std::sync::MutexGuard<'_, BTreeMap<String, Vec<u8>>>> {
+    self
+      .values
+      .lock()
+      .map_err(|_| io::Error::other("the store is poisoned"))
+  }
+}
+
+#[async_trait]
+impl KeyValueStore for MemoryStore {
+  async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+    Ok(self.lock()?.get(key).cloned())
+  }
+
+  async fn set(&self, key: &str, value: Vec<u8>) -> io::Result<()> {
+    self.lock()?.insert(key.to_string(), value);
+
+    Ok(())
+  }
+
+  async fn set_if_absent(&self, key: &str, value: Vec<u8>) -> io::Result<bool> {
+    let mut values = self.lock()?;
+
+    if values.contains_key(key) {
+      return Ok(false);
+    }
+
+    values.insert(key.to_string(), value);
+
+    Ok(true)
+  }
+
+  async fn remove(&self, key: &str) -> io::Result<bool> {
+    Ok(self.lock()?.remove(key).is_some())
+  }
+
+  async fn keys(&self, prefix: &str) -> io::Result<Vec<String>> {
+    Ok(
+      self
+        .lock()?
+        .range(prefix.to_string()..)
+        .take_while(|(key, _)| key.starts_with(prefix))
+        .map(|(key, _)| key.clone())
+        .collect(),
+    )
+  }
+
+  async fn increment(&self, key: &str, by: i64) -> io::Result<i64> {
+    let mut values = self.lock()?;
+    let value = counter(values.get(key).map(Vec::as_slice)) + by;
+
+    values.insert(key.to_string(), value.to_string().into_bytes());
+
+    Ok(value)
+  }
+}
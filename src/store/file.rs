@@ -0,0 +1,206 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+#![allow(clippy::significant_drop_tightening)]
+
+use std::{
+  collections::BTreeMap,
+  io,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use percent_encoding::{
+  percent_decode_str,
+  utf8_percent_encode,
+  NON_ALPHANUMERIC,
+};
+
+use super::{counter, KeyValueStore, MemoryStore};
+use crate::router::runtime;
+
+/// A store held in memory and saved to a file whenever it changes, suited to
+/// small stores which change infrequently.
+///
+/// A change is only made in memory once it has been saved, and the file is
+/// replaced whole, so a failed or interrupted save leaves both as they were.
+/// Saves are made on a blocking task, one at a time, while reads are served
+/// from memory.
+///
+/// The file holds one percent-encoded key and value per line, separated by a
+/// space.
+///
+/// Only available with the `file-store` feature.
+#[derive(Clone)]
+pub struct FileStore {
+  inner: Arc<Inner>,
+}
+
+struct Inner {
+  memory: MemoryStore,
+  path:   PathBuf,
+  saving: Mutex<()>,
+}
+
+impl FileStore {
+  /// Load the store saved at `path`. A missing file is treated as empty.
+  ///
+  /// # Errors
+  ///
+  /// if the file exists but could not be read.
+  pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+    let path = path.as_ref();
+    let mut values = BTreeMap::new();
+
+    match std::fs::read_to_string(path) {
+      Ok(content) =>
+        for (key, value) in content
+          .lines()
+          .filter_map(|line| line.trim().split_once(' '))
+        {
+          values.insert(
+            percent_decode_str(key).decode_utf8_lossy().into_owned(),
+            percent_decode_str(value).collect(),
+          );
+        },
+      Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+      Err(e) => return Err(e),
+    }
+
+    Ok(Self {
+      inner: Arc::new(Inner {
+        memory: MemoryStore {
+          values: Mutex::new(values),
+        },
+        path:   path.to_path_buf(),
+        saving: Mutex::new(()),
+      }),
+    })
+  }
+
+  /// Make `change` to a copy of the store's values on a blocking task, then,
+  /// unless it returns `None`, save the copy and make it in memory.
+  ///
+  /// Changes are made one at a time so that saves can not overtake one
+  /// another, but the values are only locked to copy and replace them, so
+  /// reads never wait on a save.
+  async fn commit<T: Send + 'static>(
+    &self,
+    change: impl FnOnce(&mut BTreeMap<String, Vec<u8>>) -> Option<T>
+      + Send
+      + 'static,
+  ) -> io::Result<Option<T>> {
+    let inner = self.inner.clone();
+
+    runtime::unblock(move || {
+      let _saving = inner
+        .saving
+        .lock()
+        .map_err(|_| io::Error::other("the store is poisoned"))?;
+      let mut values = inner.memory.lock()?.clone();
+      let Some(output) = change(&mut values) else {
+        return Ok(None);
+      };
+
+      save(&inner.path, &values)?;
+      *inner.memory.lock()? = values;
+
+      Ok(Some(output))
+    })
+    .await
+  }
+}
+
+/// Replace the file at `path` with `values`.
+fn save(path: &Path, values: &BTreeMap<String, Vec<u8>>) -> io::Result<()> {
+  let content =
+    values
+      .iter()
+      .fold(String::new(), |mut content, (key, value)| {
+        content.extend(utf8_percent_encode(key, NON_ALPHANUMERIC));
+        content.push(' ');
+        content
+          .extend(percent_encoding::percent_encode(value, NON_ALPHANUMERIC));
+        content.push('\n');
+
+        content
+      });
+
+  crate::utilities::write_atomically(path, content.as_bytes())
+}
+
+#[async_trait]
+impl KeyValueStore for FileStore {
+  async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+    self.inner.memory.get(key).await
+  }
+
+  async fn set(&self, key: &str, value: Vec<u8>) -> io::Result<()> {
+    let key = key.to_string();
+
+    self
+      .commit(move |values| {
+        values.insert(key, value);
+
+        Some(())
+      })
+      .await
+      .map(drop)
+  }
+
+  async fn set_if_absent(&self, key: &str, value: Vec<u8>) -> io::Result<bool> {
+    let key = key.to_string();
+
+    self
+      .commit(move |values| {
+        (!values.contains_key(&key)).then(|| {
+          values.insert(key, value);
+        })
+      })
+      .await
+      .map(|set| set.is_some())
+  }
+
+  async fn remove(&self, key: &str) -> io::Result<bool> {
+    let key = key.to_string();
+
+    self
+      .commit(move |values| values.remove(&key).map(drop))
+      .await
+      .map(|removed| removed.is_some())
+  }
+
+  async fn keys(&self, prefix: &str) -> io::Result<Vec<String>> {
+    self.inner.memory.keys(prefix).await
+  }
+
+  async fn increment(&self, key: &str, by: i64) -> io::Result<i64> {
+    let key = key.to_string();
+
+    self
+      .commit(move |values| {
+        let value = counter(values.get(&key).map(Vec::as_slice)) + by;
+
+        values.insert(key, value.to_string().into_bytes());
+
+        Some(value)
+      })
+      .await
+      .map(Option::unwrap_or_default)
+  }
+}
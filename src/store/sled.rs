@@ -0,0 +1,145 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{io, path::Path};
+
+use async_trait::async_trait;
+
+use super::{counter, KeyValueStore};
+use crate::router::runtime;
+
+/// A store kept in a [sled](https://docs.rs/sled) tree, suited to large stores
+/// or ones which change often.
+///
+/// Each operation is atomic in sled itself and runs on a blocking task.
+///
+/// Only available with the `sled` feature.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use windmark::store::{KeyValueStore, SledStore};
+///
+/// # async fn example() -> std::io::Result<()> {
+/// let store = SledStore::open("windmark.sled")?;
+///
+/// store.increment("visits/home", 1).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SledStore {
+  tree: sled::Tree,
+}
+
+impl SledStore {
+  /// Open, or create, the sled database at `path` and keep the store in its
+  /// default tree.
+  ///
+  /// # Errors
+  ///
+  /// if the database could not be opened.
+  pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+    Ok(Self::new(&*sled::open(path)?))
+  }
+
+  /// Keep the store in `tree`, e.g., a named tree of a database which is
+  /// shared with other users.
+  #[must_use]
+  pub fn new(tree: &sled::Tree) -> Self {
+    Self {
+      tree: tree.clone()
+    }
+  }
+
+  /// Run `operation` on the store's tree on a blocking task.
+  async fn run<T: Send + 'static>(
+    &self,
+    operation: impl FnOnce(&sled::Tree) -> sled::Result<T> + Send + 'static,
+  ) -> io::Result<T> {
+    let tree = self.tree.clone();
+
+    Ok(runtime::unblock(move || operation(&tree)).await?)
+  }
+}
+
+#[async_trait]
+impl KeyValueStore for SledStore {
+  async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+    let key = key.to_string();
+
+    self
+      .run(move |tree| Ok(tree.get(key)?.map(|value| value.to_vec())))
+      .await
+  }
+
+  async fn set(&self, key: &str, value: Vec<u8>) -> io::Result<()> {
+    let key = key.to_string();
+
+    self
+      .run(move |tree| tree.insert(key, value).map(drop))
+      .await
+  }
+
+  async fn set_if_absent(&self, key: &str, value: Vec<u8>) -> io::Result<bool> {
+    let key = key.to_string();
+
+    self
+      .run(move |tree| {
+        Ok(
+          tree
+            .compare_and_swap(key, None::<&[u8]>, Some(value))?
+            .is_ok(),
+        )
+      })
+      .await
+  }
+
+  async fn remove(&self, key: &str) -> io::Result<bool> {
+    let key = key.to_string();
+
+    self.run(move |tree| Ok(tree.remove(key)?.is_some())).await
+  }
+
+  async fn keys(&self, prefix: &str) -> io::Result<Vec<String>> {
+    let prefix = prefix.to_string();
+
+    self
+      .run(move |tree| {
+        tree
+          .scan_prefix(prefix)
+          .keys()
+          .map(|key| Ok(String::from_utf8_lossy(&key?).into_owned()))
+          .collect()
+      })
+      .await
+  }
+
+  async fn increment(&self, key: &str, by: i64) -> io::Result<i64> {
+    let key = key.to_string();
+
+    self
+      .run(move |tree| {
+        let value = tree.update_and_fetch(key, |value| {
+          Some((counter(value) + by).to_string().into_bytes())
+        })?;
+
+        Ok(counter(value.as_deref()))
+      })
+      .await
+  }
+}
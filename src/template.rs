@@ -0,0 +1,702 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A minimal templating language for gemtext
+//!
+//! Templates are plain gemtext files with the following additions:
+//!
+//! - `{{ name }}`: a value from the [`Context`], escaped so that it can not
+//!   start a new line or change the type of the line it appears on
+//! - `{{ name | raw }}`: a value inserted as is
+//! - `{% if name %}`, `{% if not name %}`, `{% else %}`, and `{% endif %}`:
+//!   content included if a value is a non-empty text, `true`, or a non-empty
+//!   list
+//! - `{% for item in name %}` and `{% endfor %}`: content repeated for each
+//!   value of a list, whose fields can be accessed as `{{ item.field }}`
+//! - `{% include "nav.gmi" %}`: another template, rendered with the same values
+//!
+//! A tag which is alone on its line leaves no blank line behind.
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  io,
+  path::{Component, Path, PathBuf},
+  sync::{Arc, RwLock},
+};
+
+/// The deepest templates may include one another, guarding against cycles.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A value which can be used within a template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+  Text(String),
+  Bool(bool),
+  List(Vec<Self>),
+  Map(Context),
+}
+
+impl Value {
+  fn is_truthy(&self) -> bool {
+    match self {
+      Self::Text(text) => !text.is_empty(),
+      Self::Bool(value) => *value,
+      Self::List(values) => !values.is_empty(),
+      Self::Map(context) => !context.values.is_empty(),
+    }
+  }
+}
+
+impl From<&str> for Value {
+  fn from(text: &str) -> Self { Self::Text(text.to_string()) }
+}
+
+impl From<String> for Value {
+  fn from(text: String) -> Self { Self::Text(text) }
+}
+
+impl From<bool> for Value {
+  fn from(value: bool) -> Self { Self::Bool(value) }
+}
+
+impl From<i64> for Value {
+  fn from(value: i64) -> Self { Self::Text(value.to_string()) }
+}
+
+impl From<usize> for Value {
+  fn from(value: usize) -> Self { Self::Text(value.to_string()) }
+}
+
+impl From<Context> for Value {
+  fn from(context: Context) -> Self { Self::Map(context) }
+}
+
+impl<T: Into<Self>> From<Vec<T>> for Value {
+  fn from(values: Vec<T>) -> Self {
+    Self::List(values.into_iter().map(Into::into).collect())
+  }
+}
+
+/// The named values a template is rendered with.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::template::Context;
+///
+/// Context::new().insert("title", "My Gemlog").insert(
+///   "posts",
+///   vec![Context::new()
+///     .insert("title", "Hello, World!")
+///     .insert("url", "/gemlog/hello.gmi")],
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Context {
+  values: BTreeMap<String, Value>,
+}
+
+impl Context {
+  #[must_use]
+  pub fn new() -> Self { Self::default() }
+
+  /// Set the value of `name`, replacing any value set before.
+  #[must_use]
+  pub fn insert(
+    mut self,
+    name: impl Into<String>,
+    value: impl Into<Value>,
+  ) -> Self {
+    self.values.insert(name.into(), value.into());
+
+    self
+  }
+
+  #[must_use]
+  pub fn get(&self, name: &str) -> Option<&Value> { self.values.get(name) }
+}
+
+/// Why a template could not be rendered.
+#[derive(Debug)]
+pub enum TemplateError {
+  /// The name of the template leaves the template directory.
+  InvalidName(String),
+  /// The template could not be read.
+  Io { name: String, source: io::Error },
+  /// The template is malformed.
+  Syntax { name: String, message: String },
+  /// The template refers to a value which was not provided, or is of the wrong
+  /// kind.
+  Undefined { name: String, value: String },
+  /// Templates include one another too deeply, likely in a cycle.
+  IncludeDepth(String),
+  /// A template response was produced, but no templates were set on the
+  /// `Router`.
+  Unconfigured,
+}
+
+impl std::fmt::Display for TemplateError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::InvalidName(name) => write!(f, "invalid template name: {name}"),
+      Self::Io {
+        name,
+        source,
+      } => write!(f, "could not read template {name}: {source}"),
+      Self::Syntax {
+        name,
+        message,
+      } => write!(f, "malformed template {name}: {message}"),
+      Self::Undefined {
+        name,
+        value,
+      } => write!(f, "template {name} refers to undefined value {value}"),
+      Self::IncludeDepth(name) =>
+        write!(f, "template {name} includes templates too deeply"),
+      Self::Unconfigured => write!(f, "no templates are set on the router"),
+    }
+  }
+}
+
+impl std::error::Error for TemplateError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Io {
+        source, ..
+      } => Some(source),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug)]
+enum Node {
+  Text(String),
+  Value {
+    path: String,
+    raw:  bool,
+  },
+  If {
+    path:      String,
+    negate:    bool,
+    then:      Vec<Self>,
+    otherwise: Vec<Self>,
+  },
+  For {
+    binding: String,
+    path:    String,
+    body:    Vec<Self>,
+  },
+  Include(String),
+}
+
+/// A directory of templates, which are parsed once and then cached.
+///
+/// Every clone shares the same cache.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{
+///   response::Response,
+///   template::{Context, Templates},
+/// };
+///
+/// windmark::router::Router::new()
+///   .set_templates(Templates::new("templates"))
+///   .mount("/", |_| {
+///     Response::template("index.gmi", Context::new().insert("name", "Fuwn"))
+///   });
+/// ```
+#[derive(Clone, Debug)]
+pub struct Templates {
+  directory: PathBuf,
+  caching:   bool,
+  cache:     Arc<RwLock<HashMap<String, Arc<Vec<Node>>>>>,
+}
+
+impl Templates {
+  /// Load templates from `directory`, caching them once parsed.
+  #[must_use]
+  pub fn new(directory: impl Into<PathBuf>) -> Self {
+    Self {
+      directory: directory.into(),
+      caching:   true,
+      cache:     Arc::default(),
+    }
+  }
+
+  /// Set whether parsed templates are cached, or read from disk each time
+  /// they are rendered, e.g., while they are being written.
+  pub fn set_caching(&mut self, caching: bool) -> &mut Self {
+    self.caching = caching;
+
+    self
+  }
+
+  /// Forget every cached template, so that they are read from disk again.
+  ///
+  /// Clones of a `Templates` share their cache, so a clone can be cleared by
+  /// a [`Watch`](crate::module::Watch) when the templates change.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use windmark::{module::Watch, template::Templates};
+  ///
+  /// let templates = Templates::new("templates");
+  /// let cache = templates.clone();
+  ///
+  /// windmark::router::Router::new()
+  ///   .set_templates(templates)
+  ///   .attach(Watch::new("templates").on_change(move || cache.clear_cache()));
+  /// ```
+  pub fn clear_cache(&self) {
+    if let Ok(mut cache) = self.cache.write() {
+      cache.clear();
+    }
+  }
+
+  /// Render the template `name`, relative to the template directory, with
+  /// `context`.
+  ///
+  /// # Errors
+  ///
+  /// if the template could not be read or is malformed, or refers to a value
+  /// which `context` does not provide.
+  pub fn render(
+    &self,
+    name: &str,
+    context: &Context,
+  ) -> Result<String, TemplateError> {
+    let mut output = String::new();
+
+    self.render_into(&mut output, name, &Scope::root(context), 0)?;
+
+    Ok(output)
+  }
+
+  fn render_into(
+    &self,
+    output: &mut String,
+    name: &str,
+    scope: &Scope<'_>,
+    depth: usize,
+  ) -> Result<(), TemplateError> {
+    if depth > MAX_INCLUDE_DEPTH {
+      return Err(TemplateError::IncludeDepth(name.to_string()));
+    }
+
+    let nodes = self.load(name)?;
+
+    Renderer {
+      templates: self,
+      name,
+      depth,
+    }
+    .render(output, &nodes, scope)
+  }
+
+  fn load(&self, name: &str) -> Result<Arc<Vec<Node>>, TemplateError> {
+    if let Some(nodes) = self
+      .cache
+      .read()
+      .ok()
+      .and_then(|cache| cache.get(name).cloned())
+    {
+      return Ok(nodes);
+    }
+
+    if Path::new(name)
+      .components()
+      .any(|component| !matches!(component, Component::Normal(_)))
+    {
+      return Err(TemplateError::InvalidName(name.to_string()));
+    }
+
+    let source =
+      std::fs::read_to_string(self.directory.join(name)).map_err(|source| {
+        TemplateError::Io {
+          name: name.to_string(),
+          source,
+        }
+      })?;
+    let nodes = Arc::new(parse(name, &source)?);
+
+    if self.caching {
+      if let Ok(mut cache) = self.cache.write() {
+        cache.insert(name.to_string(), nodes.clone());
+      }
+    }
+
+    Ok(nodes)
+  }
+}
+
+/// The values visible at a point in a template: the loop variables bound
+/// around it, innermost first, then the root context.
+struct Scope<'a> {
+  binding: Option<(&'a str, &'a Value)>,
+  parent:  Option<&'a Self>,
+  root:    &'a Context,
+}
+
+impl<'a> Scope<'a> {
+  const fn root(context: &'a Context) -> Self {
+    Self {
+      binding: None,
+      parent:  None,
+      root:    context,
+    }
+  }
+
+  const fn bind(&'a self, name: &'a str, value: &'a Value) -> Self {
+    Self {
+      binding: Some((name, value)),
+      parent:  Some(self),
+      root:    self.root,
+    }
+  }
+
+  fn lookup(&self, path: &str) -> Option<&'a Value> {
+    let mut segments = path.split('.');
+    let head = segments.next()?;
+    let mut value = self.resolve(head)?;
+
+    for segment in segments {
+      match value {
+        Value::Map(context) => value = context.get(segment)?,
+        _ => return None,
+      }
+    }
+
+    Some(value)
+  }
+
+  fn resolve(&self, name: &str) -> Option<&'a Value> {
+    match self.binding {
+      Some((binding, value)) if binding == name => Some(value),
+      _ =>
+        self
+          .parent
+          .map_or_else(|| self.root.get(name), |parent| parent.resolve(name)),
+    }
+  }
+}
+
+struct Renderer<'a> {
+  templates: &'a Templates,
+  name:      &'a str,
+  depth:     usize,
+}
+
+impl Renderer<'_> {
+  fn render(
+    &self,
+    output: &mut String,
+    nodes: &[Node],
+    scope: &Scope<'_>,
+  ) -> Result<(), TemplateError> {
+    for node in nodes {
+      match node {
+        Node::Text(text) => output.push_str(text),
+        Node::Value {
+          path,
+          raw,
+        } => {
+          let text = match scope.lookup(path) {
+            Some(Value::Text(text)) => text.clone(),
+            Some(Value::Bool(value)) => value.to_string(),
+            _ => return Err(self.undefined(path)),
+          };
+
+          if *raw {
+            output.push_str(&text);
+          } else {
+            let at_line_start = output.is_empty() || output.ends_with('\n');
+
+            output.push_str(&escape(&text, at_line_start));
+          }
+        }
+        Node::If {
+          path,
+          negate,
+          then,
+          otherwise,
+        } => {
+          let truthy = scope.lookup(path).is_some_and(Value::is_truthy);
+
+          self.render(
+            output,
+            if truthy == *negate { otherwise } else { then },
+            scope,
+          )?;
+        }
+        Node::For {
+          binding,
+          path,
+          body,
+        } => {
+          let Some(Value::List(values)) = scope.lookup(path) else {
+            return Err(self.undefined(path));
+          };
+
+          for value in values {
+            self.render(output, body, &scope.bind(binding, value))?;
+          }
+        }
+        Node::Include(name) =>
+          self
+            .templates
+            .render_into(output, name, scope, self.depth + 1)?,
+      }
+    }
+
+    Ok(())
+  }
+
+  fn undefined(&self, path: &str) -> TemplateError {
+    TemplateError::Undefined {
+      name:  self.name.to_string(),
+      value: path.to_string(),
+    }
+  }
+}
+
+/// Escape `text` so that it stays on the line it is inserted into, and, if it
+/// starts that line, does not change the line's type.
+fn escape(text: &str, at_line_start: bool) -> String {
+  let mut escaped = text.replace("\r\n", " ").replace(['\r', '\n'], " ");
+
+  if at_line_start
+    && ["=>", "#", "* ", ">", "```"]
+      .iter()
+      .any(|prefix| escaped.starts_with(prefix))
+  {
+    // A zero-width space keeps the line a text line without changing how it
+    // reads.
+    escaped.insert(0, '\u{200b}');
+  }
+
+  escaped
+}
+
+enum Token<'a> {
+  Text(&'a str),
+  Value(&'a str),
+  Tag(&'a str),
+}
+
+fn tokenise<'a>(
+  name: &str,
+  source: &'a str,
+) -> Result<Vec<Token<'a>>, TemplateError> {
+  let mut tokens = Vec::new();
+  let mut rest = source;
+
+  while let Some(start) =
+    rest.find("{{").into_iter().chain(rest.find("{%")).min()
+  {
+    let is_tag = rest[start..].starts_with("{%");
+    let close = if is_tag { "%}" } else { "}}" };
+    let Some(length) = rest[start + 2..].find(close) else {
+      return Err(syntax(
+        name,
+        format!("unclosed {}", &rest[start..start + 2]),
+      ));
+    };
+    let inner = rest[start + 2..start + 2 + length].trim();
+
+    if start > 0 {
+      tokens.push(Token::Text(&rest[..start]));
+    }
+
+    tokens.push(if is_tag {
+      Token::Tag(inner)
+    } else {
+      Token::Value(inner)
+    });
+    rest = &rest[start + 4 + length..];
+  }
+
+  if !rest.is_empty() {
+    tokens.push(Token::Text(rest));
+  }
+
+  Ok(tokens)
+}
+
+/// Remove the indentation before, and the line break after, each tag which is
+/// alone on its line.
+fn trim_standalone_tags(tokens: &mut [Token<'_>]) {
+  // How many bytes to remove from the start and the end of each text.
+  let mut trims = vec![(0, 0); tokens.len()];
+
+  for index in 0..tokens.len() {
+    if !matches!(tokens[index], Token::Tag(_)) {
+      continue;
+    }
+
+    let before = match index.checked_sub(1).map(|previous| &tokens[previous]) {
+      None => Some(0),
+      Some(Token::Text(text)) => {
+        let line = text.rfind('\n').map_or(*text, |end| &text[end + 1..]);
+
+        (line.trim().is_empty() && (line.len() < text.len() || index == 1))
+          .then_some(line.len())
+      }
+      Some(_) => None,
+    };
+    let after = match tokens.get(index + 1) {
+      None => Some(0),
+      Some(Token::Text(text)) => {
+        let line = text.find('\n').map_or(*text, |end| &text[..=end]);
+
+        (line.trim().is_empty()
+          && (line.ends_with('\n') || index + 2 == tokens.len()))
+        .then_some(line.len())
+      }
+      Some(_) => None,
+    };
+
+    if let (Some(before), Some(after)) = (before, after) {
+      if index > 0 {
+        trims[index - 1].1 = before;
+      }
+
+      if index + 1 < tokens.len() {
+        trims[index + 1].0 = after;
+      }
+    }
+  }
+
+  for (token, (start, end)) in tokens.iter_mut().zip(trims) {
+    if let Token::Text(text) = token {
+      *text = text.get(start..text.len() - end).unwrap_or_default();
+    }
+  }
+}
+
+fn parse(name: &str, source: &str) -> Result<Vec<Node>, TemplateError> {
+  let mut tokens = tokenise(name, source)?;
+
+  trim_standalone_tags(&mut tokens);
+
+  let mut tokens = tokens.into_iter();
+  let (nodes, end) = parse_block(name, &mut tokens)?;
+
+  end.map_or(Ok(nodes), |tag| {
+    Err(syntax(name, format!("unexpected {{% {tag} %}}")))
+  })
+}
+
+/// Parse nodes until the end of the template or an `else`, `endif`, or
+/// `endfor` tag, which is returned.
+fn parse_block<'a>(
+  name: &str,
+  tokens: &mut impl Iterator<Item = Token<'a>>,
+) -> Result<(Vec<Node>, Option<&'a str>), TemplateError> {
+  let mut nodes = Vec::new();
+
+  while let Some(token) = tokens.next() {
+    match token {
+      Token::Text(text) =>
+        if !text.is_empty() {
+          nodes.push(Node::Text(text.to_string()));
+        },
+      Token::Value(expression) => {
+        let (path, raw) = match expression.split_once('|') {
+          Some((path, filter)) if filter.trim() == "raw" => (path.trim(), true),
+          Some((_, filter)) =>
+            return Err(syntax(
+              name,
+              format!("unknown filter {}", filter.trim()),
+            )),
+          None => (expression, false),
+        };
+
+        nodes.push(Node::Value {
+          path: path.to_string(),
+          raw,
+        });
+      }
+      Token::Tag(tag) => {
+        let words = tag.split_whitespace().collect::<Vec<_>>();
+
+        match words.as_slice() {
+          ["else" | "endif" | "endfor"] => return Ok((nodes, Some(tag))),
+          ["if", path] | ["if", "not", path] => {
+            let (then, end) = parse_block(name, tokens)?;
+            let otherwise = match end {
+              Some("else") => {
+                let (otherwise, end) = parse_block(name, tokens)?;
+
+                if end != Some("endif") {
+                  return Err(syntax(name, "unclosed {% if %}".to_string()));
+                }
+
+                otherwise
+              }
+              Some("endif") => Vec::new(),
+              _ => return Err(syntax(name, "unclosed {% if %}".to_string())),
+            };
+
+            nodes.push(Node::If {
+              path: (*path).to_string(),
+              negate: words.len() == 3,
+              then,
+              otherwise,
+            });
+          }
+          ["for", binding, "in", path] => {
+            let (body, end) = parse_block(name, tokens)?;
+
+            if end != Some("endfor") {
+              return Err(syntax(name, "unclosed {% for %}".to_string()));
+            }
+
+            nodes.push(Node::For {
+              binding: (*binding).to_string(),
+              path: (*path).to_string(),
+              body,
+            });
+          }
+          ["include", included] => {
+            let Some(included) = included
+              .strip_prefix('"')
+              .and_then(|included| included.strip_suffix('"'))
+            else {
+              return Err(syntax(name, format!("unquoted include {included}")));
+            };
+
+            nodes.push(Node::Include(included.to_string()));
+          }
+          _ => return Err(syntax(name, format!("unknown tag {{% {tag} %}}"))),
+        }
+      }
+    }
+  }
+
+  Ok((nodes, None))
+}
+
+fn syntax(name: &str, message: String) -> TemplateError {
+  TemplateError::Syntax {
+    name: name.to_string(),
+    message,
+  }
+}
@@ -17,7 +17,11 @@
 
 //! Utilities to make cumbersome tasks simpler
 
-use std::collections::HashMap;
+mod pagination;
+
+use std::{collections::HashMap, fmt::Write};
+
+pub use pagination::{paginate, Pagination};
 
 /// Extract the queries from a URL into a `HashMap`.
 #[must_use]
@@ -31,6 +35,42 @@ pub fn queries_from_url(url: &url::Url) -> HashMap<String, String> {
   queries
 }
 
+/// Percent-decode a URL component, replacing invalid UTF-8 with `U+FFFD`.
+///
+/// A `+` is left as is, as Gemini clients percent-encode spaces.
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(
+///   windmark::utilities::percent_decode("1+1%20%3D%202"),
+///   "1+1 = 2"
+/// );
+/// ```
+#[must_use]
+pub fn percent_decode(component: &str) -> String {
+  percent_encoding::percent_decode_str(component)
+    .decode_utf8_lossy()
+    .into_owned()
+}
+
+/// Convert Markdown into gemtext, as described by
+/// [`gemtext::from_markdown`](crate::gemtext::from_markdown).
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(
+///   windmark::utilities::markdown_to_gemtext("# Hello\n\n- [Home](/)"),
+///   "# Hello\n\n* Home\n=> / Home\n"
+/// );
+/// ```
+#[cfg(feature = "markdown")]
+#[must_use]
+pub fn markdown_to_gemtext(markdown: &str) -> String {
+  crate::gemtext::from_markdown(markdown).to_string()
+}
+
 #[must_use]
 pub fn params_to_hashmap(
   params: &matchit::Params<'_, '_>,
@@ -40,3 +80,72 @@ pub fn params_to_hashmap(
     .map(|(k, v)| (k.to_string(), v.to_string()))
     .collect()
 }
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+  bytes.iter().fold(String::new(), |mut hex, byte| {
+    let _ = write!(hex, "{byte:02x}");
+
+    hex
+  })
+}
+
+/// Format `time` as an RFC 3339 timestamp in UTC, with second precision.
+pub(crate) fn timestamp(time: std::time::SystemTime) -> String {
+  let seconds = time
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |duration| duration.as_secs());
+  let (days, seconds_of_day) = (seconds / 86_400, seconds % 86_400);
+  // Civil-from-days, per Howard Hinnant's date algorithms.
+  let z = days + 719_468;
+  let era = z / 146_097;
+  let day_of_era = z - era * 146_097;
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+    - day_of_era / 146_096)
+    / 365;
+  let day_of_year =
+    day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  };
+  let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+  format!(
+    "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+    seconds_of_day / 3600,
+    seconds_of_day % 3600 / 60,
+    seconds_of_day % 60
+  )
+}
+
+/// Replace the file at `path` with `content` by writing it to a temporary
+/// file beside it and moving that into place, so that a failed or interrupted
+/// write leaves the file as it was.
+#[cfg(any(feature = "client", feature = "file-store"))]
+pub(crate) fn write_atomically(
+  path: &std::path::Path,
+  content: &[u8],
+) -> std::io::Result<()> {
+  let mut temporary = path.as_os_str().to_os_string();
+
+  temporary.push(".tmp");
+
+  let temporary = std::path::PathBuf::from(temporary);
+  let mut file = std::fs::File::create(&temporary)?;
+
+  std::io::Write::write_all(&mut file, content)?;
+  file.sync_all()?;
+  std::fs::rename(temporary, path)
+}
+
+/// Escape the characters of `text` which are significant in HTML and XML.
+pub(crate) fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
@@ -0,0 +1,222 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+use std::ops::Range;
+
+use crate::{context::Query, gemtext::Document};
+
+/// The page of a listing which is requested by the `page` query parameter of
+/// a URL, counting from one.
+///
+/// Requests without a valid `page` are given the first page, and requests
+/// past the last page are given the last.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::utilities::Pagination;
+///
+/// let url = url::Url::parse("gemini://fuwn.me/posts?tag=rust&page=2").unwrap();
+/// let posts = (1..=25)
+///   .map(|post| format!("Post {post}"))
+///   .collect::<Vec<_>>();
+/// let pagination = Pagination::from_url(&url, posts.len(), 10);
+///
+/// assert_eq!(pagination.items(&posts).first().unwrap(), "Post 11");
+/// assert_eq!(pagination.pages(), 3);
+/// assert_eq!(
+///   pagination.next_url(&url).as_deref(),
+///   Some("/posts?tag=rust&page=3")
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pagination {
+  page:     usize,
+  per_page: usize,
+  total:    usize,
+}
+
+impl Pagination {
+  /// Paginate `total` entries, `per_page` to a page.
+  ///
+  /// # Panics
+  ///
+  /// if `per_page` is zero.
+  #[must_use]
+  pub fn new(page: usize, per_page: usize, total: usize) -> Self {
+    assert!(per_page > 0, "a page must hold at least one entry");
+
+    let pagination = Self {
+      page: 1,
+      per_page,
+      total,
+    };
+
+    Self {
+      page: page.clamp(1, pagination.pages()),
+      ..pagination
+    }
+  }
+
+  /// Paginate `total` entries, `per_page` to a page, at the page requested
+  /// by `url`.
+  ///
+  /// # Panics
+  ///
+  /// if `per_page` is zero.
+  #[must_use]
+  pub fn from_url(url: &url::Url, total: usize, per_page: usize) -> Self {
+    Self::new(
+      Query::from_url(url)
+        .get("page")
+        .and_then(|page| page.parse().ok())
+        .unwrap_or(1),
+      per_page,
+      total,
+    )
+  }
+
+  /// The current page, counting from one.
+  #[must_use]
+  pub const fn page(&self) -> usize { self.page }
+
+  /// The number of pages, which is at least one.
+  #[must_use]
+  pub const fn pages(&self) -> usize {
+    if self.total == 0 {
+      1
+    } else {
+      self.total.div_ceil(self.per_page)
+    }
+  }
+
+  /// The indices of the entries on the current page.
+  #[must_use]
+  pub fn range(&self) -> Range<usize> {
+    let start = (self.page - 1) * self.per_page;
+
+    start.min(self.total)..(start + self.per_page).min(self.total)
+  }
+
+  /// The entries of `items` on the current page.
+  #[must_use]
+  pub fn items<'a, T>(&self, items: &'a [T]) -> &'a [T] {
+    let range = self.range();
+
+    &items[range.start.min(items.len())..range.end.min(items.len())]
+  }
+
+  /// A link to the previous page, relative to `url`, if there is one.
+  #[must_use]
+  pub fn previous_url(&self, url: &url::Url) -> Option<String> {
+    (self.page > 1).then(|| link(url, self.page - 1))
+  }
+
+  /// A link to the next page, relative to `url`, if there is one.
+  #[must_use]
+  pub fn next_url(&self, url: &url::Url) -> Option<String> {
+    (self.page < self.pages()).then(|| link(url, self.page + 1))
+  }
+
+  /// Add link lines to the previous and next pages, relative to `url`, to
+  /// `document`, if there is more than one page.
+  pub fn links(&self, document: &mut Document, url: &url::Url) {
+    if self.pages() == 1 {
+      return;
+    }
+
+    document
+      .blank()
+      .text(format!("Page {} of {}", self.page, self.pages()));
+
+    if let Some(previous) = self.previous_url(url) {
+      document.link(previous, "Previous page");
+    }
+
+    if let Some(next) = self.next_url(url) {
+      document.link(next, "Next page");
+    }
+  }
+}
+
+/// Render the gemtext `entries` on the page requested by `url`, `per_page`
+/// to a page, followed by links to the previous and next pages.
+///
+/// # Examples
+///
+/// ```rust
+/// use windmark::{context::RouteContext, response::Response};
+///
+/// windmark::router::Router::new().mount("/posts", |context: RouteContext| {
+///   let posts = (1..=100)
+///     .map(|post| format!("=> /posts/{post} Post {post}"))
+///     .collect::<Vec<_>>();
+///
+///   Response::success(windmark::utilities::paginate(&posts, 10, &context.url))
+/// });
+/// ```
+///
+/// # Panics
+///
+/// if `per_page` is zero.
+#[must_use]
+pub fn paginate(
+  entries: &[impl AsRef<str>],
+  per_page: usize,
+  url: &url::Url,
+) -> String {
+  let pagination = Pagination::from_url(url, entries.len(), per_page);
+  let mut document = Document::new();
+
+  pagination.links(&mut document, url);
+
+  let mut page = pagination
+    .items(entries)
+    .iter()
+    .map(AsRef::as_ref)
+    .collect::<Vec<_>>()
+    .join("\n");
+
+  page.push('\n');
+  page.push_str(&document.to_string());
+
+  page
+}
+
+/// The path and query of `url`, with its `page` query parameter set to
+/// `page`, or removed for the first page.
+fn link(url: &url::Url, page: usize) -> String {
+  let mut pairs = url
+    .query()
+    .unwrap_or_default()
+    .split('&')
+    .filter(|pair| {
+      !pair.is_empty()
+        && pair.split_once('=').map_or(*pair, |(key, _)| key) != "page"
+    })
+    .map(str::to_string)
+    .collect::<Vec<_>>();
+
+  if page > 1 {
+    pairs.push(format!("page={page}"));
+  }
+
+  if pairs.is_empty() {
+    url.path().to_string()
+  } else {
+    format!("{}?{}", url.path(), pairs.join("&"))
+  }
+}
@@ -0,0 +1,66 @@
+// This file is part of Windmark <https://github.com/gemrest/windmark>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2023 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+//! Every `Response` constructor which only takes its content has a matching
+//! synchronous and asynchronous macro which responds with it.
+
+#![cfg(feature = "response-macros")]
+
+use std::future::Future;
+
+use windmark::{context::RouteContext, response::Response};
+
+fn sync_handler(_: fn(RouteContext) -> Response) {}
+
+fn async_handler<R: Future<Output = Response>>(_: fn(RouteContext) -> R) {}
+
+macro_rules! assert_macros {
+  ($($name:ident = $status:expr),* $(,)?) => {
+    ::paste::paste! {
+      $(
+        sync_handler(windmark::$name!("content"));
+        sync_handler(windmark::$name!(_context, "content"));
+        async_handler(windmark::[< $name _async >]!("content"));
+        async_handler(windmark::[< $name _async >]!(_context, "content"));
+        assert_eq!(Response::$name("content").status, $status);
+      )*
+    }
+  };
+}
+
+#[test]
+fn every_content_response_has_macros() {
+  assert_macros! {
+    input = 10,
+    sensitive_input = 11,
+    success = 20,
+    temporary_redirect = 30,
+    permanent_redirect = 31,
+    temporary_failure = 40,
+    server_unavailable = 41,
+    cgi_error = 42,
+    proxy_error = 43,
+    slow_down = 44,
+    permanent_failure = 50,
+    not_found = 51,
+    gone = 52,
+    proxy_refused = 53,
+    bad_request = 59,
+    client_certificate_required = 60,
+    certificate_not_authorised = 61,
+    certificate_not_valid = 62,
+  }
+}